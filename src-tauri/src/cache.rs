@@ -0,0 +1,464 @@
+/*!
+ * 图片缓存维护（cache_image_to_path 写入的缓存目录）
+ *
+ * 缓存目录下的 manifest.json 记录每个文件的来源 URL、标签（如会话 id）和大小，
+ * 用于按标签统计占用和清理。清单通过临时文件 + 重命名整体替换，
+ * 删除时先写清单再删文件，崩溃最多留下没有清单记录的文件（按未标记统计），
+ * 不会留下指向不存在文件的记录。按标签清理可撤销，撤销时文件和清单记录一起恢复。
+ */
+
+use crate::AppState;
+use crate::ephemeral;
+use crate::events;
+use crate::imaging;
+use crate::undo;
+use rayon::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, State};
+
+/// 完整解码的默认抽样比例
+const DEFAULT_DECODE_SAMPLE: f32 = 0.1;
+const MANIFEST_FILE: &str = "manifest.json";
+/// 没有标签（或不在清单中）的文件归入该分组
+pub const UNTAGGED: &str = "(untagged)";
+
+/// 清单中的一条缓存记录
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ManifestEntry {
+    pub url: String,
+    pub tag: Option<String>,
+    pub size: u64,
+    pub cached_at: u64,
+}
+
+/// 缓存清单（文件名 -> 记录）
+#[derive(Serialize, Deserialize, Default)]
+pub struct Manifest {
+    entries: BTreeMap<String, ManifestEntry>,
+}
+
+/// 按标签统计的缓存占用
+#[derive(Serialize)]
+pub struct TagUsage {
+    pub tag: String,
+    pub bytes: u64,
+    pub files: usize,
+}
+
+/// "cache:verify" 进度事件负载
+#[derive(Serialize, Clone, JsonSchema)]
+pub struct CacheVerifyProgress {
+    pub checked: usize,
+    pub total: usize,
+    pub corrupt: usize,
+}
+
+/// 缓存校验结果
+#[derive(Serialize)]
+pub struct CacheVerifyReport {
+    pub ok: usize,
+    pub corrupt: usize,
+    pub removed: usize,
+    pub corrupt_files: Vec<String>,
+}
+
+/// 缓存目录（临时模式下重定向到沙箱）
+pub fn cache_dir(state: &AppState, cache_base: &str) -> PathBuf {
+    ephemeral::redirect(state, "cache", PathBuf::from(cache_base))
+}
+
+/// 缓存目录下的所有缓存文件（不含清单）
+pub fn cache_files(state: &AppState, cache_base: &str) -> Vec<PathBuf> {
+    match fs::read_dir(cache_dir(state, cache_base)) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.is_file() && !is_manifest(p))
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn is_manifest(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.starts_with(MANIFEST_FILE))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// 串行化对清单的读改写（不能跨 await 持有）
+fn lock(state: &AppState) -> Result<std::sync::MutexGuard<'_, ()>, String> {
+    state
+        .cache_lock
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))
+}
+
+fn load_manifest(dir: &Path) -> Manifest {
+    fs::read(dir.join(MANIFEST_FILE))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// 写入临时文件后重命名，避免崩溃时留下半截清单
+fn save_manifest(dir: &Path, manifest: &Manifest) -> Result<(), String> {
+    let bytes = serde_json::to_vec(manifest).map_err(|e| format!("serialize error: {}", e))?;
+    let tmp = dir.join(format!("{}.tmp", MANIFEST_FILE));
+    fs::write(&tmp, bytes).map_err(|e| format!("write error: {}", e))?;
+    fs::rename(&tmp, dir.join(MANIFEST_FILE)).map_err(|e| format!("rename error: {}", e))
+}
+
+/**
+ * 记录新写入的缓存文件
+ * 文件已在清单中时只在原记录没有标签时补上标签
+ */
+pub fn record(
+    state: &AppState,
+    dir: &Path,
+    filename: &str,
+    url: &str,
+    tag: Option<String>,
+    size: u64,
+) -> Result<(), String> {
+    let _guard = lock(state)?;
+    let mut manifest = load_manifest(dir);
+    match manifest.entries.get_mut(filename) {
+        Some(entry) if entry.tag.is_some() || tag.is_none() => return Ok(()),
+        Some(entry) => entry.tag = tag,
+        None => {
+            manifest.entries.insert(
+                filename.to_string(),
+                ManifestEntry {
+                    url: url.to_string(),
+                    tag,
+                    size,
+                    cached_at: now_secs(),
+                },
+            );
+        }
+    }
+    save_manifest(dir, &manifest)
+}
+
+/// 从清单中删除记录
+fn forget(state: &AppState, dir: &Path, filenames: &[String]) -> Result<(), String> {
+    let _guard = lock(state)?;
+    let mut manifest = load_manifest(dir);
+    let before = manifest.entries.len();
+    for name in filenames {
+        manifest.entries.remove(name);
+    }
+    if manifest.entries.len() == before {
+        return Ok(());
+    }
+    save_manifest(dir, &manifest)
+}
+
+pub fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// 文件所属的标签（不在清单中或未标记时为 UNTAGGED）
+fn tag_of<'a>(manifest: &'a Manifest, path: &Path) -> &'a str {
+    manifest
+        .entries
+        .get(&file_name(path))
+        .and_then(|e| e.tag.as_deref())
+        .unwrap_or(UNTAGGED)
+}
+
+/**
+ * 按标签统计缓存占用（如"哪个会话占用了最多空间"），按字节数降序
+ * 以实际存在的文件为准，清单中已不存在的文件不计入
+ */
+#[tauri::command]
+pub fn cache_usage_by_tag(
+    state: State<'_, AppState>,
+    cache_base: String,
+) -> Result<Vec<TagUsage>, String> {
+    let dir = cache_dir(&state, &cache_base);
+    let manifest = {
+        let _guard = lock(&state)?;
+        load_manifest(&dir)
+    };
+
+    let mut usage: HashMap<&str, (u64, usize)> = HashMap::new();
+    for path in cache_files(&state, &cache_base) {
+        let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        let entry = usage.entry(tag_of(&manifest, &path)).or_default();
+        entry.0 += size;
+        entry.1 += 1;
+    }
+
+    let mut result: Vec<TagUsage> = usage
+        .into_iter()
+        .map(|(tag, (bytes, files))| TagUsage {
+            tag: tag.to_string(),
+            bytes,
+            files,
+        })
+        .collect();
+    result.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.tag.cmp(&b.tag)));
+    Ok(result)
+}
+
+/**
+ * 清理某个标签下的全部缓存文件（可撤销），tag 为 "(untagged)" 时清理未标记的文件
+ * 先更新清单再把文件移入暂存区并返回 undo_token，撤销时连同清单记录一起恢复；
+ * 文件过大时同 clear_image_cache，需 confirm: true 才会永久删除
+ */
+#[tauri::command]
+pub fn clear_cache_by_tag(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    cache_base: String,
+    tag: String,
+    confirm: Option<bool>,
+) -> Result<undo::DestructiveResult, String> {
+    clear_matching(
+        &app,
+        &state,
+        &cache_base,
+        "clear_cache_by_tag",
+        |manifest, path| tag_of(manifest, path) == tag,
+        confirm.unwrap_or(false),
+    )
+}
+
+/// 清空全部缓存文件（clear_image_cache），清单记录的处理同 clear_cache_by_tag
+pub fn clear_all(
+    app: &AppHandle,
+    state: &AppState,
+    cache_base: &str,
+    confirm: bool,
+) -> Result<undo::DestructiveResult, String> {
+    clear_matching(
+        app,
+        state,
+        cache_base,
+        "clear_image_cache",
+        |_, _| true,
+        confirm,
+    )
+}
+
+/// 把满足条件的缓存文件及其清单记录移入暂存区，撤销时一起恢复
+fn clear_matching(
+    app: &AppHandle,
+    state: &AppState,
+    cache_base: &str,
+    label: &str,
+    matches: impl Fn(&Manifest, &Path) -> bool,
+    confirm: bool,
+) -> Result<undo::DestructiveResult, String> {
+    let dir = cache_dir(state, cache_base);
+    let _guard = lock(state)?;
+    let mut manifest = load_manifest(&dir);
+    let targets: Vec<PathBuf> = cache_files(state, cache_base)
+        .into_iter()
+        .filter(|p| matches(&manifest, p))
+        .collect();
+    let removed: Vec<(String, ManifestEntry)> = targets
+        .iter()
+        .filter_map(|path| manifest.entries.remove_entry(&file_name(path)))
+        .collect();
+    // 同时去掉文件已不存在的记录
+    manifest.entries.retain(|name, _| dir.join(name).exists());
+    save_manifest(&dir, &manifest)?;
+
+    let restore_dir = dir.clone();
+    let restore_entries = removed.clone();
+    let restore: undo::RestoreHook = Box::new(move |state: &AppState| {
+        let _guard = lock(state)?;
+        let mut manifest = load_manifest(&restore_dir);
+        for (name, entry) in restore_entries {
+            if restore_dir.join(&name).exists() {
+                manifest.entries.insert(name, entry);
+            }
+        }
+        save_manifest(&restore_dir, &manifest)
+    });
+    let result = undo::stage_with_restore(app, state, label, targets, confirm, Some(restore))?;
+    if result.requires_confirm {
+        // 文件没有被删除，放回清单记录
+        manifest.entries.extend(removed);
+        save_manifest(&dir, &manifest)?;
+    }
+    Ok(result)
+}
+
+/// 检查单个缓存文件：非空且文件头是可识别的图片格式，full 为 true 时完整解码
+fn check_file(path: &Path, full: bool) -> Result<(), String> {
+    let mut header = [0u8; 32];
+    let n = File::open(path)
+        .and_then(|mut f| f.read(&mut header))
+        .map_err(|e| e.to_string())?;
+    if n == 0 {
+        return Err("empty file".into());
+    }
+    image::guess_format(&header[..n]).map_err(|e| e.to_string())?;
+    if full {
+        image::open(path).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/**
+ * 校验图片缓存
+ * 检查每个文件的大小和文件头，并按 decode_sample 比例（0-1，默认 0.1）抽样完整解码
+ * repair 为 true 时删除损坏的文件及其清单记录，下次请求时会重新下载
+ * 校验在后台线程进行，通过 "cache:verify" 事件上报进度
+ */
+#[tauri::command]
+pub async fn verify_image_cache(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    cache_base: String,
+    repair: bool,
+    decode_sample: Option<f32>,
+) -> Result<CacheVerifyReport, String> {
+    let dir = cache_dir(&state, &cache_base);
+    let files = cache_files(&state, &cache_base);
+    let sample = decode_sample
+        .unwrap_or(DEFAULT_DECODE_SAMPLE)
+        .clamp(0.0, 1.0);
+    // 每隔 stride 个文件完整解码一次
+    let stride = if sample > 0.0 {
+        (1.0 / sample).round().max(1.0) as usize
+    } else {
+        usize::MAX
+    };
+
+    let report = tokio::task::spawn_blocking(move || {
+        let total = files.len();
+        let mut report = CacheVerifyReport {
+            ok: 0,
+            corrupt: 0,
+            removed: 0,
+            corrupt_files: Vec::new(),
+        };
+        let mut last_emit = Instant::now();
+
+        for (i, path) in files.iter().enumerate() {
+            match check_file(path, i % stride == 0) {
+                Ok(_) => report.ok += 1,
+                Err(e) => {
+                    println!("[cache] corrupt {}: {}", path.display(), e);
+                    report.corrupt += 1;
+                    report
+                        .corrupt_files
+                        .push(path.to_string_lossy().into_owned());
+                    if repair {
+                        match fs::remove_file(path) {
+                            Ok(_) => report.removed += 1,
+                            Err(e) => eprintln!("[cache] remove {} failed: {}", path.display(), e),
+                        }
+                    }
+                }
+            }
+
+            if last_emit.elapsed() >= Duration::from_millis(200) || i + 1 == total {
+                last_emit = Instant::now();
+                let _ = events::emit_event(
+                    &app,
+                    events::Event::CacheVerify(CacheVerifyProgress {
+                        checked: i + 1,
+                        total,
+                        corrupt: report.corrupt,
+                    }),
+                );
+            }
+        }
+        report
+    })
+    .await
+    .map_err(|e| format!("verify task error: {}", e))?;
+
+    if repair && report.removed > 0 {
+        let removed: Vec<String> = report
+            .corrupt_files
+            .iter()
+            .map(Path::new)
+            .filter(|p| !p.exists())
+            .map(file_name)
+            .collect();
+        forget(&state, &dir, &removed)?;
+    }
+    Ok(report)
+}
+
+/// find_similar_cached_images 的结果项
+#[derive(Serialize)]
+pub struct SimilarImage {
+    pub path: String,
+    pub hash: String,
+    /// 与参考哈希的汉明距离
+    pub distance: u32,
+}
+
+/// 参与相似图片查找的扩展名
+fn is_hashable(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| matches!(e.to_ascii_lowercase().as_str(), "png" | "jpg" | "jpeg"))
+}
+
+/**
+ * 在缓存目录中查找与 reference_hash 相似的图片（如不同 URL 下载的同一张图），按距离从近到远排列
+ * 扫描 .png / .jpg 文件并计算感知哈希，返回汉明距离不超过 max_distance 的文件；无法解码的文件跳过
+ * algorithm 同 compute_image_perceptual_hash，须与 reference_hash 使用的算法一致
+ */
+#[tauri::command]
+pub async fn find_similar_cached_images(
+    state: State<'_, AppState>,
+    cache_base: String,
+    reference_hash: String,
+    max_distance: u32,
+    algorithm: Option<String>,
+) -> Result<Vec<SimilarImage>, String> {
+    let reference = imaging::parse_hash(&reference_hash)?;
+    let hash_of = imaging::hash_algorithm(algorithm.as_deref())?;
+    let files: Vec<PathBuf> = cache_files(&state, &cache_base)
+        .into_iter()
+        .filter(|p| is_hashable(p))
+        .collect();
+
+    tokio::task::spawn_blocking(move || {
+        let mut similar: Vec<SimilarImage> = files
+            .par_iter()
+            .filter_map(|path| {
+                let img = image::open(path).ok()?;
+                let hash = hash_of(&img);
+                let distance = (hash ^ reference).count_ones();
+                (distance <= max_distance).then(|| SimilarImage {
+                    path: path.to_string_lossy().into_owned(),
+                    hash: format!("{:016x}", hash),
+                    distance,
+                })
+            })
+            .collect();
+        similar.sort_by(|a, b| {
+            a.distance
+                .cmp(&b.distance)
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        similar
+    })
+    .await
+    .map_err(|e| format!("hash task error: {}", e))
+}
@@ -0,0 +1,260 @@
+/*!
+ * 截图后端探测与降级
+ *
+ * screenshots 在 Windows 使用 GDI、macOS 使用 CoreGraphics、Linux 使用 X11（Wayland 下走 D-Bus）。
+ * Wayland 下没有可用接口时，单次截图命令退回 xdg-desktop-portal 的 Screenshot 接口
+ * （portal 把整个桌面保存为文件并返回 file URI，这里读取后删除）。
+ * 仍然无法截图时返回 CaptureUnsupported，附带可直接展示给用户的处理建议。
+ */
+
+use crate::error::CommandError;
+use image::RgbaImage;
+use screenshots::Screen;
+use serde::Serialize;
+
+/// 截图后端能力
+#[derive(Serialize, Clone, Debug)]
+pub struct CaptureBackendInfo {
+    /// "gdi" | "core-graphics" | "x11" | "wayland" | "xdg-desktop-portal" | "none"
+    pub backend: String,
+    pub available: bool,
+    pub reason: Option<String>,
+    pub hint: Option<String>,
+}
+
+/// 当前环境无法截图（作为 CommandError::CaptureUnsupported 返回）
+#[derive(Clone, Debug)]
+pub struct CaptureUnsupported {
+    pub reason: String,
+    pub hint: String,
+}
+
+/// 截图已完成但写入剪贴板失败（作为 CommandError::ClipboardWriteFailed 返回）
+#[derive(Clone, Debug)]
+pub struct ClipboardWriteFailed {
+    pub reason: String,
+    pub hint: String,
+}
+
+fn is_wayland() -> bool {
+    cfg!(target_os = "linux")
+        && (std::env::var_os("WAYLAND_DISPLAY").is_some()
+            || std::env::var("XDG_SESSION_TYPE").is_ok_and(|t| t == "wayland"))
+}
+
+fn native_backend() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "gdi"
+    } else if cfg!(target_os = "macos") {
+        "core-graphics"
+    } else if is_wayland() {
+        "wayland"
+    } else if cfg!(target_os = "linux") {
+        "x11"
+    } else {
+        "none"
+    }
+}
+
+/**
+ * 是否可以在多个线程上同时截图
+ * Wayland 下 screenshots 通过 D-Bus 截取整个桌面再裁剪，并发请求会互相阻塞甚至被拒绝，只能串行
+ */
+pub fn supports_parallel_capture() -> bool {
+    !is_wayland()
+}
+
+/// 根据平台把截图后端的原始错误转换为对用户有意义的说明
+fn unsupported(raw: &str) -> CaptureUnsupported {
+    if cfg!(target_os = "macos") && !mac::has_screen_capture_access() {
+        return CaptureUnsupported {
+            reason: "screen recording permission denied".into(),
+            hint: "Allow this app in System Settings > Privacy & Security > Screen Recording, then restart it".into(),
+        };
+    }
+    if is_wayland() {
+        return CaptureUnsupported {
+            reason: format!("no screenshot interface available on Wayland: {}", raw),
+            hint: "Install xdg-desktop-portal with a backend for your desktop (gnome, kde or wlr) and log in again".into(),
+        };
+    }
+    CaptureUnsupported {
+        reason: raw.to_string(),
+        hint: "Check that a graphical session is running and the app has access to the display"
+            .into(),
+    }
+}
+
+/**
+ * 截图前的权限检查
+ * macOS 未授予屏幕录制权限时截图不会报错，只会得到桌面壁纸，因此在截图前返回 CaptureUnsupported
+ */
+pub fn preflight() -> Result<(), CommandError> {
+    if cfg!(target_os = "macos") && !mac::has_screen_capture_access() {
+        return Err(CommandError::CaptureUnsupported(unsupported(
+            "screen recording permission denied",
+        )));
+    }
+    Ok(())
+}
+
+/// 剪贴板写入失败的说明（Wayland 下合成器可能拒绝未获得焦点的窗口写剪贴板）
+pub fn clipboard_write_failed(raw: &str) -> CommandError {
+    let hint = if is_wayland() {
+        "The compositor may reject clipboard writes from unfocused windows; focus the app window and try again"
+    } else {
+        "Another application may be holding the clipboard; try again"
+    };
+    CommandError::ClipboardWriteFailed(ClipboardWriteFailed {
+        reason: format!("failed to write image to clipboard: {}", raw),
+        hint: hint.into(),
+    })
+}
+
+/**
+ * 截图后端出错时的降级：Wayland 下通过 portal 截取整个桌面交给 with_image 处理，
+ * 否则（或 portal 也失败时）返回 CaptureUnsupported
+ */
+pub fn fallback<T>(
+    raw: String,
+    with_image: impl FnOnce(RgbaImage) -> Result<T, String>,
+) -> Result<T, CommandError> {
+    eprintln!("[capture] backend error: {}", raw);
+    if !is_wayland() {
+        return Err(CommandError::CaptureUnsupported(unsupported(&raw)));
+    }
+    match tauri::async_runtime::block_on(portal::screenshot()) {
+        Ok(image) => with_image(image).map_err(CommandError::Other),
+        Err(portal_error) => {
+            let raw = format!("{}; portal: {}", raw, portal_error);
+            Err(CommandError::CaptureUnsupported(unsupported(&raw)))
+        }
+    }
+}
+
+/// 用 screenshots 截取主屏左上角 1x1 像素，确认后端能实际工作
+fn probe_native() -> Result<(), String> {
+    let screens = Screen::all().map_err(|e| e.to_string())?;
+    let screen = screens
+        .iter()
+        .find(|s| s.display_info.is_primary)
+        .or(screens.first())
+        .ok_or("no screens found")?;
+    screen
+        .capture_area(0, 0, 1, 1)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/**
+ * 探测当前可用的截图后端（启动时调用，用于提前提示用户）
+ */
+#[tauri::command]
+pub async fn get_capture_backend_info() -> CaptureBackendInfo {
+    let native = tokio::task::spawn_blocking(probe_native)
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    let raw = match native {
+        Ok(_) => {
+            return CaptureBackendInfo {
+                backend: native_backend().into(),
+                available: true,
+                reason: None,
+                hint: None,
+            };
+        }
+        Err(raw) => raw,
+    };
+
+    if is_wayland() && portal::available().await {
+        return CaptureBackendInfo {
+            backend: "xdg-desktop-portal".into(),
+            available: true,
+            reason: Some(raw),
+            hint: None,
+        };
+    }
+    let u = unsupported(&raw);
+    CaptureBackendInfo {
+        backend: native_backend().into(),
+        available: false,
+        reason: Some(u.reason),
+        hint: Some(u.hint),
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod portal {
+    use ashpd::desktop::screenshot::Screenshot;
+    use image::RgbaImage;
+
+    /// 非交互地截取整个桌面
+    pub async fn screenshot() -> Result<RgbaImage, String> {
+        let response = Screenshot::request()
+            .interactive(false)
+            .modal(false)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?
+            .response()
+            .map_err(|e| e.to_string())?;
+        let path = response
+            .uri()
+            .to_file_path()
+            .map_err(|_| format!("unexpected portal uri: {}", response.uri()))?;
+        let image = image::open(&path).map_err(|e| format!("decode error: {}", e));
+        // portal 会把截图保存到用户目录，读取后删除
+        let _ = std::fs::remove_file(&path);
+        Ok(image?.to_rgba8())
+    }
+
+    /// 会话总线上是否有 Screenshot portal
+    pub async fn available() -> bool {
+        let Ok(connection) = ashpd::zbus::Connection::session().await else {
+            return false;
+        };
+        let proxy = ashpd::zbus::Proxy::new(
+            &connection,
+            "org.freedesktop.portal.Desktop",
+            "/org/freedesktop/portal/desktop",
+            "org.freedesktop.portal.Screenshot",
+        )
+        .await;
+        match proxy {
+            Ok(proxy) => proxy.get_property::<u32>("version").await.is_ok(),
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod portal {
+    use image::RgbaImage;
+
+    pub async fn screenshot() -> Result<RgbaImage, String> {
+        Err("xdg-desktop-portal is only available on Linux".into())
+    }
+
+    pub async fn available() -> bool {
+        false
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod mac {
+    #[link(name = "CoreGraphics", kind = "framework")]
+    unsafe extern "C" {
+        fn CGPreflightScreenCaptureAccess() -> bool;
+    }
+
+    pub fn has_screen_capture_access() -> bool {
+        unsafe { CGPreflightScreenCaptureAccess() }
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod mac {
+    pub fn has_screen_capture_access() -> bool {
+        true
+    }
+}
@@ -0,0 +1,375 @@
+/*!
+ * 保留的截图像素
+ *
+ * 截图命令传 keep_capture: true 时，把截到的原始像素（屏幕物理分辨率，不受 max_width /
+ * max_height 影响）登记在这里并返回 capture_id。前端冻结画面、用户拖出选区后用 crop_capture
+ * 从同一份像素裁剪，不再重新截图，避免屏幕内容在此期间发生变化。
+ * 最多保留 MAX_KEPT_CAPTURES 张，超出时丢弃最早的一张；用完后应调用 release_capture 释放。
+ */
+
+use crate::AppState;
+use crate::commands::{self, CaptureFormat, encode_rgba};
+use crate::error::CommandError;
+use image::RgbaImage;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::{AppHandle, Manager, State};
+
+/// 最多保留的截图数（一张 4K 屏幕约 33 MB）
+const MAX_KEPT_CAPTURES: usize = 4;
+
+/// 比较截图时各通道差值都不超过该值的像素视为相同（容忍有损压缩的噪声）
+const PIXEL_TOLERANCE: u8 = 8;
+
+static CAPTURE_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// 保留的截图：(capture_id, 像素)，按登记顺序排列
+#[derive(Default)]
+pub struct KeptCaptures {
+    entries: VecDeque<(String, Arc<RgbaImage>)>,
+}
+
+impl KeptCaptures {
+    /// 登记一张截图，返回 capture_id
+    pub fn insert(&mut self, pixels: RgbaImage) -> String {
+        let capture_id = format!("capture-{}", CAPTURE_SEQ.fetch_add(1, Ordering::Relaxed));
+        if self.entries.len() >= MAX_KEPT_CAPTURES {
+            self.entries.pop_front();
+        }
+        self.entries
+            .push_back((capture_id.clone(), Arc::new(pixels)));
+        capture_id
+    }
+
+    pub fn get(&self, capture_id: &str) -> Option<Arc<RgbaImage>> {
+        self.entries
+            .iter()
+            .find(|(id, _)| id == capture_id)
+            .map(|(_, pixels)| pixels.clone())
+    }
+
+    fn remove(&mut self, capture_id: &str) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|(id, _)| id != capture_id);
+        self.entries.len() != before
+    }
+}
+
+/// 取出保留的截图像素，不存在时返回 NotFound
+fn kept_pixels(state: &AppState, capture_id: &str) -> Result<Arc<RgbaImage>, CommandError> {
+    state
+        .kept_captures
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?
+        .get(capture_id)
+        .ok_or_else(|| CommandError::NotFound(format!("capture {} not found", capture_id)))
+}
+
+/// 把选区限制在图片范围内；限制后面积为 0 时返回 None
+fn clamp_rect(
+    (image_w, image_h): (u32, u32),
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+) -> Option<(u32, u32, u32, u32)> {
+    let left = (x as i64).clamp(0, image_w as i64);
+    let top = (y as i64).clamp(0, image_h as i64);
+    let right = (x as i64 + width as i64).clamp(0, image_w as i64);
+    let bottom = (y as i64 + height as i64).clamp(0, image_h as i64);
+    if right <= left || bottom <= top {
+        return None;
+    }
+    Some((
+        left as u32,
+        top as u32,
+        (right - left) as u32,
+        (bottom - top) as u32,
+    ))
+}
+
+/**
+ * 从保留的截图中裁剪选区，返回编码后的图片字节
+ * x / y / width / height: 截图像素坐标（物理像素，即逻辑坐标乘以截图的 scale_factor），
+ * 超出截图的部分被裁掉，完全在截图外时返回错误
+ * format: "png"（默认）| "jpeg" | "webp"
+ * capture_id 不存在（未保留、已释放或已被挤出）时返回 kind 为 "notFound" 的错误
+ */
+#[tauri::command]
+pub fn crop_capture(
+    state: State<'_, AppState>,
+    capture_id: String,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    format: Option<String>,
+) -> Result<Vec<u8>, CommandError> {
+    let format = CaptureFormat::parse(format.as_deref())?;
+    let pixels = kept_pixels(&state, &capture_id)?;
+    let (x, y, width, height) = clamp_rect(pixels.dimensions(), x, y, width, height)
+        .ok_or("crop rectangle is outside of the capture")?;
+    let cropped = image::imageops::crop_imm(pixels.as_ref(), x, y, width, height).to_image();
+    Ok(encode_rgba(&cropped, format, None)?)
+}
+
+/**
+ * 释放保留的截图，返回 capture_id 是否存在
+ */
+#[tauri::command]
+pub fn release_capture(state: State<'_, AppState>, capture_id: String) -> Result<bool, String> {
+    Ok(state
+        .kept_captures
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?
+        .remove(&capture_id))
+}
+
+/// 参与比较的图片：保留的截图、图片字节或图片文件路径
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum CaptureRef {
+    Capture { capture_id: String },
+    Bytes { data: Vec<u8> },
+    File { path: String },
+}
+
+impl CaptureRef {
+    /// 取出像素；图片字节和文件需要解码，应在阻塞线程上调用
+    fn resolve(self, state: &AppState) -> Result<Arc<RgbaImage>, CommandError> {
+        let image = match self {
+            CaptureRef::Capture { capture_id } => return kept_pixels(state, &capture_id),
+            CaptureRef::Bytes { data } => image::load_from_memory(&data)?,
+            CaptureRef::File { path } => image::load_from_memory(&fs::read(&path)?)?,
+        };
+        Ok(Arc::new(image.into_rgba8()))
+    }
+}
+
+/// 变化区域（图片像素坐标）
+#[derive(Serialize, Debug, PartialEq)]
+pub struct DiffBounds {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// 两张截图的比较结果
+#[derive(Serialize, Debug)]
+pub struct CaptureDiff {
+    /// 不同像素占总像素的比例 0.0 - 1.0
+    pub changed_fraction: f64,
+    /// changed_fraction 是否超过 threshold
+    pub changed: bool,
+    /// 包含所有不同像素的最小矩形，完全相同时为 None
+    pub bounds: Option<DiffBounds>,
+}
+
+/// 逐像素比较两张尺寸相同的图片（按行并行）
+fn diff(a: &RgbaImage, b: &RgbaImage, threshold: f32) -> Result<CaptureDiff, CommandError> {
+    if a.dimensions() != b.dimensions() {
+        return Err(format!(
+            "capture sizes differ: {}x{} vs {}x{}",
+            a.width(),
+            a.height(),
+            b.width(),
+            b.height()
+        )
+        .into());
+    }
+    let (width, height) = a.dimensions();
+    if width == 0 || height == 0 {
+        return Ok(CaptureDiff {
+            changed_fraction: 0.0,
+            changed: false,
+            bounds: None,
+        });
+    }
+    let row_len = width as usize * 4;
+    // 每行：(不同的像素数, 最左列, 最右列)
+    let rows: Vec<(u64, u32, u32)> = a
+        .as_raw()
+        .par_chunks(row_len)
+        .zip(b.as_raw().par_chunks(row_len))
+        .map(|(row_a, row_b)| {
+            let (mut count, mut left, mut right) = (0u64, u32::MAX, 0u32);
+            for (x, (pa, pb)) in row_a.chunks_exact(4).zip(row_b.chunks_exact(4)).enumerate() {
+                if pa
+                    .iter()
+                    .zip(pb)
+                    .any(|(ca, cb)| ca.abs_diff(*cb) > PIXEL_TOLERANCE)
+                {
+                    count += 1;
+                    left = left.min(x as u32);
+                    right = x as u32;
+                }
+            }
+            (count, left, right)
+        })
+        .collect();
+
+    let mut changed_pixels = 0u64;
+    // (left, top, right, bottom)，含边界
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+    for (y, &(count, left, right)) in rows.iter().enumerate() {
+        if count == 0 {
+            continue;
+        }
+        changed_pixels += count;
+        let y = y as u32;
+        bounds = Some(match bounds {
+            Some((l, t, r, _)) => (l.min(left), t, r.max(right), y),
+            None => (left, y, right, y),
+        });
+    }
+    let changed_fraction = changed_pixels as f64 / (width as u64 * height as u64) as f64;
+    Ok(CaptureDiff {
+        changed_fraction,
+        changed: changed_fraction > threshold as f64,
+        bounds: bounds.map(|(left, top, right, bottom)| DiffBounds {
+            x: left,
+            y: top,
+            width: right - left + 1,
+            height: bottom - top + 1,
+        }),
+    })
+}
+
+/**
+ * 比较两张截图，返回不同像素的比例、变化区域和是否超过 threshold
+ * a / b: { capture_id } | { data: 图片字节 } | { path: 图片文件路径 }
+ * threshold: 不同像素比例的阈值 0.0 - 1.0，changed 为 changed_fraction > threshold
+ * 各通道差值都不超过 8 的像素视为相同；两张图片尺寸不同时返回错误
+ */
+#[tauri::command]
+pub async fn compare_captures(
+    app: AppHandle,
+    a: CaptureRef,
+    b: CaptureRef,
+    threshold: f32,
+) -> Result<CaptureDiff, CommandError> {
+    tokio::task::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let a = a.resolve(&state)?;
+        let b = b.resolve(&state)?;
+        diff(&a, &b, threshold)
+    })
+    .await
+    .map_err(|e| format!("compare task error: {}", e))?
+}
+
+/// capture_and_compare 的截取区域（相对屏幕左上角的物理像素）
+#[derive(Deserialize)]
+pub struct CaptureRegion {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// capture_and_compare 的结果
+#[derive(Serialize)]
+pub struct CaptureComparison {
+    /// 本次截图保留后的 capture_id，下次调用时作为 previous_capture_id 传入
+    pub capture_id: String,
+    /// 与上一张截图的比较结果，没有传 previous_capture_id 时为 None
+    pub diff: Option<CaptureDiff>,
+}
+
+/**
+ * 截取屏幕（或屏幕内的区域）并与上一张截图比较，用于轮询检测画面变化
+ * region: 同 capture_screen_by_id 的 x / y / width / height，不传则截取整个屏幕
+ * previous_capture_id: 上一次调用返回的 capture_id；比较后释放，只保留本次截图
+ * threshold 同 compare_captures；截图按 keep_capture 保留，可用 crop_capture 裁剪
+ * previous_capture_id 不存在时返回 kind 为 "notFound" 的错误
+ */
+#[tauri::command]
+pub async fn capture_and_compare(
+    app: AppHandle,
+    screen_id: u32,
+    region: Option<CaptureRegion>,
+    previous_capture_id: Option<String>,
+    threshold: f32,
+) -> Result<CaptureComparison, CommandError> {
+    let area = match region {
+        Some(r) => commands::sub_area(Some(r.x), Some(r.y), Some(r.width), Some(r.height))?,
+        None => None,
+    };
+    tokio::task::spawn_blocking(move || {
+        let pixels = commands::screen_rgba(screen_id, area)?;
+        let state = app.state::<AppState>();
+        let diff = match &previous_capture_id {
+            Some(previous) => Some(diff(&*kept_pixels(&state, previous)?, &pixels, threshold)?),
+            None => None,
+        };
+        let mut kept = state
+            .kept_captures
+            .lock()
+            .map_err(|e| format!("lock error: {}", e))?;
+        if let Some(previous) = &previous_capture_id {
+            kept.remove(previous);
+        }
+        Ok(CaptureComparison {
+            capture_id: kept.insert(pixels),
+            diff,
+        })
+    })
+    .await
+    .map_err(|e| format!("compare task error: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamps_crop_to_capture_bounds() {
+        assert_eq!(
+            clamp_rect((100, 50), -10, 40, 30, 30),
+            Some((0, 40, 20, 10))
+        );
+        assert_eq!(clamp_rect((100, 50), 90, 0, 30, 50), Some((90, 0, 10, 50)));
+        assert_eq!(clamp_rect((100, 50), 100, 0, 10, 10), None);
+    }
+
+    #[test]
+    fn evicts_oldest_capture() {
+        let mut kept = KeptCaptures::default();
+        let ids: Vec<String> = (0..=MAX_KEPT_CAPTURES)
+            .map(|_| kept.insert(RgbaImage::new(1, 1)))
+            .collect();
+        assert!(kept.get(&ids[0]).is_none());
+        assert!(kept.get(&ids[MAX_KEPT_CAPTURES]).is_some());
+        assert!(kept.remove(&ids[1]));
+        assert!(!kept.remove(&ids[1]));
+    }
+
+    #[test]
+    fn diff_reports_changed_fraction_and_bounds() {
+        let a = RgbaImage::new(10, 10);
+        let mut b = a.clone();
+        b.put_pixel(2, 3, image::Rgba([255, 0, 0, 255]));
+        b.put_pixel(6, 5, image::Rgba([0, 0, 255, 255]));
+        // 差值在容差内
+        b.put_pixel(9, 9, image::Rgba([PIXEL_TOLERANCE, 0, 0, 0]));
+
+        let result = diff(&a, &b, 0.01).unwrap();
+        assert_eq!(result.changed_fraction, 0.02);
+        assert!(result.changed);
+        assert_eq!(
+            result.bounds,
+            Some(DiffBounds {
+                x: 2,
+                y: 3,
+                width: 5,
+                height: 3
+            })
+        );
+        assert!(diff(&a, &RgbaImage::new(10, 9), 0.0).is_err());
+    }
+}
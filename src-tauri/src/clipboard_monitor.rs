@@ -0,0 +1,151 @@
+/*!
+ * 剪贴板变化监听
+ *
+ * 后台线程按间隔读取剪贴板文本和图片，与上一次内容的 hash 比较，变化时发送 "clipboard:changed"。
+ * 启动时的剪贴板内容作为基准，不发送事件；剪贴板被其他程序占用时跳过本次读取。
+ */
+
+use crate::AppState;
+use crate::commands;
+use crate::events;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, State};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+/// 默认轮询间隔
+const DEFAULT_INTERVAL_MS: u64 = 500;
+/// 最小轮询间隔（读取图片需要解码，过于频繁会占用 CPU）
+const MIN_INTERVAL_MS: u64 = 100;
+/// 文本预览的最大字符数
+const PREVIEW_CHARS: usize = 100;
+
+/// "clipboard:changed" 事件负载
+#[derive(Serialize, Clone, JsonSchema)]
+pub struct ClipboardChanged {
+    /// "text" | "image" | "empty"
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// 文本为前 100 个字符，图片为 "宽x高"，空剪贴板为 ""
+    pub preview: String,
+    pub timestamp_ms: u64,
+}
+
+/// 一次读取到的剪贴板内容：(类型, 内容 hash, 预览)
+type Snapshot = (&'static str, u64, String);
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn hash_of(value: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// 读取剪贴板内容；剪贴板被占用时返回 None
+fn snapshot(app: &AppHandle) -> Option<Snapshot> {
+    let clipboard = app.clipboard();
+    match clipboard.read_text() {
+        Ok(text) if !text.is_empty() => {
+            let preview = text.chars().take(PREVIEW_CHARS).collect();
+            return Some(("text", hash_of(&text), preview));
+        }
+        Err(e) if commands::is_clipboard_occupied(&e) => return None,
+        _ => {}
+    }
+    match clipboard.read_image() {
+        Ok(image) => {
+            let (width, height) = (image.width(), image.height());
+            let hash = hash_of((width, height, image.rgba()));
+            Some(("image", hash, format!("{}x{}", width, height)))
+        }
+        Err(e) if commands::is_clipboard_occupied(&e) => None,
+        Err(_) => Some(("empty", 0, String::new())),
+    }
+}
+
+/**
+ * 开始监听剪贴板变化，变化时发送 "clipboard:changed"（{ type, preview, timestamp_ms }）
+ * interval_ms: 轮询间隔，默认 500ms，最小 100ms
+ * 已在运行时返回错误，需先调用 stop_clipboard_monitor
+ */
+#[tauri::command]
+pub fn start_clipboard_monitor(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    interval_ms: Option<u64>,
+) -> Result<(), String> {
+    let mut guard = state
+        .clipboard_monitor
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?;
+    if guard.is_some() {
+        return Err("clipboard monitor is already running".into());
+    }
+
+    let stop_flag = Arc::new(AtomicBool::new(false));
+    let stop_flag_thread = stop_flag.clone();
+    let interval = Duration::from_millis(
+        interval_ms
+            .unwrap_or(DEFAULT_INTERVAL_MS)
+            .max(MIN_INTERVAL_MS),
+    );
+
+    let handle = thread::spawn(move || {
+        println!("[clipboard_monitor] thread started");
+        let mut last = snapshot(&app).map(|(kind, hash, _)| (kind, hash));
+
+        while !stop_flag_thread.load(Ordering::Relaxed) {
+            thread::sleep(interval);
+            let Some((kind, hash, preview)) = snapshot(&app) else {
+                continue;
+            };
+            if last == Some((kind, hash)) {
+                continue;
+            }
+            last = Some((kind, hash));
+            let payload = ClipboardChanged {
+                kind: kind.into(),
+                preview,
+                timestamp_ms: now_millis(),
+            };
+            if let Err(e) = events::emit_event(&app, events::Event::ClipboardChanged(payload)) {
+                eprintln!("[clipboard_monitor] emit error: {:?}", e);
+            }
+        }
+        println!("[clipboard_monitor] thread exiting");
+    });
+
+    *guard = Some((stop_flag, handle));
+    Ok(())
+}
+
+/**
+ * 停止剪贴板监听（未在运行时不做任何事）
+ */
+#[tauri::command]
+pub fn stop_clipboard_monitor(state: State<'_, AppState>) -> Result<(), String> {
+    let mut guard = state
+        .clipboard_monitor
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?;
+    if let Some((flag, handle)) = guard.take() {
+        flag.store(true, Ordering::Relaxed);
+        // 不在调用线程上等待（最多一个轮询间隔）
+        thread::spawn(move || {
+            let _ = handle.join();
+        });
+    }
+    Ok(())
+}
@@ -2,13 +2,28 @@
 // use tauri::image::JsImage;
 // use tauri::tray::TrayIcon;
 use crate::AppState;
+use crate::cache;
+use crate::capture_backend;
+use crate::cursor;
+use crate::ephemeral;
+use crate::error::CommandError;
+use crate::events;
+use crate::history;
+use crate::imaging;
+use crate::mouse_ws;
+use crate::own_windows::HiddenWindows;
+use crate::text_util;
+use crate::undo;
+use crate::window_list::Rect;
 use base64::{Engine as _, engine::general_purpose};
 use enigo::Enigo;
+use schemars::JsonSchema;
 use screenshots::Screen;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use tauri::AppHandle;
-use tauri::Emitter;
+use tauri::Manager;
 use tauri::State;
+use tauri::ipc::{Channel, InvokeResponseBody, Response};
 use tauri::image::Image;
 use tauri_plugin_clipboard_manager::ClipboardExt;
 use tauri_plugin_clipboard_manager::Error as ClipboardError;
@@ -16,7 +31,7 @@ use tauri_plugin_http::reqwest;
 
 use std::{
     sync::Arc,
-    sync::atomic::{AtomicBool, Ordering},
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
     thread,
     time::{Duration, Instant},
 };
@@ -33,26 +48,368 @@ pub fn clipboard_image(app: AppHandle, url: String) -> Result<(), ClipboardError
     Ok(())
 }
 
+/// 剪贴板中没有图片（也没有文本）
+pub const ERR_CLIPBOARD_NO_IMAGE: &str = "clipboard has no image";
+/// 剪贴板中只有文本
+pub const ERR_CLIPBOARD_TEXT_ONLY: &str = "clipboard has text but no image";
+/// 剪贴板被其他程序占用（重试后仍无法访问）
+pub const ERR_CLIPBOARD_OCCUPIED: &str = "clipboard is in use by another application";
+
+/// 剪贴板被占用时的重试次数和间隔（Windows 上其他程序打开剪贴板期间无法访问）
+const CLIPBOARD_RETRIES: u32 = 3;
+const CLIPBOARD_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+/**
+ * 剪贴板插件返回的错误是否为剪贴板被其他程序占用（arboard 的 ClipboardOccupied）
+ * 插件把 arboard 的错误转换成了只含文本的 Error::Clipboard，无法再按变体匹配，
+ * 只能与 arboard 错误的文本比较，作为经插件访问剪贴板时的最后手段；
+ * 直接使用 arboard 的路径（with_arboard）按 arboard::Error 变体匹配
+ */
+#[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+pub fn is_clipboard_occupied(e: &ClipboardError) -> bool {
+    is_arboard_error(e, arboard::Error::ClipboardOccupied)
+}
+
+/// 剪贴板插件返回的错误是否为剪贴板为空或内容不是请求的格式（arboard 的 ContentNotAvailable）
+#[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+fn is_clipboard_content_unavailable(e: &ClipboardError) -> bool {
+    is_arboard_error(e, arboard::Error::ContentNotAvailable)
+}
+
+/// 插件错误是否由指定的 arboard 错误转换而来（见 is_clipboard_occupied）
+#[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+fn is_arboard_error(e: &ClipboardError, kind: arboard::Error) -> bool {
+    matches!(e, ClipboardError::Clipboard(message) if *message == kind.to_string())
+}
+
+/// 移动端插件不使用 arboard
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+pub fn is_clipboard_occupied(_e: &ClipboardError) -> bool {
+    false
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn is_clipboard_content_unavailable(_e: &ClipboardError) -> bool {
+    false
+}
+
+/// 执行剪贴板操作，剪贴板被占用时稍后重试
+fn with_clipboard_retry<T>(
+    mut op: impl FnMut() -> Result<T, ClipboardError>,
+) -> Result<T, ClipboardError> {
+    let mut attempt = 0;
+    loop {
+        match op() {
+            Err(e) if is_clipboard_occupied(&e) && attempt < CLIPBOARD_RETRIES => {
+                attempt += 1;
+                thread::sleep(CLIPBOARD_RETRY_DELAY);
+            }
+            result => return result,
+        }
+    }
+}
+
+/**
+ * 读取剪贴板图片，返回 (width, height, RGBA 字节)，像素格式同 url_to_rgba 的 rgba 模式
+ * 剪贴板没有图片时返回 ERR_CLIPBOARD_NO_IMAGE / ERR_CLIPBOARD_TEXT_ONLY，便于前端区分提示
+ */
 #[tauri::command]
-pub async fn url_to_rgba(url: String) -> Result<(u32, u32, Vec<u8>), String> {
-    // 1. 下载图片二进制
-    let resp = reqwest::get(&url)
-        .await
-        .map_err(|e| format!("request error: {}", e))?;
-    let buf = resp
-        .bytes()
-        .await
-        .map_err(|e| format!("bytes error: {}", e))?;
+pub fn read_clipboard_image(app: AppHandle) -> Result<(u32, u32, Vec<u8>), String> {
+    let clipboard = app.clipboard();
+    match clipboard.read_image() {
+        Ok(img) => Ok((img.width(), img.height(), img.rgba().to_vec())),
+        Err(e) => {
+            let has_text = clipboard.read_text().is_ok_and(|t| !t.trim().is_empty());
+            if has_text {
+                Err(ERR_CLIPBOARD_TEXT_ONLY.to_string())
+            } else {
+                eprintln!("[clipboard] read image failed: {}", e);
+                Err(ERR_CLIPBOARD_NO_IMAGE.to_string())
+            }
+        }
+    }
+}
 
-    // 2. 用 image crate 解析
-    let dyn_img = image::load_from_memory(&buf)
-        .map_err(|e| format!("decode error: {}", e))?
-        .to_rgba8();
+/**
+ * 写入文本到剪贴板
+ * 剪贴板被其他程序占用时重试几次，仍失败则返回错误
+ */
+#[tauri::command]
+pub fn write_clipboard_text(app: AppHandle, text: String) -> Result<(), ClipboardError> {
+    with_clipboard_retry(|| app.clipboard().write_text(text.clone()))
+}
+
+/**
+ * 读取剪贴板中的纯文本，剪贴板为空或不是文本（如图片）时返回空字符串
+ * 剪贴板被其他程序占用且重试后仍无法访问时返回 ERR_CLIPBOARD_OCCUPIED
+ */
+#[tauri::command]
+pub fn read_clipboard_text(app: AppHandle) -> Result<String, String> {
+    match with_clipboard_retry(|| app.clipboard().read_text()) {
+        Ok(text) => Ok(text),
+        Err(e) if is_clipboard_occupied(&e) => Err(ERR_CLIPBOARD_OCCUPIED.to_string()),
+        Err(e) if is_clipboard_content_unavailable(&e) => Ok(String::new()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/**
+ * 清空剪贴板
+ * 剪贴板被其他程序占用时同 write_clipboard_text
+ */
+#[tauri::command]
+pub fn clear_clipboard(app: AppHandle) -> Result<(), ClipboardError> {
+    with_clipboard_retry(|| app.clipboard().clear())
+}
+
+/**
+ * 写入 HTML 到剪贴板，同时写入纯文本格式：粘贴到不支持 HTML 的程序时得到 plain_fallback
+ * 平台支持：
+ *   Windows: CF_HTML + CF_UNICODETEXT
+ *   macOS: public.html + public.utf8-plain-text
+ *   Linux: X11（含 XWayland）text/html + UTF8_STRING；纯 Wayland 程序可能读不到
+ * 剪贴板被其他程序占用且重试后仍无法访问时返回 ERR_CLIPBOARD_OCCUPIED
+ */
+#[tauri::command]
+pub fn write_clipboard_html(
+    app: AppHandle,
+    html: String,
+    plain_fallback: String,
+) -> Result<(), String> {
+    with_clipboard_retry(|| {
+        app.clipboard()
+            .write_html(html.clone(), Some(plain_fallback.clone()))
+    })
+    .map_err(|e| match is_clipboard_occupied(&e) {
+        true => ERR_CLIPBOARD_OCCUPIED.to_string(),
+        false => e.to_string(),
+    })
+}
+
+/**
+ * 读取剪贴板中的 HTML，剪贴板没有 HTML 格式（如只有纯文本或图片）时返回 None
+ * Windows 上返回 CF_HTML 中的片段（不含 Version / StartHTML 等头部）
+ * 平台支持同 write_clipboard_html；剪贴板插件只能写入 HTML，读取直接使用 arboard
+ */
+#[tauri::command]
+pub fn read_clipboard_html() -> Result<Option<String>, String> {
+    read_html()
+}
+
+/// 直接用 arboard 访问剪贴板（插件未提供的格式），剪贴板被占用时稍后重试
+/// 内容不是请求的格式时返回 Ok(None)
+#[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+fn with_arboard<T>(
+    mut op: impl FnMut(&mut arboard::Clipboard) -> Result<T, arboard::Error>,
+) -> Result<Option<T>, String> {
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| e.to_string())?;
+    let mut attempt = 0;
+    loop {
+        match op(&mut clipboard) {
+            Ok(value) => return Ok(Some(value)),
+            Err(arboard::Error::ContentNotAvailable) => return Ok(None),
+            Err(arboard::Error::ClipboardOccupied) if attempt < CLIPBOARD_RETRIES => {
+                attempt += 1;
+                thread::sleep(CLIPBOARD_RETRY_DELAY);
+            }
+            Err(arboard::Error::ClipboardOccupied) => return Err(ERR_CLIPBOARD_OCCUPIED.into()),
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+fn read_html() -> Result<Option<String>, String> {
+    with_arboard(|clipboard| clipboard.get().html())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn read_html() -> Result<Option<String>, String> {
+    Err("reading HTML from the clipboard is not supported on this platform".into())
+}
+
+/**
+ * 读取剪贴板中的文件列表（在资源管理器 / Finder 中复制的文件），返回绝对路径
+ * 剪贴板中没有文件时返回空数组
+ * 平台支持：
+ *   Windows: CF_HDROP
+ *   macOS: NSFilenamesPboardType（public.file-url）
+ *   Linux: text/uri-list（X11 与 Wayland）
+ */
+#[tauri::command]
+pub fn read_clipboard_files() -> Result<Vec<String>, String> {
+    read_files()
+}
+
+/**
+ * 把文件列表写入剪贴板，之后可在资源管理器 / Finder 中粘贴为文件
+ * paths: 文件或目录路径，必须已存在；平台格式同 read_clipboard_files
+ * （Linux 上额外写入 x-special/gnome-copied-files 供 GNOME 文件管理器识别）
+ */
+#[tauri::command]
+pub fn write_clipboard_files(paths: Vec<String>) -> Result<(), String> {
+    if paths.is_empty() {
+        return Err("no files to write".into());
+    }
+    let mut absolute = Vec::with_capacity(paths.len());
+    for path in &paths {
+        if !std::path::Path::new(path).exists() {
+            return Err(format!("file not found: {}", path));
+        }
+        // 不用 canonicalize：Windows 上会得到 \\?\ 前缀的路径，资源管理器无法粘贴
+        absolute.push(std::path::absolute(path).map_err(|e| format!("{}: {}", path, e))?);
+    }
+    write_files(&absolute)
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+fn read_files() -> Result<Vec<String>, String> {
+    let files = with_arboard(|clipboard| clipboard.get().file_list())?.unwrap_or_default();
+    Ok(files
+        .iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect())
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+fn write_files(paths: &[std::path::PathBuf]) -> Result<(), String> {
+    with_arboard(|clipboard| clipboard.set().file_list(paths)).map(|_| ())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn read_files() -> Result<Vec<String>, String> {
+    Err("reading files from the clipboard is not supported on this platform".into())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn write_files(_paths: &[std::path::PathBuf]) -> Result<(), String> {
+    Err("writing files to the clipboard is not supported on this platform".into())
+}
+
+/// %XX 解码（非法序列原样保留）
+fn percent_decode(s: &str) -> Vec<u8> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (c, _) => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    out
+}
 
-    let (width, height) = dyn_img.dimensions();
-    let rgba = dyn_img.into_vec(); // Vec<u8>，每 4 个一组
+/**
+ * 读取 data: / file: URI 的内容；其他 scheme 返回 None，由调用方走 HTTP
+ * data:[<mime>][;base64],<data>
+ * file:///path（Windows 为 file:///C:/path）
+ */
+fn read_local_uri(url: &str) -> Option<Result<Vec<u8>, CommandError>> {
+    if let Some(rest) = url.strip_prefix("data:") {
+        let Some((meta, data)) = rest.split_once(',') else {
+            return Some(Err(CommandError::Decode("invalid data URI".into())));
+        };
+        if meta.ends_with(";base64") {
+            let cleaned: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+            let decoded = String::from_utf8(percent_decode(&cleaned)).unwrap_or(cleaned);
+            return Some(
+                general_purpose::STANDARD
+                    .decode(decoded)
+                    .map_err(|e| CommandError::Decode(format!("base64 error: {}", e))),
+            );
+        }
+        return Some(Ok(percent_decode(data)));
+    }
+
+    let rest = url.strip_prefix("file://")?;
+    // 去掉可能的主机名 localhost
+    let rest = rest.strip_prefix("localhost").unwrap_or(rest);
+    let path = String::from_utf8_lossy(&percent_decode(rest)).into_owned();
+    // file:///C:/x -> C:/x
+    let path = match path.as_bytes() {
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => path[1..].to_string(),
+        _ => path,
+    };
+    Some(std::fs::read(&path).map_err(|e| {
+        let message = format!("read {} failed: {}", path, e);
+        match e.kind() {
+            std::io::ErrorKind::NotFound => CommandError::NotFound(message),
+            _ => CommandError::Io(message),
+        }
+    }))
+}
+
+/// 把解码后的图片转换为某种像素格式的字节
+type PixelConverter = fn(image::DynamicImage) -> Vec<u8>;
+
+/// url_to_rgba 的像素格式：(每像素通道数, 转换函数)
+fn pixel_mode(mode: Option<&str>) -> Result<(u8, PixelConverter), String> {
+    match mode.unwrap_or("rgba") {
+        "rgba" => Ok((4, |img| img.into_rgba8().into_raw())),
+        "rgb" => Ok((3, |img| img.into_rgb8().into_raw())),
+        "luma" => Ok((1, |img| img.into_luma8().into_raw())),
+        other => Err(format!("unknown mode: {}", other)),
+    }
+}
+
+/**
+ * 加载图片并转换为像素数组，返回 (width, height, 像素字节, channels)
+ * 支持 http(s)、data:（base64 内联）和 file:// 本地文件
+ * mode: "rgba"（默认，channels = 4）| "rgb"（3）| "luma"（灰度，1，适合 OCR 预处理）
+ * 像素逐行从上到下排列，每像素 channels 个字节
+ * 失败时返回 CommandError（kind: network / decode / io / notFound）
+ */
+#[tauri::command]
+pub async fn url_to_rgba(
+    state: State<'_, AppState>,
+    url: String,
+    mode: Option<String>,
+) -> Result<(u32, u32, Vec<u8>, u8), CommandError> {
+    load_rgba(&state.http, &url, mode.as_deref()).await
+}
+
+/// url_to_rgba 的实现；http(s) 请求使用传入的客户端
+async fn load_rgba(
+    client: &reqwest::Client,
+    url: &str,
+    mode: Option<&str>,
+) -> Result<(u32, u32, Vec<u8>, u8), CommandError> {
+    let (channels, convert) = pixel_mode(mode)?;
+
+    // 1. 读取图片二进制（本地 URI 直接读取，其他走 HTTP）
+    let buf = match read_local_uri(url) {
+        Some(result) => result?,
+        None => {
+            let resp = client
+                .get(url)
+                .send()
+                .await
+                .map_err(|e| CommandError::Network(format!("request error: {}", e)))?;
+            resp.bytes()
+                .await
+                .map_err(|e| CommandError::Network(format!("bytes error: {}", e)))?
+                .to_vec()
+        }
+    };
 
-    Ok((width, height, rgba))
+    // 2. 用 image crate 解析并转换为目标格式
+    let dyn_img = image::load_from_memory(&buf)?;
+
+    let (width, height) = (dyn_img.width(), dyn_img.height());
+    let pixels = convert(dyn_img); // Vec<u8>，每 channels 个一组
+
+    Ok((width, height, pixels, channels))
 }
 
 /**
@@ -65,14 +422,28 @@ pub async fn url_to_rgba(url: String) -> Result<(u32, u32, Vec<u8>), String> {
  * 3. 判断文件是否存在 存在直接返回文件地址 否则 第4步
  * 4. 下载并缓存到本地 返回文件地址
  *
+ * 临时模式下 cache_base 会被重定向到沙箱目录
+ * tag: 可选分组标签（如会话 id），记录在缓存清单中，用于 cache_usage_by_tag 统计
+ * expected_sha256: 可选的内容校验值（十六进制，不区分大小写）；已缓存文件不匹配时重新下载，
+ *                  下载内容不匹配时返回 decode 错误且不写入缓存
+ * key_by: 缓存文件名的来源，"url"（默认，按 URL 的 hash 命名，已缓存时不再下载）|
+ *         "content"（每次都下载，按内容的 sha256 命名：不同 URL 的相同图片只存一份，
+ *         URL 内容变化后自动换用新文件；同名文件已存在时不重复写入）
+ * 失败时返回 CommandError（kind: network / io / decode）
+ *
  */
 #[tauri::command]
-pub async fn cache_image_to_path(url: String, cache_base: String) -> Result<String, String> {
-    use reqwest::Client;
+pub async fn cache_image_to_path(
+    state: State<'_, AppState>,
+    url: String,
+    cache_base: String,
+    tag: Option<String>,
+    expected_sha256: Option<String>,
+    key_by: Option<String>,
+) -> Result<String, CommandError> {
     use sha2::{Digest, Sha256};
     use std::fs::{self, File};
     use std::io::Write;
-    use std::path::PathBuf;
 
     let ext = url
         .rsplit('.')
@@ -80,41 +451,199 @@ pub async fn cache_image_to_path(url: String, cache_base: String) -> Result<Stri
         .and_then(|s| s.split(&['?', '#'][..]).next())
         .filter(|s| s.len() <= 5)
         .unwrap_or("jpg");
+    let by_content = match key_by.as_deref().unwrap_or("url") {
+        "url" => false,
+        "content" => true,
+        other => return Err(format!("unknown key_by: {}", other).into()),
+    };
 
     let mut hasher = Sha256::new();
     hasher.update(url.as_bytes());
     let filename = format!("{:x}.{}", hasher.finalize(), ext);
 
-    let dir = PathBuf::from(cache_base);
+    // 临时模式下缓存写入沙箱
+    let dir = cache::cache_dir(&state, &cache_base);
 
     // ✅ 确保目录创建不会因权限或路径失败
-    fs::create_dir_all(&dir).map_err(|e| format!("mkdir error: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| CommandError::Io(format!("mkdir error: {}", e)))?;
 
     let mut file_path = dir.clone();
     file_path.push(&filename);
 
-    if file_path.exists() {
+    let expected = expected_sha256.map(|h| h.trim().to_ascii_lowercase());
+    let matches = |data: &[u8]| {
+        expected
+            .as_deref()
+            .is_none_or(|h| format!("{:x}", Sha256::digest(data)) == h)
+    };
+
+    // 有校验值时已缓存文件也要校验，不匹配（如上次写入中断）则重新下载
+    let cached = !by_content
+        && file_path.exists()
+        && (expected.is_none() || fs::read(&file_path).is_ok_and(|data| matches(&data)));
+    if cached {
+        if tag.is_some() {
+            let size = fs::metadata(&file_path).map(|m| m.len()).unwrap_or(0);
+            cache::record(&state, &dir, &filename, &url, tag, size)?;
+        }
         return Ok(file_path.to_string_lossy().into_owned());
     }
 
     // ✅ 下载数据
-    let bytes = Client::new()
+    let bytes = state
+        .http
         .get(&url)
         .send()
         .await
-        .map_err(|e| format!("request error: {}", e))?
+        .map_err(|e| CommandError::Network(format!("request error: {}", e)))?
         .bytes()
         .await
-        .map_err(|e| format!("bytes error: {}", e))?;
+        .map_err(|e| CommandError::Network(format!("bytes error: {}", e)))?;
+    if !matches(&bytes) {
+        return Err(CommandError::Decode(format!("sha256 mismatch for {}", url)));
+    }
+
+    // 按内容命名时，相同内容的文件已存在则只更新清单
+    let filename = match by_content {
+        true => format!("{:x}.{}", Sha256::digest(&bytes), ext),
+        false => filename,
+    };
+    let file_path = dir.join(&filename);
+    if by_content && file_path.exists() {
+        cache::record(&state, &dir, &filename, &url, tag, bytes.len() as u64)?;
+        return Ok(file_path.to_string_lossy().into_owned());
+    }
 
     // ✅ 写入文件
-    let mut file = File::create(&file_path).map_err(|e| format!("file create: {}", e))?;
+    let mut file =
+        File::create(&file_path).map_err(|e| CommandError::Io(format!("file create: {}", e)))?;
     file.write_all(&bytes)
-        .map_err(|e| format!("write error: {}", e))?;
+        .map_err(|e| CommandError::Io(format!("write error: {}", e)))?;
+    cache::record(&state, &dir, &filename, &url, tag, bytes.len() as u64)?;
 
     Ok(file_path.to_string_lossy().into_owned())
 }
 
+/**
+ * 缓存图片的缩略图（画廊使用，避免加载原图）
+ * url / cache_base / tag 同 cache_image_to_path
+ * max_dim: 缩略图最长边（像素），按原比例缩放；原图不大于 max_dim 时直接缓存原图
+ *
+ * 缓存文件名的 hash 包含 max_dim，不同尺寸的缩略图互不覆盖；
+ * 缩略图尽量保持原图格式，原格式无法编码时保存为 PNG
+ */
+#[tauri::command]
+pub async fn cache_image_thumbnail(
+    state: State<'_, AppState>,
+    url: String,
+    cache_base: String,
+    max_dim: u32,
+    tag: Option<String>,
+) -> Result<String, String> {
+    use sha2::{Digest, Sha256};
+    use std::fs;
+
+    if max_dim == 0 {
+        return Err("max_dim must be greater than 0".into());
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(url.as_bytes());
+    hasher.update(format!("@thumb{}", max_dim).as_bytes());
+    let hash = format!("{:x}", hasher.finalize());
+
+    let dir = cache::cache_dir(&state, &cache_base);
+    fs::create_dir_all(&dir).map_err(|e| format!("mkdir error: {}", e))?;
+
+    // 格式在下载前未知，按 hash（不含扩展名）查找已缓存的缩略图
+    let cached = fs::read_dir(&dir).ok().and_then(|entries| {
+        entries
+            .flatten()
+            .map(|e| e.path())
+            .find(|p| p.file_stem().and_then(|s| s.to_str()) == Some(hash.as_str()))
+    });
+    if let Some(path) = cached {
+        if tag.is_some() {
+            let filename = cache::file_name(&path);
+            let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            cache::record(&state, &dir, &filename, &url, tag, size)?;
+        }
+        return Ok(path.to_string_lossy().into_owned());
+    }
+
+    let bytes = state
+        .http
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| format!("request error: {}", e))?
+        .bytes()
+        .await
+        .map_err(|e| format!("bytes error: {}", e))?;
+
+    let (data, ext) = tokio::task::spawn_blocking(move || thumbnail_bytes(&bytes, max_dim))
+        .await
+        .map_err(|e| format!("thumbnail task error: {}", e))??;
+
+    let filename = format!("{}.{}", hash, ext);
+    let file_path = dir.join(&filename);
+    fs::write(&file_path, &data).map_err(|e| format!("write error: {}", e))?;
+    cache::record(&state, &dir, &filename, &url, tag, data.len() as u64)?;
+
+    Ok(file_path.to_string_lossy().into_owned())
+}
+
+/// 生成最长边为 max_dim 的缩略图，返回 (编码后的数据, 扩展名)；原图足够小时原样返回
+fn thumbnail_bytes(bytes: &[u8], max_dim: u32) -> Result<(Vec<u8>, &'static str), String> {
+    use image::ImageFormat;
+
+    let format = image::guess_format(bytes).map_err(|e| format!("decode error: {}", e))?;
+    let source = image::load_from_memory_with_format(bytes, format)
+        .map_err(|e| format!("decode error: {}", e))?;
+    let (width, height) = (source.width(), source.height());
+    if width.max(height) <= max_dim {
+        return Ok((bytes.to_vec(), format.extensions_str()[0]));
+    }
+
+    let scale = max_dim as f64 / width.max(height) as f64;
+    let new_width = ((width as f64 * scale).round() as u32).max(1);
+    let new_height = ((height as f64 * scale).round() as u32).max(1);
+    let thumb = image::imageops::thumbnail(&source.to_rgba8(), new_width, new_height);
+
+    let format = if format.can_write() {
+        format
+    } else {
+        ImageFormat::Png
+    };
+    // JPEG 不支持透明通道
+    let thumb = match format {
+        ImageFormat::Jpeg => {
+            image::DynamicImage::ImageRgb8(image::DynamicImage::ImageRgba8(thumb).to_rgb8())
+        }
+        _ => image::DynamicImage::ImageRgba8(thumb),
+    };
+    let mut data = Vec::new();
+    thumb
+        .write_to(&mut std::io::Cursor::new(&mut data), format)
+        .map_err(|e| format!("encode error: {}", e))?;
+    Ok((data, format.extensions_str()[0]))
+}
+
+/**
+ * 清空图片缓存（可撤销）
+ * 缓存文件先移入暂存区并返回 undo_token，清单记录同时清空，撤销时一起恢复；
+ * 缓存过大时需 confirm: true 才会永久删除
+ */
+#[tauri::command]
+pub fn clear_image_cache(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    cache_base: String,
+    confirm: Option<bool>,
+) -> Result<undo::DestructiveResult, String> {
+    cache::clear_all(&app, &state, &cache_base, confirm.unwrap_or(false))
+}
+
 /**
  * 获取鼠标位置
  */
@@ -171,10 +700,13 @@ pub fn control_mouse_poller(
 
             // last_sent: 上一次真正发送出去的坐标（用于比较阈值）
             let mut last_sent: Option<(i32, i32)> = None;
-            // pending: 在节流窗口内，最新的待发送坐标（会覆盖）
-            let mut pending: Option<(i32, i32)> = None;
-            // 上次发送时间
-            let mut last_emit_time = Instant::now() - Duration::from_secs(3600); // 设为很久以前，首次可发送
+            // pending: 因节流尚未发送的最新采样（每次轮询覆盖，位置回到阈值内时清空）
+            let mut pending: Option<MousePos> = None;
+            // 上一次采样的坐标和时间（用于计算速度）
+            let mut last_sample: Option<((i32, i32), Instant)> = None;
+            // 上次发送时间（None 表示还没发送过，首次可立即发送）
+            let mut last_emit_time: Option<Instant> = None;
+            let throttle = Duration::from_millis(throttle_val);
 
             // 循环，直到 stop_flag 被置位
             while !stop_flag_thread.load(Ordering::Relaxed) {
@@ -182,88 +714,26 @@ pub fn control_mouse_poller(
                 let (x, y) = Enigo::mouse_location();
                 let cur = (x as i32, y as i32);
 
-                // 判断是否和 last_sent 有足够移动
-                let moved_enough = match last_sent {
-                    Some((sx, sy)) => {
-                        let dx = (cur.0 - sx).abs();
-                        let dy = (cur.1 - sy).abs();
-                        // 使用 L1 距离作为判定：abs(dx)+abs(dy) >= min_move
-                        (dx + dy) >= min_move_val
-                    }
-                    None => {
-                        // 如果还没发送过任何点，认为第一次移动应当发送（除非 min_move > 0 且在 (0,0)）
-                        true
-                    }
-                };
-
                 let now = Instant::now();
+                // 按实际经过的时间计算速度（interval 只是期望值，sleep 可能更久）
+                let sample = MousePos::sampled(cur, last_sample.map(|(p, t)| (p, now.duration_since(t))));
+                last_sample = Some((cur, now));
 
-                if moved_enough {
-                    if throttle_val == 0 {
-                        // 不做节流：立即发送
-                        let payload = MousePos { x: cur.0, y: cur.1 };
-                        let res = if let Some(ref label) = target_label {
-                            app_for_thread.emit_to(label.clone(), "mouse:position", payload)
-                        } else {
-                            app_for_thread.emit("mouse:position", payload)
-                        };
+                match should_emit(last_sent, cur, last_emit_time, now, min_move_val, throttle) {
+                    Some(p) => {
+                        let res = emit_mouse_position(&app_for_thread, target_label.as_deref(), sample);
                         if res.is_err() {
                             eprintln!("[mouse_poller] emit error: {:?}", res.err());
                         } else {
-                            // 更新 last_sent 及 last_emit_time
-                            last_sent = Some(cur);
-                            last_emit_time = now;
+                            last_sent = Some(p);
+                            last_emit_time = Some(now);
                         }
-                        // 清空 pending（已发送）
                         pending = None;
-                    } else {
-                        // 做节流：保存为 pending（覆盖），并在超过 throttle 时发送
-                        pending = Some(cur);
-
-                        // 若距离上次发送已超过 throttle，则发送 pending（最新）
-                        if now.duration_since(last_emit_time).as_millis() as u64 >= throttle_val {
-                            if let Some(p) = pending.take() {
-                                let payload = MousePos { x: p.0, y: p.1 };
-                                let res = if let Some(ref label) = target_label {
-                                    app_for_thread.emit_to(label.clone(), "mouse:position", payload)
-                                } else {
-                                    app_for_thread.emit("mouse:position", payload)
-                                };
-                                if res.is_err() {
-                                    eprintln!("[mouse_poller] emit error: {:?}", res.err());
-                                } else {
-                                    last_sent = Some(p);
-                                    last_emit_time = Instant::now();
-                                }
-                            }
-                        }
                     }
-                } else {
-                    // 没有足够移动：如果存在 pending（由先前较小移动产生），并且已超过 throttle，也可发送 pending
-                    if throttle_val > 0 {
-                        if let Some(_p) = pending {
-                            if now.duration_since(last_emit_time).as_millis() as u64 >= throttle_val
-                            {
-                                if let Some(p) = pending.take() {
-                                    let payload = MousePos { x: p.0, y: p.1 };
-                                    let res = if let Some(ref label) = target_label {
-                                        app_for_thread.emit_to(
-                                            label.clone(),
-                                            "mouse:position",
-                                            payload,
-                                        )
-                                    } else {
-                                        app_for_thread.emit("mouse:position", payload)
-                                    };
-                                    if res.is_err() {
-                                        eprintln!("[mouse_poller] emit error: {:?}", res.err());
-                                    } else {
-                                        last_sent = Some(p);
-                                        last_emit_time = Instant::now();
-                                    }
-                                }
-                            }
-                        }
+                    // 未发送：移动足够时（仅被节流挡住）记为 pending，退出时补发
+                    None => {
+                        pending = should_emit(last_sent, cur, None, now, min_move_val, Duration::ZERO)
+                            .map(|_| sample);
                     }
                 }
 
@@ -273,18 +743,13 @@ pub fn control_mouse_poller(
 
             // 线程退出前：若有未发送的 pending，则发送一次（确保不丢最后一条）
             if let Some(p) = pending {
-                let payload = MousePos { x: p.0, y: p.1 };
-                let res = if let Some(ref label) = target_label {
-                    app_for_thread.emit_to(label.clone(), "mouse:position", payload)
-                } else {
-                    app_for_thread.emit("mouse:position", payload)
-                };
+                let res = emit_mouse_position(&app_for_thread, target_label.as_deref(), p);
                 if res.is_err() {
                     eprintln!("[mouse_poller] emit error on shutdown: {:?}", res.err());
                 } else {
                     println!(
                         "[mouse_poller] emitted pending on shutdown: ({},{})",
-                        p.0, p.1
+                        p.x, p.y
                     );
                 }
             }
@@ -292,13 +757,19 @@ pub fn control_mouse_poller(
             println!("[mouse_poller] thread exiting");
         });
 
-        // 存储 stop_flag 与 handle
-        *guard = Some((stop_flag, handle));
+        // 存储 stop_flag、handle 与本次配置
+        let config = MousePollerConfig {
+            interval_ms: ms,
+            min_move: min_move_val,
+            throttle_ms: throttle_val,
+            window_label,
+        };
+        *guard = Some((stop_flag, handle, config));
         Ok("started".into())
     } else {
         // 停止：取出存储的 (flag, handle)
         match guard.take() {
-            Some((flag, handle)) => {
+            Some((flag, handle, _)) => {
                 // 标记停止
                 flag.store(true, Ordering::Relaxed);
 
@@ -318,36 +789,278 @@ pub fn control_mouse_poller(
     }
 }
 
+/**
+ * 鼠标轮询的发送判定：返回本次应发送的坐标，不发送时返回 None
+ * 与 last_sent 的 L1 距离小于 min_move 时不发送；距上次发送不足 throttle 时不发送，
+ * 由之后的轮询用当时的最新坐标补发（节流窗口内的多次移动合并为一次）
+ * last_sent / last_emit_time 为 None 表示还没发送过
+ */
+fn should_emit(
+    last_sent: Option<(i32, i32)>,
+    candidate: (i32, i32),
+    last_emit_time: Option<Instant>,
+    now: Instant,
+    min_move: i32,
+    throttle: Duration,
+) -> Option<(i32, i32)> {
+    if let Some((sx, sy)) = last_sent {
+        let moved = (candidate.0 - sx).abs() + (candidate.1 - sy).abs();
+        if moved < min_move {
+            return None;
+        }
+    }
+    match last_emit_time {
+        Some(t) if now.saturating_duration_since(t) < throttle => None,
+        _ => Some(candidate),
+    }
+}
+
+/// 发送 "mouse:position" 事件（label 为 None 时发给所有窗口），同时推送给鼠标 WebSocket 的客户端
+fn emit_mouse_position(app: &AppHandle, label: Option<&str>, pos: MousePos) -> tauri::Result<()> {
+    mouse_ws::broadcast(&app.state::<AppState>(), pos);
+    let event = events::Event::MousePosition(pos);
+    match label {
+        Some(label) => events::emit_event_to(app, label, event),
+        None => events::emit_event(app, event),
+    }
+}
+
+/// 鼠标轮询线程的启动配置
+#[derive(Serialize, Clone, Debug)]
+pub struct MousePollerConfig {
+    pub interval_ms: u64,
+    pub min_move: i32,
+    pub throttle_ms: u64,
+    pub window_label: Option<String>,
+}
+
+/// 运行中的鼠标轮询线程：停止标志、线程句柄和启动配置
+pub type MousePoller = (Arc<AtomicBool>, thread::JoinHandle<()>, MousePollerConfig);
+
+/// 鼠标轮询状态
+#[derive(Serialize)]
+pub struct MousePollerStatus {
+    pub running: bool,
+    pub interval_ms: Option<u64>,
+}
+
+/**
+ * 查询鼠标轮询线程是否在运行（前端刷新后用于同步开关状态）
+ */
+#[tauri::command]
+pub fn mouse_poller_status(state: State<'_, AppState>) -> Result<MousePollerStatus, String> {
+    let guard = state
+        .mouse_poller
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?;
+    Ok(match guard.as_ref() {
+        Some((flag, handle, config)) => MousePollerStatus {
+            running: !flag.load(Ordering::Relaxed) && !handle.is_finished(),
+            interval_ms: Some(config.interval_ms),
+        },
+        None => MousePollerStatus {
+            running: false,
+            interval_ms: None,
+        },
+    })
+}
+
+/// segment_text 的返回值：默认为词列表，with_offsets 时为带字节偏移的词
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum SegmentOutput {
+    Words(Vec<String>),
+    WithOffsets(Vec<text_util::TokenWithOffset>),
+}
+
 /**
  * 使用jieba 分词器进行分词
+ * mode: "default" | "exact" | "search" | "full"，缺省为 "default"，含义见 text_util::SegmentMode
+ * exact: 已废弃，请改用 mode（exact: true 等同 mode: "exact"）；同时传入时以 mode 为准
+ * hmm: 是否用 HMM 识别词典外的新词，缺省按模式决定（default 关闭，exact / search 开启，full 不支持）
+ * stop_words: 可选停用词表（不区分大小写），命中的词从结果中去掉；内置表见 get_default_stop_words
+ * with_offsets: 为 true 时返回 { word, start, end }，start/end 为原文 UTF-8 字节偏移
  */
 #[tauri::command]
-pub fn segment_text(state: State<'_, AppState>, text: String, exact: bool) -> Vec<String> {
+#[allow(clippy::too_many_arguments)]
+pub fn segment_text(
+    state: State<'_, AppState>,
+    text: String,
+    mode: Option<String>,
+    exact: Option<bool>,
+    hmm: Option<bool>,
+    stop_words: Option<Vec<String>>,
+    with_offsets: Option<bool>,
+) -> Result<SegmentOutput, String> {
+    let mode = text_util::SegmentMode::resolve(mode.as_deref(), exact)?;
+    let stop_words = text_util::stop_word_set(&stop_words.unwrap_or_default());
     // 读取锁（短时间持有）
     let jieba = state.jieba.read().expect("RwLock poisoned");
-    jieba
-        .cut(&text, exact)
+    let tokens = text_util::cut(&jieba, &text, mode, hmm);
+    if with_offsets.unwrap_or(false) {
+        let tokens = text_util::token_offsets(&text, &tokens)
+            .into_iter()
+            .filter(|t| !text_util::is_stop_word(&t.word, &stop_words))
+            .collect();
+        return Ok(SegmentOutput::WithOffsets(tokens));
+    }
+    let words = tokens.into_iter().map(|s| s.to_string()).collect();
+    let words = text_util::filter_stop_words(words, &stop_words);
+    Ok(SegmentOutput::Words(words))
+}
+
+/**
+ * 搜索引擎模式分词（cut_for_search）：长词之外再输出其中的子词，便于建立搜索索引
+ * 等同 segment_text 的 mode: "search"；hmm 控制是否识别词典外的新词
+ */
+#[tauri::command]
+pub fn segment_text_search(state: State<'_, AppState>, text: String, hmm: bool) -> Vec<String> {
+    let jieba = state.jieba.read().expect("RwLock poisoned");
+    text_util::cut(&jieba, &text, text_util::SegmentMode::Search, Some(hmm))
         .into_iter()
         .map(|s| s.to_string())
         .collect()
 }
 
 /// 批量分词，接受一个包含 (id, 文本) 元组的向量，返回 (id, 分词结果) 元组的向量
+/// mode / exact 同 segment_text
+/// parallel: 为 true 时多线程并行分词（大批量时更快），结果顺序与输入一致
 #[tauri::command]
 pub fn batch_segment_text(
     state: State<'_, AppState>,
     inputs: Vec<(String, String)>,
-    exact: bool,
-) -> Vec<(String, Vec<String>)> {
+    mode: Option<String>,
+    exact: Option<bool>,
+    stop_words: Option<Vec<String>>,
+    parallel: Option<bool>,
+) -> Result<Vec<(String, Vec<String>)>, String> {
+    let mode = text_util::SegmentMode::resolve(mode.as_deref(), exact)?;
+    let stop_words = text_util::stop_word_set(&stop_words.unwrap_or_default());
     let jieba = state.jieba.read().expect("RwLock poisoned");
-    inputs
-        .into_iter()
-        .map(|(id, text)| {
-            let words = jieba.cut(&text, exact);
-            (id, words.into_iter().map(|s| s.to_string()).collect())
-        })
-        .collect()
+    Ok(text_util::segment_batch(
+        &jieba,
+        inputs,
+        mode,
+        &stop_words,
+        parallel.unwrap_or(false),
+    ))
 }
+/// 流式分词的单个分片，最后一条消息 done 为 true 且 tokens 为空
+#[derive(Serialize, Clone)]
+pub struct SegmentChunk {
+    pub index: usize,
+    pub tokens: Vec<String>,
+    pub done: bool,
+    pub total_tokens: usize,
+}
+
+/// 句末标点
+fn is_sentence_end(c: char) -> bool {
+    matches!(c, '。' | '！' | '？' | '；' | '!' | '?' | ';' | '\n' | '…')
+}
+
+/// 次一级的断点（逗号、空白等），句子过长时使用
+fn is_soft_break(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '，' | '、' | '：' | ',' | ':')
+}
+
+/// window 中最后一个满足 pred 的字符之后的字节位置；片首的字符不算，避免切出只有一个标点或空白的分片
+fn break_after(window: &str, pred: fn(char) -> bool) -> Option<usize> {
+    window
+        .char_indices()
+        .rev()
+        .find(|&(i, c)| i > 0 && pred(c))
+        .map(|(i, c)| i + c.len_utf8())
+}
+
+/// 按句子边界把文本切成不超过 chunk_chars 个字符的分片（按 char 计数，不会截断多字节字符）
+/// 优先在句末切分，句子超长时退而在最后一个逗号/空白处切分（不切断英文单词），都没有时才硬切
+fn split_into_chunks(text: &str, chunk_chars: usize) -> Vec<&str> {
+    let chunk_chars = chunk_chars.max(1);
+    let mut chunks = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        // 第 chunk_chars 个字符之后的字节位置
+        let limit = rest
+            .char_indices()
+            .nth(chunk_chars)
+            .map_or(rest.len(), |(i, _)| i);
+        let cut = if limit == rest.len() {
+            limit
+        } else {
+            let window = &rest[..limit];
+            break_after(window, is_sentence_end)
+                .or_else(|| break_after(window, is_soft_break))
+                .unwrap_or(limit)
+        };
+        let (chunk, tail) = rest.split_at(cut);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks
+}
+
+/**
+ * 流式分词（适用于超长文本）
+ * 按句子边界切成不超过 chunk_chars 个字符的分片，逐片分词后通过 Channel 推送，
+ * 避免一次性返回巨大的 JSON 数组导致 webview 卡顿；分词在阻塞线程池中进行
+ * mode / exact / hmm 同 segment_text
+ * 返回总词数
+ */
+#[tauri::command]
+pub async fn segment_text_stream(
+    app: AppHandle,
+    text: String,
+    chunk_chars: usize,
+    mode: Option<String>,
+    exact: Option<bool>,
+    hmm: Option<bool>,
+    on_chunk: Channel<SegmentChunk>,
+) -> Result<usize, String> {
+    let mode = text_util::SegmentMode::resolve(mode.as_deref(), exact)?;
+    tokio::task::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let mut total_tokens = 0;
+        let mut index = 0;
+
+        for piece in split_into_chunks(&text, chunk_chars) {
+            let tokens: Vec<String> = {
+                // 每个分片单独加读锁，避免长时间阻塞词典修改
+                let jieba = state
+                    .jieba
+                    .read()
+                    .map_err(|e| format!("lock error: {}", e))?;
+                text_util::cut(&jieba, piece, mode, hmm)
+                    .into_iter()
+                    .map(|s| s.to_string())
+                    .collect()
+            };
+            total_tokens += tokens.len();
+            on_chunk
+                .send(SegmentChunk {
+                    index,
+                    tokens,
+                    done: false,
+                    total_tokens,
+                })
+                .map_err(|e| format!("channel error: {}", e))?;
+            index += 1;
+        }
+
+        on_chunk
+            .send(SegmentChunk {
+                index,
+                tokens: Vec::new(),
+                done: true,
+                total_tokens,
+            })
+            .map_err(|e| format!("channel error: {}", e))?;
+        Ok(total_tokens)
+    })
+    .await
+    .map_err(|e| format!("segment task error: {}", e))?
+}
+
 /**
  * 获取屏幕信息（优化版）
  * 返回所有屏幕的详细信息，包括虚拟桌面的总尺寸
@@ -398,7 +1111,7 @@ pub fn get_display_info() -> Result<MultiScreenInfo, String> {
 }
 
 /// 单屏幕截图结果
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, JsonSchema)]
 pub struct ScreenCapture {
     pub id: u32,
     pub x: i32,
@@ -407,38 +1120,496 @@ pub struct ScreenCapture {
     pub height: u32,
     pub scale_factor: f32,
     pub is_primary: bool,
-    pub data: Vec<u8>, // PNG 字节数据
+    pub data: Vec<u8>,  // 图片字节数据，编码见 format
+    pub format: String, // "png" | "jpeg" | "webp"
+    // 屏幕原始尺寸（逻辑像素）；width/height 在缩放输出时为缩放后的图片尺寸
+    pub display_width: u32,
+    pub display_height: u32,
+    // 不影响结果的提示（如 hide_windows 中不存在的窗口）
+    pub warnings: Vec<String>,
+    // keep_capture 时保留的截图 id，供 crop_capture / release_capture 使用
+    pub capture_id: Option<String>,
+    // keep_capture 时解码出的原始像素，由命令登记后清空
+    #[serde(skip)]
+    pub pixels: Option<image::RgbaImage>,
 }
 
-/// 多屏幕截图结果
-#[derive(Serialize)]
-pub struct MultiScreenCapture {
-    pub screens: Vec<ScreenCapture>,
-    pub virtual_x: i32,
-    pub virtual_y: i32,
-    pub virtual_width: u32,
-    pub virtual_height: u32,
+/// 截图输出格式
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CaptureFormat {
+    Png,
+    Jpeg,
+    Webp,
+}
+
+impl CaptureFormat {
+    /// 解析 format 参数，为空时默认 PNG，无法识别时报错
+    pub fn parse(format: Option<&str>) -> Result<Self, String> {
+        match format.map(|f| f.to_ascii_lowercase()).as_deref() {
+            None | Some("png") => Ok(CaptureFormat::Png),
+            Some("jpeg") | Some("jpg") => Ok(CaptureFormat::Jpeg),
+            Some("webp") => Ok(CaptureFormat::Webp),
+            Some(other) => Err(format!("unsupported image format: {}", other)),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CaptureFormat::Png => "png",
+            CaptureFormat::Jpeg => "jpeg",
+            CaptureFormat::Webp => "webp",
+        }
+    }
+}
+
+/// 有损格式的默认质量
+const DEFAULT_QUALITY: u8 = 80;
+
+/// 按指定格式编码 RGBA 图像；quality 限制在 1..=100，仅对有损格式生效
+pub fn encode_rgba(
+    image: &image::RgbaImage,
+    format: CaptureFormat,
+    quality: Option<u8>,
+) -> Result<Vec<u8>, String> {
+    let quality = quality.unwrap_or(DEFAULT_QUALITY).clamp(1, 100);
+    let mut out = Vec::new();
+    let res = match format {
+        CaptureFormat::Png => image.write_to(
+            &mut std::io::Cursor::new(&mut out),
+            image::ImageOutputFormat::Png,
+        ),
+        CaptureFormat::Jpeg => {
+            // JPEG 不支持透明通道
+            let rgb = image::DynamicImage::ImageRgba8(image.clone()).to_rgb8();
+            image::codecs::jpeg::JpegEncoder::new_with_quality(&mut out, quality).encode_image(&rgb)
+        }
+        // image 0.24 把有损 WebP（libwebp）标记为弃用，但 quality 参数依赖它
+        #[allow(deprecated)]
+        CaptureFormat::Webp => image::codecs::webp::WebPEncoder::new_with_quality(
+            &mut out,
+            image::codecs::webp::WebPQuality::lossy(quality),
+        )
+        .encode(
+            image.as_raw(),
+            image.width(),
+            image.height(),
+            image::ColorType::Rgba8,
+        ),
+    };
+    res.map_err(|e| format!("encode error: {}", e))?;
+    Ok(out)
+}
+
+/// 编码后的图像数据及缩放后的尺寸（未缩放时为 None）
+type Rendered = (Vec<u8>, Option<(u32, u32)>);
+
+/// 截图输出参数（格式、质量、尺寸上限）
+pub struct ImageOutput {
+    pub format: CaptureFormat,
+    pub quality: Option<u8>,
+    pub max_width: Option<u32>,
+    pub max_height: Option<u32>,
+    /// 为 true 时 ScreenCapture 带上解码后的原始像素（keep_capture）
+    pub keep_pixels: bool,
+    /// 按比例缩小原图（target_scale），与尺寸上限同时设置时取较小的结果；不放大
+    pub downscale: Option<f64>,
+}
+
+impl ImageOutput {
+    pub fn new(
+        format: Option<&str>,
+        quality: Option<u8>,
+        max_width: Option<u32>,
+        max_height: Option<u32>,
+    ) -> Result<Self, String> {
+        if max_width == Some(0) || max_height == Some(0) {
+            return Err("max_width / max_height must be greater than 0".into());
+        }
+        Ok(ImageOutput {
+            format: CaptureFormat::parse(format)?,
+            quality,
+            max_width,
+            max_height,
+            keep_pixels: false,
+            downscale: None,
+        })
+    }
+
+    /// 按比例缩小到尺寸上限内的目标尺寸，不需要缩小时返回 None
+    fn fit(&self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let sx = self.max_width.map_or(1.0, |m| m as f64 / width as f64);
+        let sy = self.max_height.map_or(1.0, |m| m as f64 / height as f64);
+        let scale = sx.min(sy).min(self.downscale.unwrap_or(1.0));
+        if scale >= 1.0 {
+            return None;
+        }
+        Some((
+            ((width as f64 * scale).round() as u32).max(1),
+            ((height as f64 * scale).round() as u32).max(1),
+        ))
+    }
+
+    /// 编码 RGBA 图像（必要时先用 Lanczos3 缩小），返回数据及缩放后的尺寸
+    pub fn render_rgba(&self, image: &image::RgbaImage) -> Result<Rendered, String> {
+        match self.fit(image.width(), image.height()) {
+            Some((w, h)) => {
+                let resized =
+                    image::imageops::resize(image, w, h, image::imageops::FilterType::Lanczos3);
+                Ok((
+                    encode_rgba(&resized, self.format, self.quality)?,
+                    Some((w, h)),
+                ))
+            }
+            None => Ok((encode_rgba(image, self.format, self.quality)?, None)),
+        }
+    }
+
+    /// 处理截图库返回的 PNG；未设置尺寸上限且输出 PNG 时原样返回
+    pub fn render(&self, png: &[u8]) -> Result<Rendered, String> {
+        if self.max_width.is_none() && self.max_height.is_none() && self.downscale.is_none() {
+            return Ok((encode_capture(png, self.format, self.quality)?, None));
+        }
+        let rgba = image::load_from_memory(png)
+            .map_err(|e| format!("decode error: {}", e))?
+            .to_rgba8();
+        self.render_rgba(&rgba)
+    }
+}
+
+/// 处理截图数据；include_cursor 时先把鼠标指针画到 (x, y, width, height) 区域对应的图像上
+fn render_capture(
+    png: &[u8],
+    output: &ImageOutput,
+    (x, y, width, height): (i32, i32, u32, u32),
+    include_cursor: bool,
+) -> Result<Rendered, String> {
+    if !include_cursor {
+        return output.render(png);
+    }
+    let mut rgba = image::load_from_memory(png)
+        .map_err(|e| format!("decode error: {}", e))?
+        .to_rgba8();
+    cursor::composite_cursor(&mut rgba, x, y, width, height);
+    output.render_rgba(&rgba)
+}
+
+/// 把截图结果记入截图历史（未启用时不做任何事）
+fn remember(app: &AppHandle, source: &str, capture: &ScreenCapture) {
+    history::record(
+        app,
+        history::Captured {
+            source,
+            screen_id: Some(capture.id),
+            width: capture.width,
+            height: capture.height,
+            format: &capture.format,
+            data: &capture.data,
+        },
+    );
+}
+
+/// keep_capture 时把解码出的像素登记到 AppState，并填入 capture_id
+fn keep(app: &AppHandle, capture: &mut ScreenCapture) {
+    let Some(pixels) = capture.pixels.take() else {
+        return;
+    };
+    match app.state::<AppState>().kept_captures.lock() {
+        Ok(mut kept) => capture.capture_id = Some(kept.insert(pixels)),
+        Err(e) => capture
+            .warnings
+            .push(format!("could not keep capture: {}", e)),
+    }
+}
+
+/// 根据屏幕信息和截图数据生成 ScreenCapture
+fn screen_capture(
+    d: &screenshots::DisplayInfo,
+    png: &[u8],
+    output: &ImageOutput,
+    include_cursor: bool,
+) -> Result<ScreenCapture, String> {
+    let region = (d.x, d.y, d.width, d.height);
+    let (data, resized, pixels) = if output.keep_pixels {
+        let mut rgba = image::load_from_memory(png)
+            .map_err(|e| format!("decode error: {}", e))?
+            .to_rgba8();
+        if include_cursor {
+            cursor::composite_cursor(&mut rgba, d.x, d.y, d.width, d.height);
+        }
+        let (data, resized) = output.render_rgba(&rgba)?;
+        (data, resized, Some(rgba))
+    } else {
+        let (data, resized) = render_capture(png, output, region, include_cursor)?;
+        (data, resized, None)
+    };
+    let (width, height) = resized.unwrap_or((d.width, d.height));
+    Ok(ScreenCapture {
+        id: d.id,
+        x: d.x,
+        y: d.y,
+        width,
+        height,
+        scale_factor: d.scale_factor,
+        is_primary: d.is_primary,
+        data,
+        format: output.format.as_str().into(),
+        display_width: d.width,
+        display_height: d.height,
+        warnings: Vec::new(),
+        capture_id: None,
+        pixels,
+    })
+}
+
+/// 把截图库返回的 PNG 字节转换为目标格式（PNG 直接返回，不重新编码）
+pub fn encode_capture(
+    png: &[u8],
+    format: CaptureFormat,
+    quality: Option<u8>,
+) -> Result<Vec<u8>, String> {
+    if format == CaptureFormat::Png {
+        return Ok(png.to_vec());
+    }
+    let rgba = image::load_from_memory(png)
+        .map_err(|e| format!("decode error: {}", e))?
+        .to_rgba8();
+    encode_rgba(&rgba, format, quality)
+}
+
+/// 列出屏幕；失败或没有屏幕时返回截图后端的原始错误
+fn all_screens() -> Result<Vec<Screen>, String> {
+    match Screen::all() {
+        Ok(screens) if !screens.is_empty() => Ok(screens),
+        Ok(_) => Err("No screens found".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// portal 降级得到的整张桌面截图（按单个屏幕返回，坐标原点为 0,0，不缩放）
+fn desktop_capture(
+    image: &image::RgbaImage,
+    output: &ImageOutput,
+) -> Result<ScreenCapture, String> {
+    let (data, resized) = output.render_rgba(image)?;
+    let (width, height) = resized.unwrap_or(image.dimensions());
+    Ok(ScreenCapture {
+        id: 0,
+        x: 0,
+        y: 0,
+        width,
+        height,
+        scale_factor: 1.0,
+        is_primary: true,
+        data,
+        format: output.format.as_str().into(),
+        display_width: image.width(),
+        display_height: image.height(),
+        warnings: Vec::new(),
+        capture_id: None,
+        pixels: output.keep_pixels.then(|| image.clone()),
+    })
+}
+
+/// 多屏幕截图结果
+#[derive(Serialize)]
+pub struct MultiScreenCapture {
+    pub screens: Vec<ScreenCapture>,
+    pub virtual_x: i32,
+    pub virtual_y: i32,
+    pub virtual_width: u32,
+    pub virtual_height: u32,
+    /// 不影响结果的提示（如请求的屏幕 id 不存在）
+    pub warnings: Vec<String>,
 }
 
 /**
  * 高性能多屏幕截图（返回PNG字节数组）
- * 并行捕获所有屏幕，避免base64编码开销
+ * 捕获所有屏幕，避免base64编码开销
+ * format: "png"（默认）| "jpeg" | "webp"；quality: 有损格式质量 1-100
+ * max_width / max_height: 按比例缩小到该尺寸内（不放大），不传则保持原图
+ * parallel: 为 true 时各屏幕在各自线程上同时截取（多屏时更快），结果仍按屏幕顺序返回
+ * screen_ids: 只截取这些屏幕；exclude_ids: 跳过这些屏幕（如显示敏感信息的屏幕）
+ * 指定了选择时虚拟桌面边界只按选中的屏幕计算；不存在的 id 记录在 warnings 中，
+ * 一个屏幕都没选中时返回错误
+ * 截图后端不可用时（如 Wayland 缺少 portal）返回 { kind: "captureUnsupported", message, hint }；
+ * 其他错误同样为 CommandError（如屏幕不存在时 kind 为 "notFound"）
+ * hide_windows: 截图期间临时隐藏的本应用窗口 label，截图后（包括失败时）恢复显示和焦点；
+ * 不存在的 label 记录在 warnings 中
+ * keep_capture: 为 true 时在内存中保留各屏幕的原始像素并返回 capture_id，
+ * 之后可用 crop_capture 从同一张截图裁剪选区（见 capture_store）
  */
 #[tauri::command]
-pub fn capture_all_screens() -> Result<MultiScreenCapture, String> {
-    let screens = Screen::all().map_err(|e| e.to_string())?;
+#[allow(clippy::too_many_arguments)]
+pub fn capture_all_screens(
+    app: AppHandle,
+    format: Option<String>,
+    quality: Option<u8>,
+    max_width: Option<u32>,
+    max_height: Option<u32>,
+    parallel: Option<bool>,
+    screen_ids: Option<Vec<u32>>,
+    exclude_ids: Option<Vec<u32>>,
+    hide_windows: Option<Vec<String>>,
+    keep_capture: Option<bool>,
+) -> Result<MultiScreenCapture, CommandError> {
+    let (hidden, hide_warnings) = HiddenWindows::hide(&app, hide_windows.as_deref());
+    let mut capture = capture_screens(
+        format,
+        quality,
+        max_width,
+        max_height,
+        parallel,
+        screen_ids,
+        exclude_ids,
+        keep_capture.unwrap_or(false),
+    )?;
+    drop(hidden);
+    capture.warnings.extend(hide_warnings);
+    for screen in &mut capture.screens {
+        keep(&app, screen);
+        remember(&app, "capture_all_screens", screen);
+    }
+    Ok(capture)
+}
+
+/// capture_all_screens 的实现（不记录截图历史）
+#[allow(clippy::too_many_arguments)]
+fn capture_screens(
+    format: Option<String>,
+    quality: Option<u8>,
+    max_width: Option<u32>,
+    max_height: Option<u32>,
+    parallel: Option<bool>,
+    screen_ids: Option<Vec<u32>>,
+    exclude_ids: Option<Vec<u32>>,
+    keep_pixels: bool,
+) -> Result<MultiScreenCapture, CommandError> {
+    let mut output = ImageOutput::new(format.as_deref(), quality, max_width, max_height)?;
+    output.keep_pixels = keep_pixels;
+    let screens = match all_screens() {
+        Ok(screens) => screens,
+        Err(raw) => {
+            let selected = screen_ids.is_some() || exclude_ids.is_some();
+            return capture_backend::fallback(raw, |image| {
+                let capture = desktop_capture(&image, &output)?;
+                let mut warnings = Vec::new();
+                if selected {
+                    warnings.push("screen selection is ignored by the portal fallback".into());
+                }
+                Ok(MultiScreenCapture {
+                    virtual_x: 0,
+                    virtual_y: 0,
+                    virtual_width: capture.display_width,
+                    virtual_height: capture.display_height,
+                    screens: vec![capture],
+                    warnings,
+                })
+            });
+        }
+    };
+
+    let (screens, warnings) = select_screens(screens, screen_ids, exclude_ids)?;
+    let (virtual_x, virtual_y, virtual_width, virtual_height) = virtual_bounds(&screens);
+
+    let capture_one = |screen: &Screen| {
+        let d = screen.display_info;
+        screen
+            .capture()
+            .map_err(|e| e.to_string())
+            .and_then(|image| screen_capture(&d, image.buffer(), &output, false))
+    };
+
+    // 默认串行捕获（保证稳定性）；parallel 时每个屏幕一个线程，
+    // 已知并行有问题的后端上静默退回串行
+    let parallel = parallel.unwrap_or(false)
+        && screens.len() > 1
+        && capture_backend::supports_parallel_capture();
+    let results: Vec<Result<ScreenCapture, String>> = if parallel {
+        thread::scope(|scope| {
+            let handles: Vec<_> = screens
+                .iter()
+                .map(|screen| scope.spawn(move || capture_one(screen)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| {
+                    h.join()
+                        .unwrap_or_else(|_| Err("capture thread panicked".to_string()))
+                })
+                .collect()
+        })
+    } else {
+        screens.iter().map(capture_one).collect()
+    };
+
+    // 按屏幕顺序汇总，单个屏幕失败时跳过
+    let mut captures: Vec<ScreenCapture> = Vec::with_capacity(screens.len());
+    for (screen, result) in screens.iter().zip(results) {
+        match result {
+            Ok(capture) => {
+                captures.push(capture);
+            }
+            Err(e) => {
+                eprintln!(
+                    "[capture_all_screens] screen {} failed: {}",
+                    screen.display_info.id, e
+                );
+                // 继续捕获其他屏幕
+            }
+        }
+    }
+
+    if captures.is_empty() {
+        return Err("Failed to capture any screen".into());
+    }
+
+    Ok(MultiScreenCapture {
+        screens: captures,
+        virtual_x,
+        virtual_y,
+        virtual_width,
+        virtual_height,
+        warnings,
+    })
+}
 
-    if screens.is_empty() {
-        return Err("No screens found".to_string());
+/// 按 screen_ids（白名单）和 exclude_ids 筛选屏幕，返回选中的屏幕及不存在 id 的警告
+fn select_screens(
+    screens: Vec<Screen>,
+    screen_ids: Option<Vec<u32>>,
+    exclude_ids: Option<Vec<u32>>,
+) -> Result<(Vec<Screen>, Vec<String>), String> {
+    let exists = |id: &u32| screens.iter().any(|s| s.display_info.id == *id);
+    let warnings: Vec<String> = screen_ids
+        .iter()
+        .chain(exclude_ids.iter())
+        .flatten()
+        .filter(|id| !exists(id))
+        .map(|id| format!("screen {} not found", id))
+        .collect();
+
+    let selected: Vec<Screen> = screens
+        .into_iter()
+        .filter(|s| {
+            let id = s.display_info.id;
+            screen_ids.as_ref().is_none_or(|ids| ids.contains(&id))
+                && !exclude_ids.as_ref().is_some_and(|ids| ids.contains(&id))
+        })
+        .collect();
+    if selected.is_empty() {
+        return Err("no screens selected".into());
     }
+    Ok((selected, warnings))
+}
 
-    // 计算虚拟桌面边界
+/// 计算虚拟桌面边界（全局逻辑坐标）：(x, y, width, height)
+fn virtual_bounds(screens: &[Screen]) -> (i32, i32, u32, u32) {
     let mut min_x = i32::MAX;
     let mut min_y = i32::MAX;
     let mut max_x = i32::MIN;
     let mut max_y = i32::MIN;
 
-    for screen in &screens {
+    for screen in screens {
         let d = screen.display_info;
         min_x = min_x.min(d.x);
         min_y = min_y.min(d.y);
@@ -446,115 +1617,1353 @@ pub fn capture_all_screens() -> Result<MultiScreenCapture, String> {
         max_y = max_y.max(d.y + d.height as i32);
     }
 
-    // 串行捕获（保证稳定性，screenshots crate 在某些平台上并行可能有问题）
-    let mut captures: Vec<ScreenCapture> = Vec::with_capacity(screens.len());
+    (min_x, min_y, (max_x - min_x) as u32, (max_y - min_y) as u32)
+}
 
-    for screen in screens {
-        let d = screen.display_info;
-        match screen.capture() {
-            Ok(image) => {
-                captures.push(ScreenCapture {
-                    id: d.id,
-                    x: d.x,
-                    y: d.y,
-                    width: d.width,
-                    height: d.height,
-                    scale_factor: d.scale_factor,
-                    is_primary: d.is_primary,
-                    data: image.buffer().to_vec(), // PNG 格式
-                });
-            }
-            Err(e) => {
-                eprintln!("[capture_all_screens] screen {} failed: {}", d.id, e);
-                // 继续捕获其他屏幕
-            }
+/// 整个虚拟桌面合成的一张截图
+#[derive(Serialize)]
+pub struct VirtualDesktopCapture {
+    /// PNG 字节，尺寸为 virtual_width x virtual_height（逻辑像素）
+    pub data: Vec<u8>,
+    pub virtual_x: i32,
+    pub virtual_y: i32,
+    pub virtual_width: u32,
+    pub virtual_height: u32,
+}
+
+/**
+ * 截取整个虚拟桌面并合成为一张图（逻辑分辨率）
+ * 每个屏幕放在 (d.x - virtual_x, d.y - virtual_y)，高 DPI 屏幕的截图缩放到逻辑尺寸，
+ * 混合 DPI 时前端无需自行拼接；屏幕之间的空隙为透明
+ * 需要各屏幕原始数据时使用 capture_all_screens
+ * 截图后端不可用时同 capture_all_screens
+ */
+#[tauri::command]
+pub fn capture_virtual_desktop(app: AppHandle) -> Result<VirtualDesktopCapture, CommandError> {
+    let capture = virtual_desktop()?;
+    history::record(
+        &app,
+        history::Captured {
+            source: "capture_virtual_desktop",
+            screen_id: None,
+            width: capture.virtual_width,
+            height: capture.virtual_height,
+            format: "png",
+            data: &capture.data,
+        },
+    );
+    Ok(capture)
+}
+
+/// capture_virtual_desktop 的实现（不记录截图历史）
+fn virtual_desktop() -> Result<VirtualDesktopCapture, CommandError> {
+    let output = ImageOutput::new(None, None, None, None)?;
+    let screens = match all_screens() {
+        Ok(screens) => screens,
+        Err(raw) => {
+            return capture_backend::fallback(raw, |image| {
+                let (data, _) = output.render_rgba(&image)?;
+                Ok(VirtualDesktopCapture {
+                    data,
+                    virtual_x: 0,
+                    virtual_y: 0,
+                    virtual_width: image.width(),
+                    virtual_height: image.height(),
+                })
+            });
         }
-    }
+    };
+    let (virtual_x, virtual_y, virtual_width, virtual_height) = virtual_bounds(&screens);
+    let all: Vec<&Screen> = screens.iter().collect();
+    let canvas = stitch(
+        &all,
+        virtual_x,
+        virtual_y,
+        virtual_width,
+        virtual_height,
+        1.0,
+    )?;
+    let (data, _) = output.render_rgba(&canvas)?;
+    Ok(VirtualDesktopCapture {
+        data,
+        virtual_x,
+        virtual_y,
+        virtual_width,
+        virtual_height,
+    })
+}
 
-    if captures.is_empty() {
-        return Err("Failed to capture any screen".to_string());
+/// 二进制通道中每张截图前发送的 JSON 头（图片数据随后以原始字节单独发送）
+#[derive(Serialize, Clone)]
+pub struct ScreenCaptureHeader {
+    pub index: usize,
+    pub id: u32,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f32,
+    pub is_primary: bool,
+    pub format: String,
+    pub display_width: u32,
+    pub display_height: u32,
+    /// 随后的二进制消息长度
+    pub byte_length: usize,
+}
+
+impl ScreenCaptureHeader {
+    fn split(index: usize, capture: ScreenCapture) -> (Self, Vec<u8>) {
+        let header = ScreenCaptureHeader {
+            index,
+            id: capture.id,
+            x: capture.x,
+            y: capture.y,
+            width: capture.width,
+            height: capture.height,
+            scale_factor: capture.scale_factor,
+            is_primary: capture.is_primary,
+            format: capture.format,
+            display_width: capture.display_width,
+            display_height: capture.display_height,
+            byte_length: capture.data.len(),
+        };
+        (header, capture.data)
     }
+}
 
-    Ok(MultiScreenCapture {
-        screens: captures,
-        virtual_x: min_x,
-        virtual_y: min_y,
-        virtual_width: (max_x - min_x) as u32,
-        virtual_height: (max_y - min_y) as u32,
+/// capture_all_screens_stream 的返回值：屏幕数量与虚拟桌面边界
+#[derive(Serialize)]
+pub struct CaptureStreamSummary {
+    pub screens: usize,
+    pub virtual_x: i32,
+    pub virtual_y: i32,
+    pub virtual_width: u32,
+    pub virtual_height: u32,
+    pub warnings: Vec<String>,
+}
+
+/**
+ * 多屏幕截图，通过二进制 Channel 返回（避免 Vec<u8> 被序列化成 JSON 数字数组）
+ * 每个屏幕先发送一条 JSON 消息（ScreenCaptureHeader），紧接着发送一条原始字节消息
+ * （前端收到 ArrayBuffer），两者按顺序成对出现
+ * 参数同 capture_all_screens；旧命令保留以兼容
+ */
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn capture_all_screens_stream(
+    app: AppHandle,
+    format: Option<String>,
+    quality: Option<u8>,
+    max_width: Option<u32>,
+    max_height: Option<u32>,
+    parallel: Option<bool>,
+    screen_ids: Option<Vec<u32>>,
+    exclude_ids: Option<Vec<u32>>,
+    on_screen: Channel<InvokeResponseBody>,
+) -> Result<CaptureStreamSummary, CommandError> {
+    let capture = capture_screens(
+        format,
+        quality,
+        max_width,
+        max_height,
+        parallel,
+        screen_ids,
+        exclude_ids,
+        false,
+    )?;
+    let screens = capture.screens.len();
+    for (index, screen) in capture.screens.into_iter().enumerate() {
+        remember(&app, "capture_all_screens_stream", &screen);
+        let (header, data) = ScreenCaptureHeader::split(index, screen);
+        let header =
+            serde_json::to_string(&header).map_err(|e| format!("serialize error: {}", e))?;
+        on_screen
+            .send(InvokeResponseBody::Json(header))
+            .and_then(|_| on_screen.send(InvokeResponseBody::Raw(data)))
+            .map_err(|e| format!("channel error: {}", e))?;
+    }
+    Ok(CaptureStreamSummary {
+        screens,
+        virtual_x: capture.virtual_x,
+        virtual_y: capture.virtual_y,
+        virtual_width: capture.virtual_width,
+        virtual_height: capture.virtual_height,
+        warnings: capture.warnings,
     })
 }
 
 /**
  * 单屏幕截图（根据屏幕ID）
  * 返回 PNG 字节数组，避免 base64 开销
+ * format / quality / max_width / max_height 同 capture_all_screens
+ * include_cursor: 在截图上绘制鼠标指针（默认不绘制）
+ * hide_windows / keep_capture 同 capture_all_screens
+ * x / y / width / height: 只截取屏幕内的这一区域（相对屏幕左上角的物理像素），四个参数需同时传入；
+ * 超出屏幕的部分被裁掉，返回的 x / y / width / height 为实际截取的区域，
+ * id / scale_factor / is_primary / display_width / display_height 仍为该屏幕的信息
+ * target_scale: 编码前按该比例缩小截图（Lanczos3），如 2x 屏幕传 0.5 得到逻辑像素大小的图片；
+ * 大于等于 1 时不缩放，与 max_width / max_height 同时传入时取较小的结果；
+ * 返回的 width / height 为实际输出的图片尺寸。不传时保持原始分辨率
+ * 截图后端不可用时同 capture_all_screens（降级到 portal 时区域按整个桌面的像素坐标裁剪）
  */
 #[tauri::command]
-pub fn capture_screen_by_id(screen_id: u32) -> Result<ScreenCapture, String> {
-    let screens = Screen::all().map_err(|e| e.to_string())?;
+#[allow(clippy::too_many_arguments)]
+pub fn capture_screen_by_id(
+    app: AppHandle,
+    screen_id: u32,
+    format: Option<String>,
+    quality: Option<u8>,
+    max_width: Option<u32>,
+    max_height: Option<u32>,
+    include_cursor: Option<bool>,
+    hide_windows: Option<Vec<String>>,
+    keep_capture: Option<bool>,
+    x: Option<i32>,
+    y: Option<i32>,
+    width: Option<i32>,
+    height: Option<i32>,
+    target_scale: Option<f32>,
+) -> Result<ScreenCapture, CommandError> {
+    let area = sub_area(x, y, width, height)?;
+    if target_scale.is_some_and(|s| !s.is_finite() || s <= 0.0) {
+        return Err("target_scale must be greater than 0".to_string().into());
+    }
+    let (hidden, hide_warnings) = HiddenWindows::hide(&app, hide_windows.as_deref());
+    let mut capture = screen_by_id(
+        screen_id,
+        format,
+        quality,
+        max_width,
+        max_height,
+        include_cursor,
+        keep_capture.unwrap_or(false),
+        area,
+        target_scale,
+    )?;
+    drop(hidden);
+    capture.warnings = hide_warnings;
+    keep(&app, &mut capture);
+    remember(&app, "capture_screen_by_id", &capture);
+    Ok(capture)
+}
 
-    let screen = screens
+/// 屏幕内的子区域 (x, y, width, height)，相对屏幕左上角的物理像素
+pub type SubArea = (i32, i32, u32, u32);
+
+/// 校验 capture_screen_by_id 的区域参数，四个参数都不传时返回 None（截取整个屏幕）
+pub fn sub_area(
+    x: Option<i32>,
+    y: Option<i32>,
+    width: Option<i32>,
+    height: Option<i32>,
+) -> Result<Option<SubArea>, String> {
+    match (x, y, width, height) {
+        (None, None, None, None) => Ok(None),
+        (Some(x), Some(y), Some(width), Some(height)) => {
+            if width <= 0 || height <= 0 {
+                return Err(format!(
+                    "area width and height must be greater than 0, got {}x{}",
+                    width, height
+                ));
+            }
+            Ok(Some((x, y, width as u32, height as u32)))
+        }
+        _ => Err("x, y, width and height must be given together".into()),
+    }
+}
+
+/// 把屏幕内的物理像素区域换算为全局逻辑坐标（向外取整）并裁剪到屏幕边界
+/// 区域完全在屏幕外时返回 None
+fn sub_area_on_display(
+    (dx, dy, dw, dh): (i32, i32, u32, u32),
+    scale_factor: f32,
+    (x, y, width, height): SubArea,
+) -> Option<(i32, i32, u32, u32)> {
+    let scale = if scale_factor > 0.0 {
+        scale_factor as f64
+    } else {
+        1.0
+    };
+    let to_logical = |v: i64, max: u32| (v as f64 / scale).clamp(0.0, max as f64);
+    let left = to_logical(x as i64, dw).floor() as u32;
+    let top = to_logical(y as i64, dh).floor() as u32;
+    let right = to_logical(x as i64 + width as i64, dw).ceil() as u32;
+    let bottom = to_logical(y as i64 + height as i64, dh).ceil() as u32;
+    if right <= left || bottom <= top {
+        return None;
+    }
+    Some((
+        dx + left as i32,
+        dy + top as i32,
+        right - left,
+        bottom - top,
+    ))
+}
+
+/// portal 降级时按整个桌面的像素坐标裁剪子区域
+fn crop_desktop(
+    image: image::RgbaImage,
+    area: Option<SubArea>,
+) -> Result<image::RgbaImage, String> {
+    let Some((x, y, width, height)) = area else {
+        return Ok(image);
+    };
+    let (left, top) = (x.max(0) as u32, y.max(0) as u32);
+    let w = width.min(image.width().saturating_sub(left));
+    let h = height.min(image.height().saturating_sub(top));
+    if w == 0 || h == 0 {
+        return Err("area is outside of the desktop".to_string());
+    }
+    Ok(image::imageops::crop_imm(&image, left, top, w, h).to_image())
+}
+
+/// capture_screen_by_id 的实现（不记录截图历史）
+#[allow(clippy::too_many_arguments)]
+fn screen_by_id(
+    screen_id: u32,
+    format: Option<String>,
+    quality: Option<u8>,
+    max_width: Option<u32>,
+    max_height: Option<u32>,
+    include_cursor: Option<bool>,
+    keep_pixels: bool,
+    area: Option<SubArea>,
+    target_scale: Option<f32>,
+) -> Result<ScreenCapture, CommandError> {
+    let mut output = ImageOutput::new(format.as_deref(), quality, max_width, max_height)?;
+    output.keep_pixels = keep_pixels;
+    output.downscale = target_scale.map(f64::from);
+    let portal = |image| desktop_capture(&crop_desktop(image, area)?, &output);
+    let screens = match all_screens() {
+        Ok(screens) => screens,
+        Err(raw) => return capture_backend::fallback(raw, portal),
+    };
+
+    let screen = find_screen(screens, screen_id)?;
+    let d = screen.display_info;
+    let (png, region) = match grab_screen(&screen, area) {
+        Ok(captured) => captured,
+        Err(e) => return capture_backend::fallback(e, portal),
+    };
+
+    let mut capture = screen_capture(&region, &png, &output, include_cursor.unwrap_or(false))?;
+    capture.display_width = d.width;
+    capture.display_height = d.height;
+    Ok(capture)
+}
+
+fn find_screen(screens: Vec<Screen>, screen_id: u32) -> Result<Screen, CommandError> {
+    screens
         .into_iter()
         .find(|s| s.display_info.id == screen_id)
-        .ok_or_else(|| format!("Screen {} not found", screen_id))?;
+        .ok_or_else(|| CommandError::NotFound(format!("Screen {} not found", screen_id)))
+}
 
+/// 截取整个屏幕或屏幕内的子区域，返回 PNG 和实际截取区域的屏幕信息
+/// （截取子区域时坐标、尺寸为实际截取的区域，其他屏幕信息不变）
+fn grab_screen(
+    screen: &Screen,
+    area: Option<SubArea>,
+) -> Result<(Vec<u8>, screenshots::DisplayInfo), String> {
     let d = screen.display_info;
-    let image = screen.capture().map_err(|e| e.to_string())?;
+    let Some(area) = area else {
+        return screen
+            .capture()
+            .map(|image| (image.buffer().to_vec(), d))
+            .map_err(|e| e.to_string());
+    };
+    let (x, y, width, height) =
+        sub_area_on_display((d.x, d.y, d.width, d.height), d.scale_factor, area)
+            .ok_or_else(|| format!("area is outside of screen {}", d.id))?;
+    let (png, (x, y, w, h)) = capture_screen_area(screen, x, y, width, height)?;
+    let mut region = d;
+    (region.x, region.y, region.width, region.height) = (x, y, w, h);
+    Ok((png, region))
+}
 
-    Ok(ScreenCapture {
-        id: d.id,
-        x: d.x,
-        y: d.y,
-        width: d.width,
-        height: d.height,
-        scale_factor: d.scale_factor,
-        is_primary: d.is_primary,
-        data: image.buffer().to_vec(),
-    })
+/// 截取屏幕或屏幕内子区域的原始像素（不编码），参数和降级同 capture_screen_by_id
+pub fn screen_rgba(
+    screen_id: u32,
+    area: Option<SubArea>,
+) -> Result<image::RgbaImage, CommandError> {
+    let screens = match all_screens() {
+        Ok(screens) => screens,
+        Err(raw) => return capture_backend::fallback(raw, |image| crop_desktop(image, area)),
+    };
+    let screen = find_screen(screens, screen_id)?;
+    match grab_screen(&screen, area) {
+        Ok((png, _)) => Ok(image::load_from_memory(&png)?.into_rgba8()),
+        Err(e) => capture_backend::fallback(e, |image| crop_desktop(image, area)),
+    }
 }
 
 /**
  * 根据鼠标位置截取当前屏幕
  * 返回 PNG 字节数组
+ * format / quality / max_width / max_height 同 capture_all_screens
+ * include_cursor / hide_windows / keep_capture 同 capture_screen_by_id
+ * 截图后端不可用时同 capture_all_screens
  */
 #[tauri::command]
-pub fn capture_screen_at_point(x: i32, y: i32) -> Result<ScreenCapture, String> {
+#[allow(clippy::too_many_arguments)]
+pub fn capture_screen_at_point(
+    app: AppHandle,
+    x: i32,
+    y: i32,
+    format: Option<String>,
+    quality: Option<u8>,
+    max_width: Option<u32>,
+    max_height: Option<u32>,
+    include_cursor: Option<bool>,
+    hide_windows: Option<Vec<String>>,
+    keep_capture: Option<bool>,
+) -> Result<ScreenCapture, CommandError> {
+    let options = CaptureOptions {
+        format,
+        quality,
+        max_width,
+        max_height,
+        include_cursor,
+        hide_windows,
+        keep_capture,
+    };
+    capture_target(
+        &app,
+        CaptureTarget::Point { x, y },
+        &options,
+        "capture_screen_at_point",
+    )
+}
+
+/// capture_screen_at_point 的实现（不记录截图历史）
+#[allow(clippy::too_many_arguments)]
+fn screen_at_point(
+    x: i32,
+    y: i32,
+    format: Option<String>,
+    quality: Option<u8>,
+    max_width: Option<u32>,
+    max_height: Option<u32>,
+    include_cursor: Option<bool>,
+    keep_pixels: bool,
+) -> Result<ScreenCapture, CommandError> {
+    let mut output = ImageOutput::new(format.as_deref(), quality, max_width, max_height)?;
+    output.keep_pixels = keep_pixels;
+    let captured = Screen::from_point(x, y).and_then(|screen| {
+        let image = screen.capture()?;
+        Ok((screen.display_info, image))
+    });
+    let (d, image) = match captured {
+        Ok(captured) => captured,
+        Err(e) => {
+            return capture_backend::fallback(e.to_string(), |image| {
+                desktop_capture(&image, &output)
+            });
+        }
+    };
+
+    Ok(screen_capture(
+        &d,
+        image.buffer(),
+        &output,
+        include_cursor.unwrap_or(false),
+    )?)
+}
+
+/// 区域截图的原始结果：单屏时为 screenshots 返回的 PNG 及实际截取的区域，跨屏拼接或 portal 降级时为 RGBA
+enum AreaImage {
+    Png(Vec<u8>, (i32, i32, u32, u32)),
+    Rgba(image::RgbaImage),
+}
+
+impl AreaImage {
+    fn into_rgba(self, include_cursor: bool) -> Result<image::RgbaImage, String> {
+        match self {
+            AreaImage::Rgba(rgba) => Ok(rgba),
+            AreaImage::Png(png, (x, y, width, height)) => {
+                let mut rgba = image::load_from_memory(&png)
+                    .map_err(|e| format!("decode error: {}", e))?
+                    .to_rgba8();
+                if include_cursor {
+                    cursor::composite_cursor(&mut rgba, x, y, width, height);
+                }
+                Ok(rgba)
+            }
+        }
+    }
+}
+
+/**
+ * 截取区域：跨屏时拼接，单屏时裁剪到屏幕边界，截图后端不可用时降级到 portal
+ * 拼接结果已按 include_cursor 绘制指针；单屏 PNG 由调用方处理
+ */
+fn grab_area(
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    include_cursor: bool,
+) -> Result<AreaImage, CommandError> {
+    let portal_crop = |image: image::RgbaImage| {
+        let (left, top) = (x.max(0) as u32, y.max(0) as u32);
+        let w = width.min(image.width().saturating_sub(left));
+        let h = height.min(image.height().saturating_sub(top));
+        if w == 0 || h == 0 {
+            return Err("area is outside of the desktop".to_string());
+        }
+        let cropped = image::imageops::crop_imm(&image, left, top, w, h).to_image();
+        Ok(AreaImage::Rgba(cropped))
+    };
+    let screens = match all_screens() {
+        Ok(screens) => screens,
+        Err(raw) => return capture_backend::fallback(raw, portal_crop),
+    };
+    let overlapping: Vec<&Screen> = screens
+        .iter()
+        .filter(|s| intersect(&s.display_info, x, y, width, height).is_some())
+        .collect();
+    if overlapping.len() > 1 {
+        let mut stitched = stitch_area(&overlapping, x, y, width, height)?;
+        if include_cursor {
+            cursor::composite_cursor(&mut stitched, x, y, width, height);
+        }
+        return Ok(AreaImage::Rgba(stitched));
+    }
+
     let screen = Screen::from_point(x, y).map_err(|e| e.to_string())?;
+    match capture_screen_area(&screen, x, y, width, height) {
+        Ok((png, region)) => Ok(AreaImage::Png(png, region)),
+        Err(e) => capture_backend::fallback(e, portal_crop),
+    }
+}
+
+/// 区域相对于屏幕 (x, y, width, height) 的坐标，并裁剪到屏幕边界：(rel_x, rel_y, width, height)
+fn screen_relative_area(
+    (dx, dy, dw, dh): (i32, i32, u32, u32),
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+) -> (u32, u32, u32, u32) {
+    let rel_x = (x - dx).max(0) as u32;
+    let rel_y = (y - dy).max(0) as u32;
+    (
+        rel_x,
+        rel_y,
+        width.min(dw.saturating_sub(rel_x)),
+        height.min(dh.saturating_sub(rel_y)),
+    )
+}
+
+/// PNG 数据及实际截取的区域（全局逻辑坐标）
+type AreaPng = (Vec<u8>, (i32, i32, u32, u32));
+
+/// 截取单个屏幕上的区域（全局逻辑坐标，超出屏幕的部分被裁掉），返回 PNG 及实际截取的区域
+fn capture_screen_area(
+    screen: &Screen,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+) -> Result<AreaPng, String> {
     let d = screen.display_info;
-    let image = screen.capture().map_err(|e| e.to_string())?;
+    let (rel_x, rel_y, cap_width, cap_height) =
+        screen_relative_area((d.x, d.y, d.width, d.height), x, y, width, height);
+    let image = screen
+        .capture_area(rel_x as i32, rel_y as i32, cap_width, cap_height)
+        .map_err(|e| e.to_string())?;
+    let region = (
+        d.x + rel_x as i32,
+        d.y + rel_y as i32,
+        cap_width,
+        cap_height,
+    );
+    Ok((image.buffer().to_vec(), region))
+}
 
-    Ok(ScreenCapture {
-        id: d.id,
-        x: d.x,
-        y: d.y,
-        width: d.width,
-        height: d.height,
+/**
+ * 截取指定区域（兼容旧API，但返回PNG字节）
+ * 区域跨越多个屏幕时分别截取各屏幕的重叠部分并拼接，
+ * 输出按所涉屏幕中最大的 scale_factor 缩放，无屏幕覆盖的部分为透明
+ * format / quality / max_width / max_height 同 capture_all_screens
+ * include_cursor 同 capture_screen_by_id
+ * hide_windows 同 capture_all_screens；返回值只有图片字节，不存在的 label 只打印日志
+ * 截图后端不可用时同 capture_all_screens（portal 截图按原点 0,0、不缩放裁剪）
+ */
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn capture_area(
+    app: AppHandle,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    format: Option<String>,
+    quality: Option<u8>,
+    max_width: Option<u32>,
+    max_height: Option<u32>,
+    include_cursor: Option<bool>,
+    hide_windows: Option<Vec<String>>,
+) -> Result<Vec<u8>, CommandError> {
+    let output = ImageOutput::new(format.as_deref(), quality, max_width, max_height)?;
+    let include_cursor = include_cursor.unwrap_or(false);
+    let (hidden, hide_warnings) = HiddenWindows::hide(&app, hide_windows.as_deref());
+    for warning in hide_warnings {
+        eprintln!("[capture_area] {}", warning);
+    }
+    let area = grab_area(x, y, width, height, include_cursor)?;
+    drop(hidden);
+    let (data, (out_width, out_height)) = match area {
+        AreaImage::Png(png, region) => {
+            let (data, resized) = render_capture(&png, &output, region, include_cursor)?;
+            (data, resized.unwrap_or((region.2, region.3)))
+        }
+        AreaImage::Rgba(rgba) => {
+            let (data, resized) = output.render_rgba(&rgba)?;
+            (data, resized.unwrap_or(rgba.dimensions()))
+        }
+    };
+    history::record(
+        &app,
+        history::Captured {
+            source: "capture_area",
+            screen_id: None,
+            width: out_width,
+            height: out_height,
+            format: output.format.as_str(),
+            data: &data,
+        },
+    );
+    Ok(data)
+}
+
+/// 截图目标（延时截图等后端内部调用用）
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum CaptureTarget {
+    /// 按屏幕 id 截全屏
+    Screen { screen_id: u32 },
+    /// 截取某点所在屏幕
+    Point { x: i32, y: i32 },
+    /// 截取区域（全局逻辑坐标）
+    Area {
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    },
+}
+
+/// 截图的公共选项，含义同各截图命令的同名参数；Default 即各命令不传参数时的行为
+#[derive(Default, Clone, Debug)]
+pub struct CaptureOptions {
+    pub format: Option<String>,
+    pub quality: Option<u8>,
+    pub max_width: Option<u32>,
+    pub max_height: Option<u32>,
+    pub include_cursor: Option<bool>,
+    pub hide_windows: Option<Vec<String>>,
+    pub keep_capture: Option<bool>,
+}
+
+/**
+ * 按目标截图并返回完整的截图信息（隐藏窗口、keep_capture、记录截图历史同各截图命令）
+ * source 为截图历史中记录的来源
+ */
+pub fn capture_target(
+    app: &AppHandle,
+    target: CaptureTarget,
+    options: &CaptureOptions,
+    source: &str,
+) -> Result<ScreenCapture, CommandError> {
+    let keep_pixels = options.keep_capture.unwrap_or(false);
+    let (hidden, hide_warnings) = HiddenWindows::hide(app, options.hide_windows.as_deref());
+    let captured = match target {
+        CaptureTarget::Screen { screen_id } => screen_by_id(
+            screen_id,
+            options.format.clone(),
+            options.quality,
+            options.max_width,
+            options.max_height,
+            options.include_cursor,
+            keep_pixels,
+            None,
+            None,
+        ),
+        CaptureTarget::Point { x, y } => screen_at_point(
+            x,
+            y,
+            options.format.clone(),
+            options.quality,
+            options.max_width,
+            options.max_height,
+            options.include_cursor,
+            keep_pixels,
+        ),
+        CaptureTarget::Area {
+            x,
+            y,
+            width,
+            height,
+        } => area_capture(x, y, width, height, options),
+    };
+    drop(hidden);
+    let mut capture = captured?;
+    capture.warnings = hide_warnings;
+    keep(app, &mut capture);
+    remember(app, source, &capture);
+    Ok(capture)
+}
+
+/**
+ * 区域截图的完整结果（capture_area 命令只返回图片字节）
+ * 单屏时 id / scale_factor 等为所在屏幕的信息，x / y / display_width / display_height 为实际截取的区域；
+ * 跨屏拼接或 portal 降级时为请求的区域，id / is_primary 取区域左上角所在的屏幕，
+ * scale_factor 为输出像素与逻辑像素之比
+ */
+fn area_capture(
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    options: &CaptureOptions,
+) -> Result<ScreenCapture, CommandError> {
+    let mut output = ImageOutput::new(
+        options.format.as_deref(),
+        options.quality,
+        options.max_width,
+        options.max_height,
+    )?;
+    output.keep_pixels = options.keep_capture.unwrap_or(false);
+    let include_cursor = options.include_cursor.unwrap_or(false);
+    let area = grab_area(x, y, width, height, include_cursor)?;
+    let origin = Screen::from_point(x, y).map(|s| s.display_info).ok();
+    match area {
+        AreaImage::Png(png, (left, top, w, h)) => {
+            let mut region = origin.ok_or_else(|| format!("no screen at ({}, {})", x, y))?;
+            (region.x, region.y, region.width, region.height) = (left, top, w, h);
+            Ok(screen_capture(&region, &png, &output, include_cursor)?)
+        }
+        AreaImage::Rgba(rgba) => {
+            let mut capture = desktop_capture(&rgba, &output)?;
+            capture.x = x;
+            capture.y = y;
+            capture.display_width = width;
+            capture.display_height = height;
+            capture.scale_factor = rgba.width() as f32 / width as f32;
+            if let Some(d) = origin {
+                capture.id = d.id;
+                capture.is_primary = d.is_primary;
+            }
+            Ok(capture)
+        }
+    }
+}
+
+/// capture_area_logical 的结果
+#[derive(Serialize, Clone, Debug)]
+pub struct LogicalAreaCapture {
+    pub screen_id: u32,
+    pub scale_factor: f32,
+    // 实际截取区域（已裁剪到屏幕边界），webview 逻辑坐标
+    pub logical: Rect,
+    // 对应的物理像素区域
+    pub physical: Rect,
+    // 输出图片尺寸（缩放输出时为缩放后的尺寸）
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+    pub format: String,
+    // 不影响结果的提示（如 hide_windows 中不存在的窗口）
+    pub warnings: Vec<String>,
+}
+
+/// 屏幕 (x, y, width, height) 是否包含逻辑坐标点
+fn display_contains((dx, dy, dw, dh): (i32, i32, u32, u32), x: i32, y: i32) -> bool {
+    let (x, y) = (x as i64, y as i64);
+    x >= dx as i64 && y >= dy as i64 && x < dx as i64 + dw as i64 && y < dy as i64 + dh as i64
+}
+
+/// 把屏幕上的逻辑区域换算为全局物理像素区域（各边按该屏幕的 scale_factor 换算后取整）
+/// 与 DisplayInfo 一致：屏幕的物理原点 = 逻辑原点 × 该屏幕自身的 scale_factor
+fn logical_to_physical(
+    (x, y, width, height): (i32, i32, u32, u32),
+    scale_factor: f32,
+) -> (i32, i32, u32, u32) {
+    let f = scale_factor as f64;
+    let edge = |v: i64| (v as f64 * f).round() as i64;
+    let (left, top) = (edge(x as i64), edge(y as i64));
+    let right = edge(x as i64 + width as i64);
+    let bottom = edge(y as i64 + height as i64);
+    (
+        left as i32,
+        top as i32,
+        (right - left) as u32,
+        (bottom - top) as u32,
+    )
+}
+
+/**
+ * 按 webview 逻辑坐标截取区域
+ * 截图使用的屏幕坐标（DisplayInfo）本身就是逻辑坐标，与 webview 一致：
+ * 先找到包含 (x, y) 的屏幕，超出该屏幕的部分被裁掉（不跨屏拼接），
+ * 再按该屏幕的 scale_factor 换算出物理像素区域；混合 DPI 的多屏环境下每块屏幕分别换算，
+ * 返回值同时给出逻辑和物理区域
+ * format / quality / max_width / max_height 同 capture_all_screens
+ * include_cursor 同 capture_screen_by_id；hide_windows 同 capture_all_screens
+ */
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn capture_area_logical(
+    app: AppHandle,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    format: Option<String>,
+    quality: Option<u8>,
+    max_width: Option<u32>,
+    max_height: Option<u32>,
+    include_cursor: Option<bool>,
+    hide_windows: Option<Vec<String>>,
+) -> Result<LogicalAreaCapture, CommandError> {
+    let output = ImageOutput::new(format.as_deref(), quality, max_width, max_height)?;
+    if width == 0 || height == 0 {
+        return Err("area width and height must be greater than 0".into());
+    }
+    let screens = all_screens()?;
+    let screen = screens
+        .iter()
+        .find(|s| {
+            let d = &s.display_info;
+            display_contains((d.x, d.y, d.width, d.height), x, y)
+        })
+        .ok_or_else(|| {
+            CommandError::NotFound(format!("no screen contains logical point ({}, {})", x, y))
+        })?;
+
+    let d = screen.display_info;
+    let (hidden, warnings) = HiddenWindows::hide(&app, hide_windows.as_deref());
+    let captured = capture_screen_area(screen, x, y, width, height);
+    drop(hidden);
+    let (png, region) = captured?;
+    let (px, py, pw, ph) = logical_to_physical(region, d.scale_factor);
+    let (data, resized) = render_capture(&png, &output, region, include_cursor.unwrap_or(false))?;
+    let (out_width, out_height) = resized.unwrap_or((pw, ph));
+    history::record(
+        &app,
+        history::Captured {
+            source: "capture_area_logical",
+            screen_id: Some(d.id),
+            width: out_width,
+            height: out_height,
+            format: output.format.as_str(),
+            data: &data,
+        },
+    );
+    Ok(LogicalAreaCapture {
+        screen_id: d.id,
         scale_factor: d.scale_factor,
-        is_primary: d.is_primary,
-        data: image.buffer().to_vec(),
+        logical: Rect {
+            x: region.0,
+            y: region.1,
+            width: region.2,
+            height: region.3,
+        },
+        physical: Rect {
+            x: px,
+            y: py,
+            width: pw,
+            height: ph,
+        },
+        width: out_width,
+        height: out_height,
+        data,
+        format: output.format.as_str().into(),
+        warnings,
     })
 }
 
+/// capture_screen_raw / capture_area_raw 响应中像素数据之前的头部长度（字节）
+pub const RAW_HEADER_LEN: usize = 24;
+
+/// 把 RGBA 图像打包为二进制响应：头部（小端）+ 像素
+fn raw_response(
+    rgba: image::RgbaImage,
+    screen_id: u32,
+    scale_factor: f32,
+    x: i32,
+    y: i32,
+) -> Response {
+    let (width, height) = rgba.dimensions();
+    let pixels = rgba.into_raw();
+    let mut body = Vec::with_capacity(RAW_HEADER_LEN + pixels.len());
+    body.extend_from_slice(&width.to_le_bytes());
+    body.extend_from_slice(&height.to_le_bytes());
+    body.extend_from_slice(&scale_factor.to_le_bytes());
+    body.extend_from_slice(&x.to_le_bytes());
+    body.extend_from_slice(&y.to_le_bytes());
+    body.extend_from_slice(&screen_id.to_le_bytes());
+    body.extend_from_slice(&pixels);
+    Response::new(body)
+}
+
 /**
- * 截取指定区域（兼容旧API，但返回PNG字节）
+ * 截取整个屏幕，返回未编码的 RGBA 像素（选区遮罩直接画到 canvas，省去 PNG 编码和前端解码）
+ * 前端收到 ArrayBuffer，前 RAW_HEADER_LEN（24）字节为小端头部：
+ *   0 width u32 | 4 height u32 | 8 scale_factor f32 | 12 x i32 | 16 y i32 | 20 screen_id u32
+ * 之后为 width * height * 4 字节像素：RGBA 顺序、逐行从上到下、行间无填充，
+ * 可直接 new ImageData(new Uint8ClampedArray(buf, 24), width, height)
+ * width/height 为物理像素，canvas 的 CSS 尺寸为其除以 scale_factor
+ * include_cursor / hide_windows 同 capture_screen_by_id；不记录截图历史
  */
 #[tauri::command]
-pub fn capture_area(x: i32, y: i32, width: u32, height: u32) -> Result<Vec<u8>, String> {
-    let screen = Screen::from_point(x, y).map_err(|e| e.to_string())?;
+pub fn capture_screen_raw(
+    app: AppHandle,
+    screen_id: u32,
+    include_cursor: Option<bool>,
+    hide_windows: Option<Vec<String>>,
+) -> Result<Response, CommandError> {
+    // portal 降级时为整张桌面，screen_id 为 0、不缩放
+    let portal = |rgba: image::RgbaImage| Ok(raw_response(rgba, 0, 1.0, 0, 0));
+    let screens = match all_screens() {
+        Ok(screens) => screens,
+        Err(raw) => return capture_backend::fallback(raw, portal),
+    };
+    let screen = screens
+        .into_iter()
+        .find(|s| s.display_info.id == screen_id)
+        .ok_or_else(|| CommandError::NotFound(format!("Screen {} not found", screen_id)))?;
     let d = screen.display_info;
 
-    // 计算相对于屏幕的坐标
-    let rel_x = (x - d.x).max(0) as u32;
-    let rel_y = (y - d.y).max(0) as u32;
+    let (hidden, warnings) = HiddenWindows::hide(&app, hide_windows.as_deref());
+    for warning in warnings {
+        eprintln!("[capture_screen_raw] {}", warning);
+    }
+    let image = match screen.capture() {
+        Ok(image) => image,
+        Err(e) => return capture_backend::fallback(e.to_string(), portal),
+    };
+    drop(hidden);
+
+    // screenshots 只提供 PNG，这里解码一次，之后不再编码
+    let rgba = AreaImage::Png(image.buffer().to_vec(), (d.x, d.y, d.width, d.height))
+        .into_rgba(include_cursor.unwrap_or(false))?;
+    Ok(raw_response(rgba, d.id, d.scale_factor, d.x, d.y))
+}
 
-    // 限制区域不超出屏幕边界
-    let cap_width = width.min(d.width.saturating_sub(rel_x));
-    let cap_height = height.min(d.height.saturating_sub(rel_y));
+/**
+ * 截取区域，返回未编码的 RGBA 像素，格式同 capture_screen_raw
+ * 截取逻辑同 capture_area（跨屏拼接、裁剪到屏幕边界）；头部的 screen_id / scale_factor
+ * 为 (x, y) 所在屏幕的值，x / y 为实际截取区域的左上角
+ */
+#[tauri::command]
+pub fn capture_area_raw(
+    app: AppHandle,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    include_cursor: Option<bool>,
+    hide_windows: Option<Vec<String>>,
+) -> Result<Response, CommandError> {
+    let include_cursor = include_cursor.unwrap_or(false);
+    let (hidden, warnings) = HiddenWindows::hide(&app, hide_windows.as_deref());
+    for warning in warnings {
+        eprintln!("[capture_area_raw] {}", warning);
+    }
+    let area = grab_area(x, y, width, height, include_cursor)?;
+    drop(hidden);
+
+    let (left, top) = match &area {
+        AreaImage::Png(_, (left, top, _, _)) => (*left, *top),
+        AreaImage::Rgba(_) => (x, y),
+    };
+    let (screen_id, scale_factor) = Screen::from_point(x, y)
+        .map(|s| (s.display_info.id, s.display_info.scale_factor))
+        .unwrap_or((0, 1.0));
+    let rgba = area.into_rgba(include_cursor)?;
+    Ok(raw_response(rgba, screen_id, scale_factor, left, top))
+}
+
+/// 截取区域的 RGBA 像素（截取逻辑同 capture_area，不绘制鼠标指针、不记录截图历史）
+pub fn area_rgba(
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+) -> Result<image::RgbaImage, CommandError> {
+    Ok(grab_area(x, y, width, height, false)?.into_rgba(false)?)
+}
+
+/// capture_area_to_clipboard 的结果：写入剪贴板的图片像素尺寸
+#[derive(Serialize, Clone, Debug)]
+pub struct ClipboardCapture {
+    pub width: u32,
+    pub height: u32,
+}
+
+/**
+ * 截取区域并直接写入剪贴板（不经过临时文件）
+ * 截取逻辑同 capture_area（跨屏拼接、裁剪到屏幕边界），图片保持屏幕原始分辨率
+ * 剪贴板写入失败时返回 kind 为 "clipboardWriteFailed" 的错误
+ */
+#[tauri::command]
+pub fn capture_area_to_clipboard(
+    app: AppHandle,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+) -> Result<ClipboardCapture, CommandError> {
+    let rgba = area_rgba(x, y, width, height)?;
+    let (width, height) = rgba.dimensions();
+    let image = Image::new_owned(rgba.into_raw(), width, height);
+    app.clipboard()
+        .write_image(&image)
+        .map_err(|e| capture_backend::clipboard_write_failed(&e.to_string()))?;
+    Ok(ClipboardCapture { width, height })
+}
+
+/**
+ * 截取标题包含 title_substring（不区分大小写）的顶层窗口，输出 PNG
+ * 多个窗口匹配时取最上层的；在窗口所属屏幕上截取其客户区（不含标题栏和边框，
+ * 平台无法获取客户区时截取整个窗口矩形），超出该屏幕的部分被裁掉
+ * 截取的是屏幕像素，窗口被遮挡的部分会一并截入
+ * 返回的 x / y / display_width / display_height 为实际截取区域（逻辑坐标），
+ * width / height 为图片的实际像素尺寸（HiDPI 屏上为物理像素）
+ */
+#[tauri::command]
+pub fn capture_window(
+    app: AppHandle,
+    title_substring: String,
+) -> Result<ScreenCapture, CommandError> {
+    let needle = title_substring.to_lowercase();
+    let window = crate::window_list::visible_windows()?
+        .into_iter()
+        .find(|w| w.title.to_lowercase().contains(&needle))
+        .ok_or_else(|| {
+            CommandError::NotFound(format!(
+                "no visible window title contains \"{}\"",
+                title_substring
+            ))
+        })?;
+
+    let screen = all_screens()?
+        .into_iter()
+        .find(|s| s.display_info.id == window.screen_id)
+        .ok_or_else(|| CommandError::NotFound(format!("Screen {} not found", window.screen_id)))?;
+    let d = screen.display_info;
+    let r = crate::window_list::client_area(&window).unwrap_or(window.logical);
+    let (x, y, width, height) = intersect(&d, r.x, r.y, r.width, r.height)
+        .ok_or_else(|| format!("window \"{}\" is off screen", window.title))?;
 
     let image = screen
-        .capture_area(rel_x as i32, rel_y as i32, cap_width, cap_height)
+        .capture_area(x - d.x, y - d.y, width, height)
         .map_err(|e| e.to_string())?;
+    let output = ImageOutput::new(None, None, None, None)?;
+    let (data, _) = output.render(image.buffer())?;
+    let capture = ScreenCapture {
+        id: d.id,
+        x,
+        y,
+        width: image.width(),
+        height: image.height(),
+        scale_factor: d.scale_factor,
+        is_primary: d.is_primary,
+        data,
+        format: output.format.as_str().into(),
+        display_width: width,
+        display_height: height,
+        warnings: Vec::new(),
+        capture_id: None,
+        pixels: None,
+    };
+    remember(&app, "capture_window", &capture);
+    Ok(capture)
+}
+
+/// 屏幕上某点的颜色；hex 为 "#rrggbb"
+#[derive(Serialize, Clone, Debug)]
+pub struct PixelColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+    pub hex: String,
+}
+
+impl From<image::Rgba<u8>> for PixelColor {
+    fn from(image::Rgba([r, g, b, a]): image::Rgba<u8>) -> Self {
+        PixelColor {
+            r,
+            g,
+            b,
+            a,
+            hex: format!("#{:02x}{:02x}{:02x}", r, g, b),
+        }
+    }
+}
+
+/**
+ * 截取全局逻辑坐标 (x, y) 所在的 1x1 逻辑像素并取色
+ * 坐标先减去所在屏幕的 x/y 转为屏幕内坐标；HiDPI 屏上 1 个逻辑像素对应
+ * scale_factor x scale_factor 个物理像素，取其中左上角（物理坐标 rel * scale_factor）那个
+ */
+fn sample_pixel(screens: &[Screen], x: i32, y: i32) -> Result<PixelColor, CommandError> {
+    let screen = screens
+        .iter()
+        .find(|s| intersect(&s.display_info, x, y, 1, 1).is_some())
+        .ok_or_else(|| format!("point ({}, {}) is not on any screen", x, y))?;
+    let d = screen.display_info;
+    let image = match screen.capture_area(x - d.x, y - d.y, 1, 1) {
+        Ok(image) => image,
+        Err(e) => {
+            return capture_backend::fallback(e.to_string(), |desktop| {
+                desktop_pixel(&desktop, x, y)
+            });
+        }
+    };
+    let rgba = image::load_from_memory(image.buffer())
+        .map_err(|e| format!("decode error: {}", e))?
+        .to_rgba8();
+    let pixel = rgba.get_pixel_checked(0, 0).ok_or("empty capture")?;
+    Ok(PixelColor::from(*pixel))
+}
+
+/// portal 降级：从整张桌面截图（原点 0,0）中取色
+fn desktop_pixel(desktop: &image::RgbaImage, x: i32, y: i32) -> Result<PixelColor, String> {
+    if x < 0 || y < 0 {
+        return Err(format!("point ({}, {}) is outside of the desktop", x, y));
+    }
+    desktop
+        .get_pixel_checked(x as u32, y as u32)
+        .map(|p| PixelColor::from(*p))
+        .ok_or_else(|| format!("point ({}, {}) is outside of the desktop", x, y))
+}
+
+/**
+ * 屏幕取色（吸管工具）：只截取该点所在的 1 个逻辑像素，不截整屏
+ * x / y 为全局逻辑坐标（与 get_mouse_position 一致）
+ */
+#[tauri::command]
+pub fn get_pixel_color(x: i32, y: i32) -> Result<PixelColor, CommandError> {
+    let screens = match all_screens() {
+        Ok(screens) => screens,
+        Err(raw) => return capture_backend::fallback(raw, |desktop| desktop_pixel(&desktop, x, y)),
+    };
+    sample_pixel(&screens, x, y)
+}
+
+/**
+ * 批量取色（如采样渐变），结果顺序与 points 一致
+ * 无法枚举屏幕时只通过 portal 截取一次桌面，在同一张截图上取色
+ */
+#[tauri::command]
+pub fn get_pixel_colors(points: Vec<(i32, i32)>) -> Result<Vec<PixelColor>, CommandError> {
+    let sample_desktop = |desktop: image::RgbaImage| {
+        points
+            .iter()
+            .map(|&(x, y)| desktop_pixel(&desktop, x, y))
+            .collect::<Result<Vec<_>, _>>()
+    };
+    let screens = match all_screens() {
+        Ok(screens) => screens,
+        Err(raw) => return capture_backend::fallback(raw, sample_desktop),
+    };
+    points
+        .iter()
+        .map(|&(x, y)| sample_pixel(&screens, x, y))
+        .collect()
+}
+/// 区域与屏幕的交集（全局逻辑坐标）：(x, y, width, height)
+fn intersect(
+    d: &screenshots::DisplayInfo,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+) -> Option<(i32, i32, u32, u32)> {
+    let left = x.max(d.x);
+    let top = y.max(d.y);
+    let right = (x as i64 + width as i64).min(d.x as i64 + d.width as i64);
+    let bottom = (y as i64 + height as i64).min(d.y as i64 + d.height as i64);
+    if right <= left as i64 || bottom <= top as i64 {
+        return None;
+    }
+    Some((
+        left,
+        top,
+        (right - left as i64) as u32,
+        (bottom - top as i64) as u32,
+    ))
+}
+
+/// 跨屏区域拼接：各屏截取重叠部分，统一缩放到最大 scale_factor 后贴到画布
+fn stitch_area(
+    screens: &[&Screen],
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+) -> Result<image::RgbaImage, String> {
+    let scale = screens
+        .iter()
+        .map(|s| s.display_info.scale_factor)
+        .fold(1.0f32, f32::max);
+    stitch(screens, x, y, width, height, scale)
+}
+
+/// 把各屏幕与区域的重叠部分按 scale（画布像素 / 逻辑像素）缩放后贴到画布
+fn stitch(
+    screens: &[&Screen],
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    scale: f32,
+) -> Result<image::RgbaImage, String> {
+    let canvas_w = (width as f32 * scale).round() as u32;
+    let canvas_h = (height as f32 * scale).round() as u32;
+    let mut canvas = image::RgbaImage::new(canvas_w, canvas_h);
+
+    for screen in screens {
+        let d = screen.display_info;
+        let Some((ix, iy, iw, ih)) = intersect(&d, x, y, width, height) else {
+            continue;
+        };
+        let piece = screen
+            .capture_area(ix - d.x, iy - d.y, iw, ih)
+            .map_err(|e| e.to_string())?;
+        let mut piece = image::load_from_memory(piece.buffer())
+            .map_err(|e| format!("decode error: {}", e))?
+            .to_rgba8();
+
+        // 不同 DPI 的屏幕截图像素密度不同，统一到画布的密度
+        let target_w = (iw as f32 * scale).round() as u32;
+        let target_h = (ih as f32 * scale).round() as u32;
+        if piece.dimensions() != (target_w, target_h) {
+            piece = image::imageops::resize(
+                &piece,
+                target_w,
+                target_h,
+                image::imageops::FilterType::Triangle,
+            );
+        }
+
+        let off_x = ((ix - x) as f32 * scale).round() as i64;
+        let off_y = ((iy - y) as f32 * scale).round() as i64;
+        image::imageops::replace(&mut canvas, &piece, off_x, off_y);
+    }
+
+    Ok(canvas)
+}
+
+/// 截图保存结果
+#[derive(Serialize)]
+pub struct SavedCapture {
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+    pub file_size: u64,
+}
+
+/// capture_screen_to_file 临时文件序号
+static SAVE_SEQ: AtomicU64 = AtomicU64::new(0);
 
-    Ok(image.buffer().to_vec())
+/**
+ * 截图并直接写入 PNG 文件（避免经 IPC 传输大图）
+ * screen_id 为空时使用主屏；x / y / width / height 同 capture_screen_by_id
+ * （相对屏幕左上角的物理像素，需同时提供，超出屏幕的部分被裁掉），省略则截全屏
+ * 截图后端不可用时同 capture_screen_by_id 降级到 portal
+ * 自动创建父目录；文件已存在时需 overwrite: true 才会覆盖。先写入同目录下的临时文件再发布
+ * （覆盖时重命名，不覆盖时硬链接，由系统保证不会覆盖期间新建的同名文件），
+ * 中途失败不会留下不完整的目标文件
+ */
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn capture_screen_to_file(
+    state: State<'_, AppState>,
+    screen_id: Option<u32>,
+    x: Option<i32>,
+    y: Option<i32>,
+    width: Option<i32>,
+    height: Option<i32>,
+    path: String,
+    overwrite: Option<bool>,
+    allow_persistent: Option<bool>,
+) -> Result<SavedCapture, CommandError> {
+    ephemeral::check_persist(&state, allow_persistent.unwrap_or(false))?;
+    let area = sub_area(x, y, width, height)?;
+    let target = std::path::PathBuf::from(&path);
+    let overwrite = overwrite.unwrap_or(false);
+    // 提前失败，避免无谓的截图；真正的防覆盖由下面的硬链接保证
+    if !overwrite && target.exists() {
+        return Err(CommandError::Io(format!(
+            "{} already exists; set overwrite to replace it",
+            path
+        )));
+    }
+
+    let portal = |image| encode_rgba(&crop_desktop(image, area)?, CaptureFormat::Png, None);
+    let png = match all_screens() {
+        Ok(screens) => {
+            let screen = match screen_id {
+                Some(id) => find_screen(screens, id)?,
+                None => screens
+                    .into_iter()
+                    .find(|s| s.display_info.is_primary)
+                    .ok_or_else(|| CommandError::NotFound("no primary screen".into()))?,
+            };
+            match grab_screen(&screen, area) {
+                Ok((png, _)) => png,
+                Err(e) => capture_backend::fallback(e, portal)?,
+            }
+        }
+        Err(raw) => capture_backend::fallback(raw, portal)?,
+    };
+    let (width, height, _) = imaging::probe_header(&png)?;
+
+    let parent = match target.parent().filter(|p| !p.as_os_str().is_empty()) {
+        Some(parent) => parent.to_path_buf(),
+        None => std::path::PathBuf::from("."),
+    };
+    std::fs::create_dir_all(&parent).map_err(|e| {
+        CommandError::Io(format!(
+            "create directory {} failed: {}",
+            parent.display(),
+            e
+        ))
+    })?;
+    let name = target
+        .file_name()
+        .ok_or_else(|| CommandError::Io(format!("{} is not a file path", path)))?;
+    let tmp = parent.join(format!(
+        ".{}.{}-{}.tmp",
+        name.to_string_lossy(),
+        std::process::id(),
+        SAVE_SEQ.fetch_add(1, Ordering::Relaxed)
+    ));
+    let written = std::fs::write(&tmp, &png).and_then(|_| match overwrite {
+        true => std::fs::rename(&tmp, &target),
+        false => std::fs::hard_link(&tmp, &target),
+    });
+    let _ = std::fs::remove_file(&tmp);
+    written.map_err(|e| match e.kind() {
+        std::io::ErrorKind::AlreadyExists => CommandError::Io(format!(
+            "{} already exists; set overwrite to replace it",
+            path
+        )),
+        _ => CommandError::Io(format!("write {} failed: {}", path, e)),
+    })?;
+
+    let final_path = target.canonicalize().unwrap_or(target);
+    Ok(SavedCapture {
+        path: final_path.to_string_lossy().into_owned(),
+        width,
+        height,
+        file_size: png.len() as u64,
+    })
 }
 
 // === 保留旧API兼容性（标记为deprecated） ===
@@ -578,6 +2987,8 @@ pub fn get_all_screens() -> Result<Vec<String>, String> {
 
 /**
  * 截屏（旧API，返回base64）
+ * 截取 (x, y) 起的区域（全局逻辑坐标），超出 (x, y) 所在屏幕的部分被裁掉，同单屏的 capture_area
+ * 注意：以前的版本忽略 x / y，总是截取所在屏幕左上角的区域；依赖旧行为的调用方需要改传 (屏幕 x, 屏幕 y)
  * @deprecated 使用 capture_screen_at_point 获取更好性能
  */
 #[tauri::command]
@@ -588,14 +2999,35 @@ pub fn screenshot(x: &str, y: &str, width: &str, height: &str) -> Result<String,
     let ph = height.parse::<u32>().map_err(|e| e.to_string())?;
 
     let screen = Screen::from_point(px, py).map_err(|e| e.to_string())?;
-    let image = screen
-        .capture_area(0, 0, pw, ph)
-        .map_err(|e| e.to_string())?;
+    let (png, _) = capture_screen_area(&screen, px, py, pw, ph)?;
 
-    Ok(general_purpose::STANDARD_NO_PAD.encode(image.buffer()))
+    Ok(general_purpose::STANDARD_NO_PAD.encode(png))
 }
 
-#[derive(Serialize, Clone)]
+/// 系统信息（包含当前运行模式）
+#[derive(Serialize)]
+pub struct SystemInfo {
+    pub os: String,
+    pub arch: String,
+    pub os_version: Option<String>,
+    pub host_name: Option<String>,
+    pub ephemeral: ephemeral::EphemeralStatus,
+}
+
+#[tauri::command]
+pub fn get_system_info(state: State<'_, AppState>) -> SystemInfo {
+    use sysinfo::{System, SystemExt};
+    let sys = System::new();
+    SystemInfo {
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        os_version: sys.long_os_version(),
+        host_name: sys.host_name(),
+        ephemeral: ephemeral::status(&state),
+    }
+}
+
+#[derive(Serialize, Clone, PartialEq, JsonSchema)]
 pub struct DisplayInfo {
     pub id: u32,
     pub x: i32,
@@ -609,7 +3041,7 @@ pub struct DisplayInfo {
 }
 
 /// 多屏幕信息（包含虚拟桌面尺寸）
-#[derive(Serialize)]
+#[derive(Serialize, Clone, JsonSchema)]
 pub struct MultiScreenInfo {
     pub screens: Vec<DisplayInfo>,
     pub virtual_x: i32,
@@ -619,10 +3051,32 @@ pub struct MultiScreenInfo {
 }
 
 /// 鼠标坐标结构，公开以便序列化/使用
-#[derive(Serialize, Debug, Clone, Copy)]
+#[derive(Serialize, Debug, Clone, Copy, JsonSchema)]
 pub struct MousePos {
     pub x: i32,
     pub y: i32,
+    /// 与上一次采样之间的速度（像素/秒），轮询的第一次采样为 null
+    pub vx: Option<f64>,
+    pub vy: Option<f64>,
+    /// 速率 sqrt(vx² + vy²)
+    pub speed: Option<f64>,
+}
+
+impl MousePos {
+    /// 轮询采样的坐标；prev 为上一次采样的坐标及距今的时间
+    pub fn sampled((x, y): (i32, i32), prev: Option<((i32, i32), Duration)>) -> Self {
+        let velocity = prev.and_then(|((px, py), elapsed)| {
+            let secs = elapsed.as_secs_f64();
+            (secs > 0.0).then(|| ((x - px) as f64 / secs, (y - py) as f64 / secs))
+        });
+        MousePos {
+            x,
+            y,
+            vx: velocity.map(|v| v.0),
+            vy: velocity.map(|v| v.1),
+            speed: velocity.map(|(vx, vy)| vx.hypot(vy)),
+        }
+    }
 }
 
 //#[tauri::command]
@@ -640,3 +3094,204 @@ pub struct MousePos {
 //     };
 //     tray.set_icon(icon)
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_split_multibyte_text_at_punctuation() {
+        let text = "今天天气很好，我们去公园散步吧。明天下雨";
+        assert_eq!(
+            split_into_chunks(text, 10),
+            vec!["今天天气很好，", "我们去公园散步吧。", "明天下雨"]
+        );
+        // 没有断点时按字符硬切
+        assert_eq!(
+            split_into_chunks("一二三四五六七", 3),
+            vec!["一二三", "四五六", "七"]
+        );
+    }
+
+    #[test]
+    fn chunks_do_not_split_latin_words() {
+        assert_eq!(
+            split_into_chunks("The quick brown fox jumps", 12),
+            vec!["The quick ", "brown fox ", "jumps"]
+        );
+        // 英文句点可能是缩写或小数点，不作为句末，按空白切分
+        assert_eq!(
+            split_into_chunks("Hi. Lorem ipsum dolor", 12),
+            vec!["Hi. Lorem ", "ipsum dolor"]
+        );
+    }
+
+    /// 1x1 的 PNG
+    const PIXEL_PNG: &str = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mP8z8BQDwAEhQGAhKmMIQAAAABJRU5ErkJggg==";
+
+    #[tokio::test]
+    async fn url_to_rgba_decodes_data_uri() {
+        let url = format!("data:image/png;base64,{}", PIXEL_PNG);
+        let (width, height, rgba, channels) = load_rgba(&reqwest::Client::new(), &url, None)
+            .await
+            .unwrap();
+        assert_eq!((width, height, channels), (1, 1, 4));
+        assert_eq!(rgba.len(), 4);
+
+        let (_, _, luma, channels) = load_rgba(&reqwest::Client::new(), &url, Some("luma"))
+            .await
+            .unwrap();
+        assert_eq!((luma.len(), channels), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn url_to_rgba_reads_file_uri() {
+        let mut img = image::RgbaImage::new(2, 3);
+        img.put_pixel(1, 2, image::Rgba([10, 20, 30, 255]));
+        let path =
+            std::env::temp_dir().join(format!("url_to_rgba test {}.png", std::process::id()));
+        img.save(&path).unwrap();
+
+        let path_str = path
+            .to_string_lossy()
+            .replace('\\', "/")
+            .replace(' ', "%20");
+        let url = match path_str.starts_with('/') {
+            true => format!("file://{}", path_str),
+            false => format!("file:///{}", path_str),
+        };
+        let result = load_rgba(&reqwest::Client::new(), &url, None).await;
+        let _ = std::fs::remove_file(&path);
+
+        let (width, height, rgba, _) = result.unwrap();
+        assert_eq!((width, height), (2, 3));
+        assert_eq!(&rgba[rgba.len() - 4..], &[10, 20, 30, 255]);
+    }
+
+    #[test]
+    fn screen_relative_area_on_secondary_monitor_left_of_primary() {
+        let secondary = (-1920, 0, 1920, 1080);
+        assert_eq!(
+            screen_relative_area(secondary, -1000, 100, 500, 200),
+            (920, 100, 500, 200)
+        );
+        // 超出屏幕右边界的部分被裁掉
+        assert_eq!(
+            screen_relative_area(secondary, -200, 1000, 500, 200),
+            (1720, 1000, 200, 80)
+        );
+    }
+
+    #[test]
+    fn logical_area_on_secondary_monitor_with_different_scale() {
+        // 主屏 1920x1080 @1x，右侧副屏物理 2880x1620 @1.5x（逻辑 1920x1080）
+        let primary = (0, 0, 1920, 1080);
+        let secondary = (1920, 0, 1920, 1080);
+        assert!(display_contains(secondary, 2000, 100));
+        assert!(!display_contains(primary, 2000, 100));
+
+        assert_eq!(
+            screen_relative_area(secondary, 2000, 100, 400, 200),
+            (80, 100, 400, 200)
+        );
+        let logical = (2000, 100, 400, 200);
+        assert_eq!(logical_to_physical(logical, 1.5), (3000, 150, 600, 300));
+        // 超出副屏右边界的部分先按逻辑坐标裁掉，再换算
+        let (rx, ry, rw, rh) = screen_relative_area(secondary, 3700, 1000, 400, 200);
+        let logical = (secondary.0 + rx as i32, secondary.1 + ry as i32, rw, rh);
+        assert_eq!(logical, (3700, 1000, 140, 80));
+        assert_eq!(logical_to_physical(logical, 1.5), (5550, 1500, 210, 120));
+        assert_eq!(logical_to_physical((10, 20, 30, 40), 1.0), (10, 20, 30, 40));
+    }
+
+    #[test]
+    fn mouse_poller_emits_immediately_without_throttle() {
+        let now = Instant::now();
+        let last = Some((10, 10));
+        assert_eq!(
+            should_emit(last, (11, 10), Some(now), now, 0, Duration::ZERO),
+            Some((11, 10))
+        );
+        assert_eq!(
+            should_emit(None, (0, 0), None, now, 5, Duration::from_millis(100)),
+            Some((0, 0))
+        );
+    }
+
+    #[test]
+    fn mouse_poller_suppresses_moves_below_min_move() {
+        let now = Instant::now();
+        let last = Some((10, 10));
+        assert_eq!(
+            should_emit(last, (12, 11), None, now, 4, Duration::ZERO),
+            None
+        );
+        assert_eq!(
+            should_emit(last, (12, 12), None, now, 4, Duration::ZERO),
+            Some((12, 12))
+        );
+    }
+
+    #[test]
+    fn mouse_poller_coalesces_within_throttle_window_and_flushes_latest() {
+        let throttle = Duration::from_millis(100);
+        let emitted = Instant::now();
+        let last = Some((0, 0));
+        // 窗口内的移动都不发送
+        for (i, p) in [(5, 0), (9, 3), (20, 7)].into_iter().enumerate() {
+            let now = emitted + Duration::from_millis(20 * (i as u64 + 1));
+            assert_eq!(should_emit(last, p, Some(emitted), now, 0, throttle), None);
+        }
+        // 窗口结束后（鼠标已停下）发送的是最新位置，而不是窗口内较早的坐标
+        let after = emitted + Duration::from_millis(120);
+        assert_eq!(
+            should_emit(last, (20, 7), Some(emitted), after, 0, throttle),
+            Some((20, 7))
+        );
+        // 停下的位置回到阈值内时不再补发旧坐标
+        assert_eq!(
+            should_emit(last, (1, 0), Some(emitted), after, 3, throttle),
+            None
+        );
+    }
+
+    #[test]
+    fn target_scale_downscales_output() {
+        let mut output = ImageOutput::new(None, None, None, None).unwrap();
+        output.downscale = Some(0.5);
+        assert_eq!(output.fit(3840, 2160), Some((1920, 1080)));
+        output.max_width = Some(1280);
+        assert_eq!(output.fit(3840, 2160), Some((1280, 720)));
+        output.downscale = Some(1.5);
+        assert_eq!(output.fit(1280, 720), None);
+    }
+
+    #[test]
+    fn sub_area_is_converted_to_logical_and_clamped() {
+        let display = (1920, 0, 1280, 720);
+        // 2x 屏幕上的物理像素区域
+        assert_eq!(
+            sub_area_on_display(display, 2.0, (101, 200, 400, 300)),
+            Some((1970, 100, 201, 150))
+        );
+        // 超出屏幕右下角的部分被裁掉
+        assert_eq!(
+            sub_area_on_display(display, 2.0, (2400, 1400, 400, 300)),
+            Some((3120, 700, 80, 20))
+        );
+        assert_eq!(sub_area_on_display(display, 2.0, (-500, 0, 400, 300)), None);
+        assert!(sub_area(Some(0), Some(0), Some(0), Some(10)).is_err());
+        assert!(sub_area(Some(0), None, None, None).is_err());
+    }
+    #[test]
+    fn mouse_velocity_uses_elapsed_time() {
+        let first = MousePos::sampled((10, 10), None);
+        assert_eq!((first.vx, first.speed), (None, None));
+
+        let pos = MousePos::sampled((40, 50), Some(((10, 10), Duration::from_millis(500))));
+        assert_eq!(
+            (pos.vx, pos.vy, pos.speed),
+            (Some(60.0), Some(80.0), Some(100.0))
+        );
+    }
+}
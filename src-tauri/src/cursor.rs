@@ -0,0 +1,74 @@
+/*!
+ * 在截图上绘制鼠标指针（screenshots 截图不包含指针，教程录制需要）
+ */
+
+use enigo::Enigo;
+use image::{Rgba, RgbaImage};
+
+/// 箭头指针位图：X 为描边，o 为填充，热点在左上角
+const ARROW: [&str; 19] = [
+    "X...........",
+    "XX..........",
+    "XoX.........",
+    "XooX........",
+    "XoooX.......",
+    "XooooX......",
+    "XoooooX.....",
+    "XooooooX....",
+    "XoooooooX...",
+    "XooooooooX..",
+    "XoooooooooX.",
+    "XooooooXXXXX",
+    "XoooXooX....",
+    "XooXXooX....",
+    "XoX..XooX...",
+    "XX...XooX...",
+    "X.....XooX..",
+    "......XooX..",
+    ".......XX...",
+];
+
+const OUTLINE: Rgba<u8> = Rgba([0, 0, 0, 255]);
+const FILL: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/**
+ * 把当前鼠标指针画到截图上
+ * (x, y, width, height) 为截图对应的全局逻辑区域；图像像素与逻辑坐标的比例即屏幕 scale_factor
+ * 指针不在区域内时不绘制，返回是否绘制
+ */
+pub fn composite_cursor(image: &mut RgbaImage, x: i32, y: i32, width: u32, height: u32) -> bool {
+    if width == 0 || height == 0 {
+        return false;
+    }
+    let (mx, my) = Enigo::mouse_location();
+    if mx < x || my < y || mx >= x + width as i32 || my >= y + height as i32 {
+        return false;
+    }
+
+    let sx = image.width() as f32 / width as f32;
+    let sy = image.height() as f32 / height as f32;
+    let hot_x = ((mx - x) as f32 * sx) as i64;
+    let hot_y = ((my - y) as f32 * sy) as i64;
+    // 指针随屏幕缩放放大，保持与系统指针相近的视觉大小
+    let scale = sx.max(sy).round().max(1.0) as i64;
+
+    for (row, line) in ARROW.iter().enumerate() {
+        for (col, c) in line.chars().enumerate() {
+            let color = match c {
+                'X' => OUTLINE,
+                'o' => FILL,
+                _ => continue,
+            };
+            for dy in 0..scale {
+                for dx in 0..scale {
+                    let px = hot_x + col as i64 * scale + dx;
+                    let py = hot_y + row as i64 * scale + dy;
+                    if px < image.width() as i64 && py < image.height() as i64 {
+                        image.put_pixel(px as u32, py as u32, color);
+                    }
+                }
+            }
+        }
+    }
+    true
+}
@@ -0,0 +1,130 @@
+/*!
+ * 延时截图（"3 秒后截图"，便于先打开菜单或悬停状态）
+ *
+ * 倒计时在后台线程进行，每秒发送 "capture:countdown" 事件，截图完成后发送 "capture:done"。
+ * 同一时间只允许一个延时截图：已有任务在倒计时时新请求直接返回 AlreadyPending，不排队。
+ */
+
+use crate::AppState;
+use crate::commands::{self, CaptureOptions, CaptureTarget, ScreenCapture};
+use crate::events;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+
+/// 倒计时检查取消标记的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// "capture:countdown" 事件负载：剩余秒数（向上取整）
+#[derive(Serialize, Clone, JsonSchema)]
+pub struct CaptureCountdown {
+    pub remaining_secs: u64,
+}
+
+/// 延时截图结果；status 为 "captured" | "cancelled" | "alreadyPending"
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum DelayedCapture {
+    Captured {
+        capture: ScreenCapture,
+    },
+    /// 被 cancel_delayed_capture 取消
+    Cancelled,
+    /// 已有延时截图在进行，本次请求被拒绝
+    AlreadyPending,
+}
+
+/// 倒计时，返回 false 表示被取消
+fn countdown(app: &AppHandle, cancel: &AtomicBool, delay: Duration) -> bool {
+    let deadline = Instant::now() + delay;
+    let mut last_emitted = None;
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return false;
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return true;
+        }
+        let secs = remaining.as_millis().div_ceil(1000) as u64;
+        if last_emitted != Some(secs) {
+            last_emitted = Some(secs);
+            let _ = events::emit_event(
+                app,
+                events::Event::CaptureCountdown(CaptureCountdown {
+                    remaining_secs: secs,
+                }),
+            );
+        }
+        std::thread::sleep(POLL_INTERVAL.min(remaining));
+    }
+}
+
+/**
+ * 延时 delay_ms 毫秒后截图
+ * 倒计时期间每秒发送 "capture:countdown"，成功后发送 "capture:done"（ScreenCapture）并返回截图
+ * 已有延时截图进行中时返回 AlreadyPending
+ */
+#[tauri::command]
+pub async fn capture_after_delay(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    delay_ms: u64,
+    target: CaptureTarget,
+) -> Result<DelayedCapture, String> {
+    let cancel = Arc::new(AtomicBool::new(false));
+    {
+        let mut pending = state
+            .delayed_capture
+            .lock()
+            .map_err(|e| format!("lock error: {}", e))?;
+        if pending.is_some() {
+            return Ok(DelayedCapture::AlreadyPending);
+        }
+        *pending = Some(cancel.clone());
+    }
+
+    let handle = app.clone();
+    let result = tokio::task::spawn_blocking(move || -> Result<DelayedCapture, String> {
+        if !countdown(&handle, &cancel, Duration::from_millis(delay_ms)) {
+            return Ok(DelayedCapture::Cancelled);
+        }
+        let capture = commands::capture_target(
+            &handle,
+            target,
+            &CaptureOptions::default(),
+            "capture_after_delay",
+        )?;
+        let _ = events::emit_event(&handle, events::Event::CaptureDone(capture.clone()));
+        Ok(DelayedCapture::Captured { capture })
+    })
+    .await
+    .map_err(|e| format!("capture task error: {}", e))
+    .and_then(|r| r);
+
+    if let Ok(mut pending) = app.state::<AppState>().delayed_capture.lock() {
+        *pending = None;
+    }
+    result
+}
+
+/**
+ * 取消进行中的延时截图，返回是否有任务被取消
+ */
+#[tauri::command]
+pub fn cancel_delayed_capture(state: State<'_, AppState>) -> Result<bool, String> {
+    let pending = state
+        .delayed_capture
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?;
+    match pending.as_ref() {
+        Some(flag) => {
+            flag.store(true, Ordering::Relaxed);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
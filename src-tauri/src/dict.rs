@@ -0,0 +1,595 @@
+/*!
+ * 用户词典
+ *
+ * 运行时添加到 jieba 的词条都记录在 AppState.user_words 中，
+ * 并以标准 jieba 词典格式（word freq [tag]）持久化到 custom_jieba.txt，启动时重新加载。
+ * 文件可以手动编辑，下次启动生效。
+ * 通过命令修改的词条同时记入同步日志（sync::KIND_DICT_WORD），由 sync_push 推送到其他设备。
+ */
+
+use crate::AppState;
+use crate::ephemeral;
+use crate::events;
+use crate::sync;
+use jieba_rs::Jieba;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::MutexGuard;
+use tauri::{AppHandle, Manager, State};
+
+const CUSTOM_DICT_FILE: &str = "custom_jieba.txt";
+
+/// 用户添加的词条
+#[derive(Serialize, Clone, Debug)]
+pub struct UserWord {
+    pub word: String,
+    pub freq: usize,
+    pub tag: Option<String>,
+}
+
+/// 词典导入结果
+#[derive(Serialize)]
+pub struct DictImportReport {
+    pub imported: usize,
+    pub skipped: usize,
+    pub warnings: Vec<String>,
+}
+
+/// 解析后的一行词典：(word, freq, tag)
+type DictEntry = (String, Option<usize>, Option<String>);
+
+/**
+ * 启动时确定自定义词典路径（app_local_data_dir/custom_jieba.txt），记录到 AppState.custom_dict_path
+ */
+pub fn init_custom_dict_path(app: &AppHandle, state: &AppState) -> Result<PathBuf, String> {
+    let path = app
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| format!("path error: {}", e))?
+        .join(CUSTOM_DICT_FILE);
+    *state
+        .custom_dict_path
+        .write()
+        .map_err(|e| format!("lock error: {}", e))? = Some(path.clone());
+    Ok(path)
+}
+
+/// 当前使用的自定义词典文件（临时模式下位于沙箱）
+fn custom_dict_path(state: &AppState) -> Result<PathBuf, String> {
+    let path = state
+        .custom_dict_path
+        .read()
+        .map_err(|e| format!("lock error: {}", e))?
+        .clone()
+        .ok_or("custom dictionary path is not initialised")?;
+    let dir = path.parent().map(PathBuf::from).unwrap_or_default();
+    let dir = ephemeral::redirect(state, "dict", dir);
+    fs::create_dir_all(&dir).map_err(|e| format!("mkdir error: {}", e))?;
+    Ok(dir.join(CUSTOM_DICT_FILE))
+}
+
+/// 解析 jieba 词典格式，格式错误的行跳过并返回警告
+pub fn parse_dict(content: &str) -> (Vec<DictEntry>, Vec<String>) {
+    let mut entries = Vec::new();
+    let mut warnings = Vec::new();
+
+    for (no, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let freq = match parts.get(1) {
+            Some(f) => match f.parse::<usize>() {
+                Ok(f) => Some(f),
+                Err(_) => {
+                    warnings.push(format!("line {}: invalid frequency '{}'", no + 1, f));
+                    continue;
+                }
+            },
+            None => None,
+        };
+        if parts.len() > 3 {
+            warnings.push(format!("line {}: too many fields", no + 1));
+            continue;
+        }
+        entries.push((
+            parts[0].to_string(),
+            freq,
+            parts.get(2).map(|t| t.to_string()),
+        ));
+    }
+
+    (entries, warnings)
+}
+
+fn format_dict(words: &[UserWord]) -> String {
+    let mut out = String::new();
+    for w in words {
+        match &w.tag {
+            Some(tag) => out.push_str(&format!("{} {} {}\n", w.word, w.freq, tag)),
+            None => out.push_str(&format!("{} {}\n", w.word, w.freq)),
+        }
+    }
+    out
+}
+
+/// 添加词条到 jieba 并记录到用户词典（不落盘）
+pub fn add_word(
+    state: &AppState,
+    word: &str,
+    freq: Option<usize>,
+    tag: Option<&str>,
+) -> Result<usize, String> {
+    let _update = update_guard(state)?;
+    let mut jieba = state
+        .jieba
+        .write()
+        .map_err(|e| format!("lock error: {}", e))?;
+    let freq = jieba.add_word(word, freq, tag);
+    let mut words = state
+        .user_words
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?;
+    words.insert(
+        word.to_string(),
+        UserWord {
+            word: word.to_string(),
+            freq,
+            tag: tag.map(|t| t.to_string()),
+        },
+    );
+    Ok(freq)
+}
+
+/// 持有期间其他修改词典的操作等待，保证在锁外构建的新词典替换时不会丢失并发的修改
+fn update_guard(state: &AppState) -> Result<MutexGuard<'_, ()>, String> {
+    state
+        .dict_update
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))
+}
+
+/// 用新词典替换当前词典；写锁内只做替换，并发的分词只会用到旧词典或完整的新词典
+fn swap_in(state: &AppState, jieba: Jieba) -> Result<(), String> {
+    *state
+        .jieba
+        .write()
+        .map_err(|e| format!("lock error: {}", e))? = jieba;
+    Ok(())
+}
+
+/// 用默认词典 + 已加载的词典文件 + 当前用户词条重建 jieba（jieba 不支持删除词条）
+pub fn rebuild(state: &AppState) -> Result<(), String> {
+    let _update = update_guard(state)?;
+    let words = user_words(state)?;
+    let dicts = state
+        .loaded_dicts
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?
+        .clone();
+    let mut rebuilt = Jieba::new();
+    for path in dicts.iter() {
+        let res = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|c| rebuilt.load_dict(&mut c.as_bytes()).map_err(|e| e.to_string()));
+        if let Err(e) = res {
+            eprintln!("[dict] reload {} failed: {}", path.display(), e);
+        }
+    }
+    for w in &words {
+        rebuilt.add_word(&w.word, Some(w.freq), w.tag.as_deref());
+    }
+    swap_in(state, rebuilt)
+}
+
+/// 把用户词典写入本地文件（整体重写）
+pub fn persist(state: &AppState) -> Result<(), String> {
+    let path = custom_dict_path(state)?;
+    let words: Vec<UserWord> = state
+        .user_words
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?
+        .values()
+        .cloned()
+        .collect();
+    fs::write(&path, format_dict(&words)).map_err(|e| format!("write error: {}", e))
+}
+
+/// 把单个词条追加到本地文件
+fn append_persisted(state: &AppState, word: &UserWord) -> Result<(), String> {
+    use std::io::Write;
+
+    let path = custom_dict_path(state)?;
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("open {} failed: {}", path.display(), e))?;
+    file.write_all(format_dict(std::slice::from_ref(word)).as_bytes())
+        .map_err(|e| format!("write error: {}", e))
+}
+
+/// 把用户词条的修改记入同步日志（value 为 None 表示删除）；失败只打印日志，不影响词典本身
+fn journal(app: &AppHandle, state: &AppState, changes: Vec<(String, Option<&UserWord>)>) {
+    let changes = changes.into_iter().map(|(word, entry)| {
+        let value = entry.map(|w| serde_json::json!({ "freq": w.freq, "tag": w.tag }));
+        (sync::KIND_DICT_WORD.to_string(), word, value)
+    });
+    if let Err(e) = sync::record_local_changes(app, state, changes) {
+        eprintln!("[dict] record sync change failed: {}", e);
+    }
+}
+
+/// 当前用户词条的副本
+fn user_words(state: &AppState) -> Result<Vec<UserWord>, String> {
+    Ok(state
+        .user_words
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?
+        .values()
+        .cloned()
+        .collect())
+}
+
+/// 启动时加载持久化的用户词典
+pub fn load_persisted(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let path = match init_custom_dict_path(app, &state).and_then(|_| custom_dict_path(&state)) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("[dict] {}", e);
+            return;
+        }
+    };
+    let Ok(content) = fs::read_to_string(&path) else {
+        return;
+    };
+    let (entries, warnings) = parse_dict(&content);
+    for w in &warnings {
+        eprintln!("[dict] {}: {}", path.display(), w);
+    }
+    for (word, freq, tag) in &entries {
+        if let Err(e) = add_word(&state, word, *freq, tag.as_deref()) {
+            eprintln!("[dict] load error: {}", e);
+            return;
+        }
+    }
+    println!("[dict] loaded {} user words", entries.len());
+}
+
+/**
+ * 导出用户词典（标准 jieba 词典格式）
+ * 返回导出的词条数
+ */
+#[tauri::command]
+pub fn export_user_dict(
+    state: State<'_, AppState>,
+    path: String,
+    allow_persistent: Option<bool>,
+) -> Result<usize, String> {
+    ephemeral::check_persist(&state, allow_persistent.unwrap_or(false))?;
+    let words: Vec<UserWord> = state
+        .user_words
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?
+        .values()
+        .cloned()
+        .collect();
+    fs::write(&path, format_dict(&words)).map_err(|e| format!("write error: {}", e))?;
+    Ok(words.len())
+}
+
+/**
+ * 导入用户词典
+ * merge: true 合并到现有词条；false 替换现有的用户词条
+ * 格式错误的行会被跳过并在 warnings 中说明
+ */
+#[tauri::command]
+pub fn import_user_dict(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    merge: bool,
+) -> Result<DictImportReport, String> {
+    let content = fs::read_to_string(&path).map_err(|e| format!("read error: {}", e))?;
+    let (entries, warnings) = parse_dict(&content);
+
+    let replaced = match merge {
+        true => Vec::new(),
+        false => {
+            let old = user_words(&state)?;
+            state
+                .user_words
+                .lock()
+                .map_err(|e| format!("lock error: {}", e))?
+                .clear();
+            rebuild(&state)?;
+            old
+        }
+    };
+    for (word, freq, tag) in &entries {
+        add_word(&state, word, *freq, tag.as_deref())?;
+    }
+    persist(&state)?;
+
+    let current = user_words(&state)?;
+    let imported: HashSet<&str> = entries.iter().map(|(word, _, _)| word.as_str()).collect();
+    let removed = replaced
+        .into_iter()
+        .filter(|w| !imported.contains(w.word.as_str()))
+        .map(|w| (w.word, None));
+    let added = current
+        .iter()
+        .filter(|w| imported.contains(w.word.as_str()))
+        .map(|w| (w.word.clone(), Some(w)));
+    journal(&app, &state, removed.chain(added).collect());
+
+    Ok(DictImportReport {
+        imported: entries.len(),
+        skipped: warnings.len(),
+        warnings,
+    })
+}
+
+/// "jieba:dict-loaded" 事件负载
+#[derive(Serialize, Clone, JsonSchema)]
+pub struct DictLoaded {
+    pub path: String,
+    pub new_words: usize,
+}
+
+/**
+ * 运行时加载自定义词典文件（jieba 词典格式：每行 word freq [tag]）
+ * 先在当前词典的副本上加载（不持有写锁，加载期间分词不受影响），成功后再替换；
+ * 文件不存在或格式错误时返回错误，不影响当前词典
+ * 成功后发送 "jieba:dict-loaded" 事件，包含新增词数
+ */
+#[tauri::command]
+pub fn load_jieba_dict(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    dict_path: String,
+) -> Result<(), String> {
+    let content = fs::read_to_string(&dict_path)
+        .map_err(|e| format!("read dictionary {} failed: {}", dict_path, e))?;
+
+    let update = update_guard(&state)?;
+    let mut loaded = state
+        .jieba
+        .read()
+        .map_err(|e| format!("lock error: {}", e))?
+        .clone();
+    let new_words = content
+        .lines()
+        .filter_map(|l| l.split_whitespace().next())
+        .filter(|w| !loaded.has_word(w))
+        .collect::<HashSet<_>>()
+        .len();
+    loaded
+        .load_dict(&mut content.as_bytes())
+        .map_err(|e| format!("invalid dictionary {}: {}", dict_path, e))?;
+    swap_in(&state, loaded)?;
+    state
+        .loaded_dicts
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?
+        .push(PathBuf::from(&dict_path));
+    drop(update);
+
+    if let Err(e) = events::emit_event(
+        &app,
+        events::Event::JiebaDictLoaded(DictLoaded {
+            path: dict_path,
+            new_words,
+        }),
+    ) {
+        eprintln!("[dict] emit error: {:?}", e);
+    }
+    Ok(())
+}
+
+/**
+ * 运行时添加词条（如产品名、品牌词）
+ * freq 为空时由 jieba 自动推算词频；tag 为可选词性
+ * 词条同时追加到自定义词典文件，重启后仍然有效
+ */
+#[tauri::command]
+pub fn add_jieba_word(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    word: String,
+    freq: Option<usize>,
+    tag: Option<String>,
+) -> Result<(), String> {
+    let word = word.trim();
+    if word.is_empty() || word.contains(char::is_whitespace) {
+        return Err("word must be non-empty and contain no whitespace".into());
+    }
+    let freq = add_word(&state, word, freq, tag.as_deref())?;
+    state
+        .custom_words
+        .write()
+        .map_err(|e| format!("lock error: {}", e))?
+        .insert(word.to_string());
+    let entry = UserWord {
+        word: word.to_string(),
+        freq,
+        tag,
+    };
+    // 同一个词重复添加时文件中会有多行，加载时以最后一行为准
+    append_persisted(&state, &entry)?;
+    journal(&app, &state, vec![(entry.word.clone(), Some(&entry))]);
+    Ok(())
+}
+
+/**
+ * 移除运行时添加的词条，返回该词是否存在
+ * jieba 不支持删除，移除后会重建词典并重写自定义词典文件；内置词典中的词无法移除，返回 false
+ */
+#[tauri::command]
+pub fn remove_jieba_word(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    word: String,
+) -> Result<bool, String> {
+    let removed = state
+        .user_words
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?
+        .remove(&word)
+        .is_some();
+    state
+        .custom_words
+        .write()
+        .map_err(|e| format!("lock error: {}", e))?
+        .remove(&word);
+    if removed {
+        rebuild(&state)?;
+        persist(&state)?;
+        journal(&app, &state, vec![(word, None)]);
+    }
+    Ok(removed)
+}
+
+/**
+ * 屏蔽一个词（包括内置词典中的词），返回该词此前是否在词典中生效
+ * jieba 不支持删除词条，这里把词频设为 0，分词时不会再把它切成一个整体（full 模式仍会列出）
+ * 屏蔽以词频 0 的词条写入自定义词典文件，重启后仍然有效；用 add_jieba_word 重新添加即可恢复
+ * 只想撤销 add_jieba_word 添加的词时用 remove_jieba_word
+ */
+#[tauri::command]
+pub fn remove_user_word(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    word: String,
+) -> Result<bool, String> {
+    let word = word.trim();
+    let present = {
+        let jieba = state
+            .jieba
+            .read()
+            .map_err(|e| format!("lock error: {}", e))?;
+        let words = state
+            .user_words
+            .lock()
+            .map_err(|e| format!("lock error: {}", e))?;
+        jieba.has_word(word) && words.get(word).is_none_or(|w| w.freq > 0)
+    };
+    if !present {
+        return Ok(false);
+    }
+    add_word(&state, word, Some(0), None)?;
+    state
+        .custom_words
+        .write()
+        .map_err(|e| format!("lock error: {}", e))?
+        .remove(word);
+    persist(&state)?;
+    let entry = UserWord {
+        word: word.to_string(),
+        freq: 0,
+        tag: None,
+    };
+    journal(&app, &state, vec![(entry.word.clone(), Some(&entry))]);
+    Ok(true)
+}
+
+/**
+ * 清空自定义词典：删除词典文件并重建 jieba
+ * 重建后只保留默认词典和通过 load_jieba_dict 加载的词典文件
+ */
+#[tauri::command]
+pub fn reset_custom_dict(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    let path = custom_dict_path(&state)?;
+    match fs::remove_file(&path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(format!("remove {} failed: {}", path.display(), e)),
+    }
+    let removed: Vec<(String, Option<&UserWord>)> = std::mem::take(
+        &mut *state
+            .user_words
+            .lock()
+            .map_err(|e| format!("lock error: {}", e))?,
+    )
+    .into_keys()
+    .map(|word| (word, None))
+    .collect();
+    state
+        .custom_words
+        .write()
+        .map_err(|e| format!("lock error: {}", e))?
+        .clear();
+    rebuild(&state)?;
+    journal(&app, &state, removed);
+    Ok(())
+}
+
+/**
+ * 把 jieba 词典重置为默认词典（Jieba::new()），丢弃本次会话中通过 load_jieba_dict 加载的
+ * 词典文件（用于切换词典配置），list_custom_words 清空
+ * keep_user_words: 为 true 时在默认词典上重新加入用户词条；默认 false，用户词条不进入新词典，
+ * 但仍保留在自定义词典文件中（重启后重新加载），要删除用户词条请用 reset_custom_dict
+ * 新词典在锁外构建，写锁内只做替换，并发的分词只会用到旧词典或完整的新词典
+ */
+#[tauri::command]
+pub fn reset_jieba_dict(
+    state: State<'_, AppState>,
+    keep_user_words: Option<bool>,
+) -> Result<(), String> {
+    let _update = update_guard(&state)?;
+    let mut fresh = Jieba::new();
+    if keep_user_words.unwrap_or(false) {
+        for w in user_words(&state)? {
+            fresh.add_word(&w.word, Some(w.freq), w.tag.as_deref());
+        }
+    }
+    swap_in(&state, fresh)?;
+    state
+        .loaded_dicts
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?
+        .clear();
+    state
+        .custom_words
+        .write()
+        .map_err(|e| format!("lock error: {}", e))?
+        .clear();
+    Ok(())
+}
+
+/**
+ * 列出本次启动后添加的自定义词（按字典序）
+ */
+#[tauri::command]
+pub fn list_custom_words(state: State<'_, AppState>) -> Vec<String> {
+    let mut words: Vec<String> = match state.custom_words.read() {
+        Ok(words) => words.iter().cloned().collect(),
+        Err(_) => return Vec::new(),
+    };
+    words.sort();
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_freq_word_is_no_longer_kept_together() {
+        let mut jieba = Jieba::new();
+        let sentence = "我在北京大学读书";
+        assert!(jieba.cut(sentence, true).contains(&"北京大学"));
+
+        // remove_user_word 通过 add_word(word, Some(0)) 屏蔽词条
+        jieba.add_word("北京大学", Some(0), None);
+        assert_eq!(
+            jieba.cut(sentence, true),
+            vec!["我", "在", "北京", "大学", "读书"]
+        );
+    }
+}
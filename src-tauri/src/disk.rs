@@ -1,6 +1,18 @@
+use crate::AppState;
+use crate::events;
+use schemars::JsonSchema;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 use std::fs;
-use std::path::Path;
+use std::io::{ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use sysinfo::{DiskExt, System, SystemExt};
+use tauri::{AppHandle, Manager, State};
 
 #[tauri::command]
 pub fn get_drive_size(path: String) -> Result<(u64, u64), String> {
@@ -14,6 +26,38 @@ pub fn get_drive_size(path: String) -> Result<(u64, u64), String> {
     Err("未找到指定盘符".to_string())
 }
 
+/// 磁盘信息
+#[derive(Serialize)]
+pub struct DriveInfo {
+    pub name: String,
+    pub mount_point: String,
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+    pub is_removable: bool,
+}
+
+/**
+ * 列出所有磁盘（Windows 为盘符，Unix 为挂载点）
+ * 读取失败（如断开的网络盘，总容量为 0）的磁盘会被跳过
+ */
+#[tauri::command]
+pub fn list_drives() -> Vec<DriveInfo> {
+    let mut sys = System::new();
+    sys.refresh_disks_list();
+    sys.refresh_disks();
+    sys.disks()
+        .iter()
+        .filter(|disk| disk.total_space() > 0)
+        .map(|disk| DriveInfo {
+            name: disk.name().to_string_lossy().into_owned(),
+            mount_point: disk.mount_point().to_string_lossy().into_owned(),
+            total_bytes: disk.total_space(),
+            available_bytes: disk.available_space(),
+            is_removable: disk.is_removable(),
+        })
+        .collect()
+}
+
 #[tauri::command]
 pub fn get_folder_size(path: String) -> Result<u64, String> {
     fn dir_size(path: &Path) -> u64 {
@@ -37,3 +81,525 @@ pub fn get_folder_size(path: String) -> Result<u64, String> {
         Err("文件夹路径不存在".to_string())
     }
 }
+
+/// "folder:scan" 进度事件负载
+#[derive(Serialize, Clone, JsonSchema)]
+pub struct FolderScanProgress {
+    pub scan_id: String,
+    pub total_bytes: u64,
+    pub files: u64,
+    pub current_path: String,
+}
+
+/// 文件夹扫描结果，status 为 "completed" 或 "cancelled"
+#[derive(Serialize)]
+pub struct FolderScanResult {
+    pub status: String,
+    pub total_bytes: u64,
+    pub files: u64,
+}
+
+/**
+ * 异步统计文件夹大小（可取消，带进度）
+ * 在后台线程遍历，每 200ms 通过 "folder:scan" 事件上报累计大小与当前路径
+ * 无权限等无法读取的目录会被跳过
+ */
+#[tauri::command]
+pub async fn get_folder_size_progress(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    scan_id: String,
+) -> Result<FolderScanResult, String> {
+    let root = PathBuf::from(&path);
+    if !root.exists() {
+        return Err("文件夹路径不存在".to_string());
+    }
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    state
+        .folder_scans
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?
+        .insert(scan_id.clone(), cancel.clone());
+
+    let id = scan_id.clone();
+    let app_for_task = app.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let mut stack = vec![root];
+        let mut total_bytes = 0u64;
+        let mut files = 0u64;
+        let mut last_emit = Instant::now();
+
+        while let Some(dir) = stack.pop() {
+            if cancel.load(Ordering::Relaxed) {
+                return FolderScanResult {
+                    status: "cancelled".into(),
+                    total_bytes,
+                    files,
+                };
+            }
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                if metadata.is_dir() {
+                    stack.push(entry.path());
+                } else {
+                    total_bytes += metadata.len();
+                    files += 1;
+                }
+            }
+
+            if last_emit.elapsed() >= Duration::from_millis(200) {
+                last_emit = Instant::now();
+                let _ = events::emit_event(
+                    &app_for_task,
+                    events::Event::FolderScan(FolderScanProgress {
+                        scan_id: id.clone(),
+                        total_bytes,
+                        files,
+                        current_path: dir.to_string_lossy().into_owned(),
+                    }),
+                );
+            }
+        }
+
+        FolderScanResult {
+            status: "completed".into(),
+            total_bytes,
+            files,
+        }
+    })
+    .await
+    .map_err(|e| format!("scan task error: {}", e));
+
+    if let Ok(mut scans) = app.state::<AppState>().folder_scans.lock() {
+        scans.remove(&scan_id);
+    }
+    result
+}
+
+/**
+ * 取消正在进行的文件夹扫描
+ */
+#[tauri::command]
+pub fn cancel_folder_scan(state: State<'_, AppState>, scan_id: String) -> Result<bool, String> {
+    let scans = state
+        .folder_scans
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?;
+    match scans.get(&scan_id) {
+        Some(flag) => {
+            flag.store(true, Ordering::Relaxed);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// 大文件条目
+#[derive(Serialize)]
+pub struct LargeFile {
+    pub path: String,
+    pub size_bytes: u64,
+}
+
+/// 大文件查找结果；permission_denied 为因无权限而跳过的条目数
+#[derive(Serialize)]
+pub struct LargeFilesResult {
+    pub files: Vec<LargeFile>,
+    pub permission_denied: u64,
+}
+
+/**
+ * 查找目录下最大的 top_n 个文件（按大小降序）
+ * 小于 min_bytes 的文件被忽略；使用容量为 top_n 的小顶堆，内存占用与文件总数无关
+ * 不跟随符号链接；无权限的目录/文件计入 permission_denied，不中断扫描
+ */
+#[tauri::command]
+pub async fn find_large_files(
+    path: String,
+    top_n: usize,
+    min_bytes: Option<u64>,
+) -> Result<LargeFilesResult, String> {
+    let root = PathBuf::from(&path);
+    if !root.is_dir() {
+        return Err("文件夹路径不存在".to_string());
+    }
+    let min_bytes = min_bytes.unwrap_or(0);
+
+    tokio::task::spawn_blocking(move || {
+        let mut heap: BinaryHeap<Reverse<(u64, PathBuf)>> = BinaryHeap::with_capacity(top_n + 1);
+        let mut permission_denied = 0u64;
+        let mut stack = vec![root];
+
+        while let Some(dir) = stack.pop() {
+            let entries = match fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    if e.kind() == ErrorKind::PermissionDenied {
+                        permission_denied += 1;
+                    }
+                    continue;
+                }
+            };
+            for entry in entries.flatten() {
+                // file_type 不跟随符号链接
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                if file_type.is_symlink() {
+                    continue;
+                }
+                if file_type.is_dir() {
+                    stack.push(entry.path());
+                    continue;
+                }
+                let size = match entry.metadata() {
+                    Ok(m) => m.len(),
+                    Err(e) => {
+                        if e.kind() == ErrorKind::PermissionDenied {
+                            permission_denied += 1;
+                        }
+                        continue;
+                    }
+                };
+                if size < min_bytes || top_n == 0 {
+                    continue;
+                }
+                if heap.len() < top_n {
+                    heap.push(Reverse((size, entry.path())));
+                } else if heap.peek().is_some_and(|Reverse((min, _))| size > *min) {
+                    heap.pop();
+                    heap.push(Reverse((size, entry.path())));
+                }
+            }
+        }
+
+        // into_sorted_vec 对 Reverse 升序即原值降序
+        let files = heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((size_bytes, path))| LargeFile {
+                path: path.to_string_lossy().into_owned(),
+                size_bytes,
+            })
+            .collect();
+        LargeFilesResult {
+            files,
+            permission_denied,
+        }
+    })
+    .await
+    .map_err(|e| format!("scan task error: {}", e))
+}
+
+/// 目录条目；时间为 Unix 毫秒时间戳，无法获取时为 0（如部分 Linux 文件系统没有创建时间）
+#[derive(Serialize)]
+pub struct FileEntry {
+    pub name: String,
+    pub path: String,
+    pub is_dir: bool,
+    /// 文件大小（字节），目录为 0
+    pub size: u64,
+    pub modified_ms: u64,
+    pub created_ms: u64,
+    /// 小写扩展名，不含 "."
+    pub extension: Option<String>,
+    pub mime_type: Option<String>,
+}
+
+/// 递归列出时未指定 max_depth 的深度上限（防止跟随符号链接时陷入循环）
+const DEFAULT_MAX_DEPTH: u32 = 10;
+
+/// 按扩展名识别的 MIME 类型
+const MIME_TYPES: &[(&str, &str)] = &[
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("bmp", "image/bmp"),
+    ("ico", "image/x-icon"),
+    ("svg", "image/svg+xml"),
+    ("tif", "image/tiff"),
+    ("tiff", "image/tiff"),
+    ("mp3", "audio/mpeg"),
+    ("wav", "audio/wav"),
+    ("ogg", "audio/ogg"),
+    ("flac", "audio/flac"),
+    ("mp4", "video/mp4"),
+    ("webm", "video/webm"),
+    ("mov", "video/quicktime"),
+    ("mkv", "video/x-matroska"),
+    ("avi", "video/x-msvideo"),
+    ("txt", "text/plain"),
+    ("md", "text/markdown"),
+    ("csv", "text/csv"),
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("css", "text/css"),
+    ("js", "text/javascript"),
+    ("json", "application/json"),
+    ("xml", "application/xml"),
+    ("pdf", "application/pdf"),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("7z", "application/x-7z-compressed"),
+    ("rar", "application/vnd.rar"),
+    ("doc", "application/msword"),
+    (
+        "docx",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    ),
+    ("xls", "application/vnd.ms-excel"),
+    (
+        "xlsx",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    ),
+    ("ppt", "application/vnd.ms-powerpoint"),
+    (
+        "pptx",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    ),
+    ("exe", "application/vnd.microsoft.portable-executable"),
+];
+
+fn mime_type(extension: &str) -> Option<&'static str> {
+    MIME_TYPES
+        .iter()
+        .find(|(ext, _)| *ext == extension)
+        .map(|(_, mime)| *mime)
+}
+
+fn unix_millis(time: std::io::Result<SystemTime>) -> u64 {
+    time.ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// 以 "." 开头的文件；Windows 上还包括带隐藏属性的文件
+fn is_hidden(name: &str, metadata: &fs::Metadata) -> bool {
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        if metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0 {
+            return true;
+        }
+    }
+    #[cfg(not(windows))]
+    let _ = metadata;
+    name.starts_with('.')
+}
+
+/// 列目录的选项
+struct ListOptions {
+    include_hidden: bool,
+    follow_symlinks: bool,
+    max_depth: u32,
+}
+
+/// 先序遍历：每个目录内目录在前、按名称排序，子目录的内容紧跟在该目录之后
+fn list_dir(dir: &Path, depth: u32, options: &ListOptions, out: &mut Vec<FileEntry>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut children: Vec<(PathBuf, fs::Metadata)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let metadata = if options.follow_symlinks {
+                // 失效的链接按链接本身处理
+                fs::metadata(&path).or_else(|_| fs::symlink_metadata(&path))
+            } else {
+                fs::symlink_metadata(&path)
+            };
+            metadata.ok().map(|metadata| (path, metadata))
+        })
+        .collect();
+    children.sort_by(|(a, ma), (b, mb)| mb.is_dir().cmp(&ma.is_dir()).then_with(|| a.cmp(b)));
+
+    for (path, metadata) in children {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if !options.include_hidden && is_hidden(&name, &metadata) {
+            continue;
+        }
+        let is_dir = metadata.is_dir();
+        let extension = match is_dir {
+            true => None,
+            false => path.extension().map(|e| e.to_string_lossy().to_lowercase()),
+        };
+        out.push(FileEntry {
+            name,
+            path: path.to_string_lossy().into_owned(),
+            is_dir,
+            size: if is_dir { 0 } else { metadata.len() },
+            modified_ms: unix_millis(metadata.modified()),
+            created_ms: unix_millis(metadata.created()),
+            mime_type: extension.as_deref().and_then(mime_type).map(String::from),
+            extension,
+        });
+        if is_dir && depth < options.max_depth {
+            list_dir(&path, depth + 1, options, out);
+        }
+    }
+}
+
+/**
+ * 列出文件夹内容（含大小、时间、扩展名和按扩展名识别的 MIME 类型）
+ * include_hidden: 是否包含隐藏文件（以 "." 开头；Windows 上还包括带隐藏属性的文件）
+ * recursive: 为 true 时列出子目录内容，深度由 max_depth 限制（1 为只列直接子项，默认 10）；
+ * 为 false 时忽略 max_depth，只列直接子项
+ * follow_symlinks: 为 true 时指向目录的符号链接按目录处理并进入（深度上限防止循环）；
+ * 否则符号链接按普通条目列出，不进入
+ * 结果按目录先序排列：每个目录内目录在前、按名称排序，子目录的内容紧跟在该目录之后
+ * 无权限等无法读取的子目录会被跳过
+ */
+#[tauri::command]
+pub async fn get_folder_contents(
+    path: String,
+    include_hidden: bool,
+    recursive: bool,
+    max_depth: Option<u32>,
+    follow_symlinks: Option<bool>,
+) -> Result<Vec<FileEntry>, String> {
+    let root = PathBuf::from(&path);
+    if !root.is_dir() {
+        return Err("文件夹路径不存在".to_string());
+    }
+    let options = ListOptions {
+        include_hidden,
+        follow_symlinks: follow_symlinks.unwrap_or(false),
+        max_depth: match recursive {
+            true => max_depth.unwrap_or(DEFAULT_MAX_DEPTH).max(1),
+            false => 1,
+        },
+    };
+
+    tokio::task::spawn_blocking(move || {
+        let mut entries = Vec::new();
+        list_dir(&root, 1, &options, &mut entries);
+        entries
+    })
+    .await
+    .map_err(|e| format!("list task error: {}", e))
+}
+
+/// 计算文件哈希时每次读取的字节数
+const HASH_CHUNK: usize = 64 * 1024;
+
+/// 支持的哈希算法
+enum FileHasher {
+    Sha256(Sha256),
+    Md5(md5::Md5),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl FileHasher {
+    fn new(algorithm: &str) -> Result<Self, String> {
+        match algorithm.to_ascii_lowercase().as_str() {
+            "sha256" => Ok(FileHasher::Sha256(Sha256::new())),
+            "md5" => Ok(FileHasher::Md5(md5::Md5::new())),
+            "blake3" => Ok(FileHasher::Blake3(Box::default())),
+            other => Err(format!("unsupported hash algorithm: {}", other)),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            FileHasher::Sha256(h) => h.update(data),
+            FileHasher::Md5(h) => h.update(data),
+            FileHasher::Blake3(h) => {
+                h.update(data);
+            }
+        }
+    }
+
+    /// 小写十六进制
+    fn finish(self) -> String {
+        match self {
+            FileHasher::Sha256(h) => format!("{:x}", h.finalize()),
+            FileHasher::Md5(h) => format!("{:x}", h.finalize()),
+            FileHasher::Blake3(h) => h.finalize().to_hex().to_string(),
+        }
+    }
+}
+
+/// 分块读取文件计算哈希（不把整个文件读入内存）
+fn hash_file(path: &Path, mut hasher: FileHasher) -> Result<String, String> {
+    let mut file = fs::File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut buf = vec![0u8; HASH_CHUNK];
+    loop {
+        let n = file
+            .read(&mut buf)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finish())
+}
+
+/**
+ * 计算文件哈希，返回小写十六进制
+ * algorithm: "sha256" | "md5" | "blake3"（不区分大小写）
+ * 按 64 KB 分块读取，大文件不会整个读入内存
+ */
+#[tauri::command]
+pub async fn compute_file_hash(path: String, algorithm: String) -> Result<String, String> {
+    let hasher = FileHasher::new(&algorithm)?;
+    tokio::task::spawn_blocking(move || hash_file(Path::new(&path), hasher))
+        .await
+        .map_err(|e| format!("hash task error: {}", e))?
+}
+
+/**
+ * 计算内存数据的哈希，algorithm 和返回值同 compute_file_hash
+ */
+#[tauri::command]
+pub fn compute_bytes_hash(data: Vec<u8>, algorithm: String) -> Result<String, String> {
+    let mut hasher = FileHasher::new(&algorithm)?;
+    hasher.update(&data);
+    Ok(hasher.finish())
+}
+
+/**
+ * 校验文件哈希，expected_hash 为十六进制（不区分大小写，忽略首尾空白）
+ * algorithm 同 compute_file_hash；文件无法读取时返回错误而不是 false
+ */
+#[tauri::command]
+pub async fn verify_file_hash(
+    path: String,
+    expected_hash: String,
+    algorithm: String,
+) -> Result<bool, String> {
+    let actual = compute_file_hash(path, algorithm).await?;
+    Ok(actual.eq_ignore_ascii_case(expected_hash.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_bytes_as_lowercase_hex() {
+        assert_eq!(
+            compute_bytes_hash(b"abc".to_vec(), "SHA256".into()).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            compute_bytes_hash(b"abc".to_vec(), "md5".into()).unwrap(),
+            "900150983cd24fb0d6963f7d28e17f72"
+        );
+        assert!(compute_bytes_hash(Vec::new(), "crc32".into()).is_err());
+    }
+}
@@ -0,0 +1,619 @@
+/*!
+ * 显示器状态监听
+ *
+ * 检测显示器休眠/唤醒并发送 "display:sleep" / "display:wake"，
+ * 同时维护 AppState.display_asleep，截图流等后台任务据此暂停。
+ *
+ * 原生信号：X11 下查询 DPMS；Windows 订阅 GUID_CONSOLE_DISPLAY_STATE 电源设置通知
+ * （即 WM_POWERBROADCAST 携带的显示器状态）；macOS 订阅 NSWorkspace 的
+ * screensDidSleep / screensDidWake 通知。其他平台（以及原生信号不可用时）退化为
+ * 连续多次采样全黑画面判定为休眠。
+ *
+ * 另有一个随应用启动的线程轮询屏幕布局，显示器插拔或分辨率、缩放变化时
+ * 发送 "displays:changed"（新增 / 移除的屏幕 id 及新的完整布局），
+ * 可通过 start_display_watcher / stop_display_watcher 控制。
+ */
+
+use crate::AppState;
+use crate::commands;
+use crate::events;
+use crate::screen_stream;
+use schemars::JsonSchema;
+use screenshots::Screen;
+use serde::Serialize;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+
+/// 连续多少次全黑判定为休眠
+const BLACK_FRAMES_FOR_SLEEP: u32 = 3;
+/// 采样块大小（像素）
+const PROBE_SIZE: u32 = 32;
+/// 亮度上限，低于此值视为黑色
+const BLACK_THRESHOLD: u8 = 8;
+/// 屏幕布局默认轮询间隔
+const SCREENS_POLL_INTERVAL: Duration = Duration::from_millis(2000);
+/// 休眠/唤醒默认检测间隔
+pub const DISPLAY_WATCH_INTERVAL: Duration = Duration::from_millis(2000);
+/// 布局变化后需保持稳定的时间，期间的连续变化合并为一次事件
+const SCREENS_SETTLE: Duration = Duration::from_millis(750);
+
+#[derive(Serialize, Clone, JsonSchema)]
+pub struct DisplayPowerEvent {
+    /// 原生信号来源（"dpms" / "console-display-state" / "workspace"）或 "black-frames"
+    pub source: String,
+    pub at: u64,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// 在主屏中心和四角各采样一小块，全部为黑色时返回 true
+fn primary_screen_is_black() -> Option<bool> {
+    let screens = Screen::all().ok()?;
+    let screen = screens
+        .iter()
+        .find(|s| s.display_info.is_primary)
+        .or(screens.first())?;
+    let d = screen.display_info;
+    if d.width < PROBE_SIZE * 2 || d.height < PROBE_SIZE * 2 {
+        return None;
+    }
+
+    let (w, h) = (d.width - PROBE_SIZE, d.height - PROBE_SIZE);
+    let probes = [(0, 0), (w, 0), (0, h), (w, h), (w / 2, h / 2)];
+    for (px, py) in probes {
+        let image = screen
+            .capture_area(px as i32, py as i32, PROBE_SIZE, PROBE_SIZE)
+            .ok()?;
+        let rgba = image::load_from_memory(image.buffer()).ok()?.to_rgba8();
+        if rgba.pixels().any(|p| {
+            p.0[0] > BLACK_THRESHOLD || p.0[1] > BLACK_THRESHOLD || p.0[2] > BLACK_THRESHOLD
+        }) {
+            return Some(false);
+        }
+    }
+    Some(true)
+}
+
+/// 启动显示器休眠/唤醒监听线程；已在运行时返回 false（截图流开始时也会调用）
+pub fn spawn_display_watch(
+    app: &AppHandle,
+    state: &AppState,
+    interval: Duration,
+) -> Result<bool, String> {
+    let mut guard = state
+        .display_watch
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?;
+    if guard.is_some() {
+        return Ok(false);
+    }
+
+    let stop_flag = Arc::new(AtomicBool::new(false));
+    let stop_flag_thread = stop_flag.clone();
+    let asleep = state.display_asleep.clone();
+    let app = app.clone();
+
+    let handle = thread::spawn(move || {
+        println!("[display_watch] thread started");
+        let mut black_frames = 0u32;
+
+        while !stop_flag_thread.load(Ordering::Relaxed) {
+            let (now_asleep, source) = match platform::display_off() {
+                Some(off) => (off, platform::SOURCE),
+                None => {
+                    match primary_screen_is_black() {
+                        Some(true) => black_frames += 1,
+                        _ => black_frames = 0,
+                    }
+                    (black_frames >= BLACK_FRAMES_FOR_SLEEP, "black-frames")
+                }
+            };
+
+            let was_asleep = asleep.swap(now_asleep, Ordering::Relaxed);
+            if was_asleep != now_asleep {
+                let payload = DisplayPowerEvent {
+                    source: source.into(),
+                    at: now_millis(),
+                };
+                let event = if now_asleep {
+                    events::Event::DisplaySleep(payload)
+                } else {
+                    events::Event::DisplayWake(payload)
+                };
+                if let Err(e) = events::emit_event(&app, event) {
+                    eprintln!("[display_watch] emit error: {:?}", e);
+                }
+            }
+
+            thread::sleep(interval);
+        }
+        println!("[display_watch] thread exiting");
+    });
+
+    *guard = Some((stop_flag, handle));
+    Ok(true)
+}
+
+/**
+ * 启动显示器休眠/唤醒监听
+ * interval_ms: 检测间隔（默认 2000ms）
+ */
+#[tauri::command]
+pub fn start_display_watch(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    interval_ms: Option<u64>,
+) -> Result<String, String> {
+    let interval = interval_ms.map_or(DISPLAY_WATCH_INTERVAL, |ms| {
+        Duration::from_millis(ms.max(200))
+    });
+    if spawn_display_watch(&app, &state, interval)? {
+        Ok("started".into())
+    } else {
+        Ok("already running".into())
+    }
+}
+
+/**
+ * 停止显示器休眠/唤醒监听
+ * 有画面流在运行时继续监听（画面流依赖它暂停截图），返回 "in use by screen streams"
+ */
+#[tauri::command]
+pub fn stop_display_watch(state: State<'_, AppState>) -> Result<String, String> {
+    if screen_stream::has_active(&state)? {
+        return Ok("in use by screen streams".into());
+    }
+    let mut guard = state
+        .display_watch
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?;
+    match guard.take() {
+        Some((flag, handle)) => {
+            flag.store(true, Ordering::Relaxed);
+            std::thread::spawn(move || {
+                let _ = handle.join();
+            });
+            Ok("stopping".into())
+        }
+        None => Ok("not running".into()),
+    }
+}
+
+/// "displays:changed" 事件负载：新增 / 移除的屏幕 id 及变化后的完整布局（字段同 get_display_info）
+#[derive(Serialize, Clone, JsonSchema)]
+pub struct DisplaysChanged {
+    pub added: Vec<u32>,
+    pub removed: Vec<u32>,
+    #[serde(flatten)]
+    pub info: commands::MultiScreenInfo,
+}
+
+/// 布局变化后等待其稳定，返回稳定后的布局；期间收到停止信号时返回 None
+fn settle_screens(
+    mut info: commands::MultiScreenInfo,
+    stop: &AtomicBool,
+) -> Option<commands::MultiScreenInfo> {
+    loop {
+        thread::sleep(SCREENS_SETTLE);
+        if stop.load(Ordering::Relaxed) {
+            return None;
+        }
+        match commands::get_display_info() {
+            Ok(next) if next.screens == info.screens => return Some(info),
+            Ok(next) => info = next,
+            Err(_) => {}
+        }
+    }
+}
+
+/// 对比前后布局，得到事件负载
+fn diff_screens(
+    previous: &[commands::DisplayInfo],
+    info: commands::MultiScreenInfo,
+) -> DisplaysChanged {
+    let added = info
+        .screens
+        .iter()
+        .filter(|s| !previous.iter().any(|p| p.id == s.id))
+        .map(|s| s.id)
+        .collect();
+    let removed = previous
+        .iter()
+        .filter(|p| !info.screens.iter().any(|s| s.id == p.id))
+        .map(|p| p.id)
+        .collect();
+    DisplaysChanged {
+        added,
+        removed,
+        info,
+    }
+}
+
+/// 启动屏幕布局监听线程；已在运行时返回 false
+fn spawn_screens_watch(
+    app: &AppHandle,
+    state: &AppState,
+    interval: Duration,
+) -> Result<bool, String> {
+    let mut guard = state
+        .screens_watch
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?;
+    if guard.is_some() {
+        return Ok(false);
+    }
+
+    let stop_flag = Arc::new(AtomicBool::new(false));
+    let stop_flag_thread = stop_flag.clone();
+    let app = app.clone();
+
+    let handle = thread::spawn(move || {
+        // 只读取屏幕信息做比较，不截图
+        let mut last = commands::get_display_info()
+            .map(|info| info.screens)
+            .unwrap_or_default();
+
+        while !stop_flag_thread.load(Ordering::Relaxed) {
+            thread::sleep(interval);
+            let Ok(info) = commands::get_display_info() else {
+                continue;
+            };
+            if info.screens == last {
+                continue;
+            }
+            let Some(info) = settle_screens(info, &stop_flag_thread) else {
+                break;
+            };
+            // 短暂变化后又恢复原状
+            if info.screens == last {
+                continue;
+            }
+
+            let changed = diff_screens(&last, info);
+            last = changed.info.screens.clone();
+            if let Err(e) = events::emit_event(&app, events::Event::DisplaysChanged(changed)) {
+                eprintln!("[screens_watch] emit error: {:?}", e);
+            }
+        }
+    });
+
+    *guard = Some((stop_flag, handle));
+    Ok(true)
+}
+
+/// 应用启动时开始监听屏幕布局（setup 时调用）
+pub fn start_screens_watch(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    if let Err(e) = spawn_screens_watch(app, &state, SCREENS_POLL_INTERVAL) {
+        eprintln!("[screens_watch] start failed: {}", e);
+    }
+}
+
+/// 停止屏幕布局监听（退出时调用），不等待线程结束
+pub fn stop_screens_watch(state: &AppState) {
+    let watch = state.screens_watch.lock().ok().and_then(|mut g| g.take());
+    if let Some((flag, _handle)) = watch {
+        flag.store(true, Ordering::Relaxed);
+    }
+}
+
+/**
+ * 启动屏幕布局监听（应用启动时已自动开启，stop_display_watcher 之后可用此命令重新开启）
+ * 显示器增减、位置、分辨率、缩放或主屏变化时发送 "displays:changed"，
+ * 负载包含新增 / 移除的屏幕 id 和新的完整布局；连续变化在稳定后合并为一次事件
+ * interval_ms: 轮询间隔（默认 2000ms）
+ */
+#[tauri::command]
+pub fn start_display_watcher(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    interval_ms: Option<u64>,
+) -> Result<String, String> {
+    let interval = interval_ms.map_or(SCREENS_POLL_INTERVAL, |ms| {
+        Duration::from_millis(ms.max(200))
+    });
+    if spawn_screens_watch(&app, &state, interval)? {
+        Ok("started".into())
+    } else {
+        Ok("already running".into())
+    }
+}
+
+/**
+ * 停止屏幕布局监听
+ */
+#[tauri::command]
+pub fn stop_display_watcher(state: State<'_, AppState>) -> Result<String, String> {
+    let mut guard = state
+        .screens_watch
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?;
+    match guard.take() {
+        Some((flag, handle)) => {
+            flag.store(true, Ordering::Relaxed);
+            std::thread::spawn(move || {
+                let _ = handle.join();
+            });
+            Ok("stopping".into())
+        }
+        None => Ok("not running".into()),
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::os::raw::{c_char, c_int, c_void};
+
+    #[link(name = "X11")]
+    unsafe extern "C" {
+        fn XOpenDisplay(name: *const c_char) -> *mut c_void;
+        fn XCloseDisplay(display: *mut c_void) -> c_int;
+    }
+
+    #[link(name = "Xext")]
+    unsafe extern "C" {
+        fn DPMSCapable(display: *mut c_void) -> c_int;
+        fn DPMSInfo(display: *mut c_void, power_level: *mut u16, state: *mut c_int) -> c_int;
+    }
+
+    const DPMS_MODE_ON: u16 = 0;
+
+    pub const SOURCE: &str = "dpms";
+
+    /// 通过 DPMS 查询显示器是否关闭，不可用时返回 None
+    pub fn display_off() -> Option<bool> {
+        std::env::var_os("DISPLAY")?;
+        unsafe {
+            let display = XOpenDisplay(std::ptr::null());
+            if display.is_null() {
+                return None;
+            }
+            let mut level: u16 = DPMS_MODE_ON;
+            let mut enabled: c_int = 0;
+            let res =
+                if DPMSCapable(display) != 0 && DPMSInfo(display, &mut level, &mut enabled) != 0 {
+                    // DPMS 未启用时显示器不会被关闭
+                    Some(enabled != 0 && level != DPMS_MODE_ON)
+                } else {
+                    None
+                };
+            XCloseDisplay(display);
+            res
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::ffi::c_void;
+    use std::sync::OnceLock;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[repr(C)]
+    struct Guid {
+        data1: u32,
+        data2: u16,
+        data3: u16,
+        data4: [u8; 8],
+    }
+
+    #[repr(C)]
+    struct DeviceNotifySubscribeParameters {
+        callback: unsafe extern "system" fn(*mut c_void, u32, *mut c_void) -> u32,
+        context: *mut c_void,
+    }
+
+    #[repr(C)]
+    struct PowerBroadcastSetting {
+        power_setting: Guid,
+        data_length: u32,
+        data: [u8; 1],
+    }
+
+    #[link(name = "powrprof")]
+    unsafe extern "system" {
+        fn PowerSettingRegisterNotification(
+            setting: *const Guid,
+            flags: u32,
+            recipient: *mut c_void,
+            handle: *mut *mut c_void,
+        ) -> u32;
+    }
+
+    const GUID_CONSOLE_DISPLAY_STATE: Guid = Guid {
+        data1: 0x6fe6_9556,
+        data2: 0x704a,
+        data3: 0x47a0,
+        data4: [0x8f, 0x24, 0xc2, 0x8d, 0x93, 0x6f, 0xda, 0x47],
+    };
+    const DEVICE_NOTIFY_CALLBACK: u32 = 2;
+    const PBT_POWERSETTINGCHANGE: u32 = 0x8013;
+    /// 显示器状态：0 关闭，1 开启，2 变暗
+    const DISPLAY_STATE_OFF: u32 = 0;
+    const DISPLAY_STATE_UNKNOWN: u32 = u32::MAX;
+
+    pub const SOURCE: &str = "console-display-state";
+
+    static DISPLAY_STATE: AtomicU32 = AtomicU32::new(DISPLAY_STATE_UNKNOWN);
+    static REGISTERED: OnceLock<bool> = OnceLock::new();
+
+    /// 与窗口收到的 WM_POWERBROADCAST / PBT_POWERSETTINGCHANGE 相同，只是不需要窗口
+    unsafe extern "system" fn on_power_setting(
+        _context: *mut c_void,
+        kind: u32,
+        setting: *mut c_void,
+    ) -> u32 {
+        if kind == PBT_POWERSETTINGCHANGE && !setting.is_null() {
+            let setting = unsafe { &*(setting as *const PowerBroadcastSetting) };
+            if setting.data_length >= 4 {
+                let value =
+                    unsafe { std::ptr::read_unaligned(setting.data.as_ptr() as *const u32) };
+                DISPLAY_STATE.store(value, Ordering::Relaxed);
+            }
+        }
+        0
+    }
+
+    /// 注册后系统会立即回调一次当前状态；注册在进程生命周期内保留
+    fn register() -> bool {
+        let params = Box::leak(Box::new(DeviceNotifySubscribeParameters {
+            callback: on_power_setting,
+            context: std::ptr::null_mut(),
+        }));
+        let mut handle = std::ptr::null_mut();
+        let res = unsafe {
+            PowerSettingRegisterNotification(
+                &GUID_CONSOLE_DISPLAY_STATE,
+                DEVICE_NOTIFY_CALLBACK,
+                params as *mut DeviceNotifySubscribeParameters as *mut c_void,
+                &mut handle,
+            )
+        };
+        if res != 0 {
+            eprintln!(
+                "[display_watch] PowerSettingRegisterNotification failed: {}",
+                res
+            );
+        }
+        res == 0
+    }
+
+    /// 显示器是否关闭（变暗视为开启），注册失败或尚未收到通知时返回 None
+    pub fn display_off() -> Option<bool> {
+        if !*REGISTERED.get_or_init(register) {
+            return None;
+        }
+        match DISPLAY_STATE.load(Ordering::Relaxed) {
+            DISPLAY_STATE_UNKNOWN => None,
+            state => Some(state == DISPLAY_STATE_OFF),
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ffi::{c_char, c_void};
+    use std::sync::OnceLock;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    type Id = *mut c_void;
+    type Sel = *const c_void;
+
+    #[link(name = "objc")]
+    unsafe extern "C" {
+        fn objc_getClass(name: *const c_char) -> Id;
+        fn objc_allocateClassPair(superclass: Id, name: *const c_char, extra: usize) -> Id;
+        fn objc_registerClassPair(class: Id);
+        fn class_addMethod(class: Id, sel: Sel, imp: *const c_void, types: *const c_char) -> bool;
+        fn sel_registerName(name: *const c_char) -> Sel;
+        fn objc_msgSend();
+    }
+
+    #[link(name = "AppKit", kind = "framework")]
+    unsafe extern "C" {
+        static NSWorkspaceScreensDidSleepNotification: Id;
+        static NSWorkspaceScreensDidWakeNotification: Id;
+    }
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    unsafe extern "C" {
+        fn CGMainDisplayID() -> u32;
+        fn CGDisplayIsAsleep(display: u32) -> u32;
+    }
+
+    pub const SOURCE: &str = "workspace";
+
+    static ASLEEP: AtomicBool = AtomicBool::new(false);
+    static REGISTERED: OnceLock<bool> = OnceLock::new();
+
+    extern "C" fn screens_did_sleep(_this: Id, _cmd: Sel, _notification: Id) {
+        ASLEEP.store(true, Ordering::Relaxed);
+    }
+
+    extern "C" fn screens_did_wake(_this: Id, _cmd: Sel, _notification: Id) {
+        ASLEEP.store(false, Ordering::Relaxed);
+    }
+
+    /// 创建一个响应 screensDidSleep: / screensDidWake: 的 NSObject 子类实例，
+    /// 注册到 NSWorkspace 的通知中心（通知在主线程的 run loop 上派发）
+    unsafe fn register_observer() -> bool {
+        let send: unsafe extern "C" fn(Id, Sel) -> Id =
+            unsafe { std::mem::transmute(objc_msgSend as unsafe extern "C" fn()) };
+        let add_observer: unsafe extern "C" fn(Id, Sel, Id, Sel, Id, Id) =
+            unsafe { std::mem::transmute(objc_msgSend as unsafe extern "C" fn()) };
+        unsafe {
+            let sleep_sel = sel_registerName(c"screensDidSleep:".as_ptr());
+            let wake_sel = sel_registerName(c"screensDidWake:".as_ptr());
+
+            let class = objc_allocateClassPair(
+                objc_getClass(c"NSObject".as_ptr()),
+                c"LuckyDisplaySleepObserver".as_ptr(),
+                0,
+            );
+            if class.is_null() {
+                return false;
+            }
+            let types = c"v@:@".as_ptr();
+            class_addMethod(class, sleep_sel, screens_did_sleep as *const c_void, types);
+            class_addMethod(class, wake_sel, screens_did_wake as *const c_void, types);
+            objc_registerClassPair(class);
+
+            // observer 不释放，通知中心在进程生命周期内持有它
+            let observer = send(class, sel_registerName(c"new".as_ptr()));
+            let workspace = send(
+                objc_getClass(c"NSWorkspace".as_ptr()),
+                sel_registerName(c"sharedWorkspace".as_ptr()),
+            );
+            let center = send(workspace, sel_registerName(c"notificationCenter".as_ptr()));
+            if observer.is_null() || center.is_null() {
+                return false;
+            }
+            let add_sel = sel_registerName(c"addObserver:selector:name:object:".as_ptr());
+            add_observer(
+                center,
+                add_sel,
+                observer,
+                sleep_sel,
+                NSWorkspaceScreensDidSleepNotification,
+                std::ptr::null_mut(),
+            );
+            add_observer(
+                center,
+                add_sel,
+                observer,
+                wake_sel,
+                NSWorkspaceScreensDidWakeNotification,
+                std::ptr::null_mut(),
+            );
+            // 注册前已经休眠的情况以当前状态为准
+            ASLEEP.store(CGDisplayIsAsleep(CGMainDisplayID()) != 0, Ordering::Relaxed);
+        }
+        true
+    }
+
+    /// 显示器是否休眠，通知注册失败时返回 None
+    pub fn display_off() -> Option<bool> {
+        if !*REGISTERED.get_or_init(|| unsafe { register_observer() }) {
+            return None;
+        }
+        Some(ASLEEP.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+mod platform {
+    pub const SOURCE: &str = "none";
+
+    /// 暂无可用的原生信号，由调用方退化为黑帧检测
+    pub fn display_off() -> Option<bool> {
+        None
+    }
+}
@@ -0,0 +1,272 @@
+use crate::AppState;
+use crate::events;
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+use sysinfo::{Pid, PidExt, System, SystemExt};
+use tauri::{AppHandle, Manager, State};
+
+/// 启动参数：以访客/演示模式启动
+pub const EPHEMERAL_FLAG: &str = "--ephemeral";
+
+/// 沙箱目录名前缀，后缀为进程 pid
+const SANDBOX_PREFIX: &str = "lucky-ephemeral-";
+
+/// 临时模式下应用 identifier 的后缀，后接进程 pid
+const IDENTIFIER_SUFFIX: &str = ".ephemeral-";
+
+/// 临时模式状态
+#[derive(Serialize, Clone)]
+pub struct EphemeralStatus {
+    pub enabled: bool,
+    pub sandbox_dir: Option<String>,
+    pub expires_in_secs: Option<u64>,
+}
+
+/// 启动参数中是否包含 --ephemeral
+pub fn flag_from_args() -> bool {
+    std::env::args().any(|a| a == EPHEMERAL_FLAG)
+}
+
+/// 创建当前进程的沙箱目录
+pub fn create_sandbox() -> Result<PathBuf, String> {
+    let dir = std::env::temp_dir().join(format!("{}{}", SANDBOX_PREFIX, std::process::id()));
+    fs::create_dir_all(&dir).map_err(|e| format!("mkdir error: {}", e))?;
+    Ok(dir)
+}
+
+/**
+ * 临时模式使用的应用 identifier
+ * 插件按 identifier 解析应用目录（SQLite、store、stronghold vault、webview 数据等），
+ * 改用带 pid 的 identifier 后这些目录都与正常模式分开，退出时由 remove_app_dirs 擦除
+ */
+pub fn sandbox_identifier(identifier: &str) -> String {
+    format!("{}{}{}", identifier, IDENTIFIER_SUFFIX, std::process::id())
+}
+
+/// 清理上次非正常退出遗留的沙箱（对应进程已不存在）
+pub fn purge_stale_sandboxes() {
+    purge_stale(&std::env::temp_dir(), SANDBOX_PREFIX);
+}
+
+/// 清理上次非正常退出遗留的临时模式应用目录；identifier 为正常模式的 identifier
+pub fn purge_stale_app_dirs(app: &AppHandle, identifier: &str) {
+    let prefix = format!("{}{}", identifier, IDENTIFIER_SUFFIX);
+    let path = app.path();
+    let mut bases: Vec<PathBuf> = [
+        path.data_dir(),
+        path.local_data_dir(),
+        path.config_dir(),
+        path.cache_dir(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    bases.dedup();
+    for base in bases {
+        purge_stale(&base, &prefix);
+    }
+}
+
+/// 擦除本次临时模式的应用目录（只处理 identifier 带临时模式后缀的目录）
+pub fn remove_app_dirs(app: &AppHandle) {
+    let path = app.path();
+    let dirs = [
+        path.app_data_dir(),
+        path.app_local_data_dir(),
+        path.app_config_dir(),
+        path.app_cache_dir(),
+        path.app_log_dir(),
+    ];
+    let marker = format!("{}{}", IDENTIFIER_SUFFIX, std::process::id());
+    for dir in dirs.into_iter().flatten() {
+        if dir.to_string_lossy().contains(&marker) && dir.exists() {
+            secure_remove_dir(&dir);
+        }
+    }
+}
+
+/// 删除 dir 下名称为 prefix + pid 且对应进程已不存在的目录
+fn purge_stale(dir: &Path, prefix: &str) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut sys = System::new();
+    let current = std::process::id();
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Some(pid) = name
+            .strip_prefix(prefix)
+            .and_then(|s| s.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        if pid == current || sys.refresh_process(Pid::from_u32(pid)) {
+            continue;
+        }
+        println!("[ephemeral] purging stale sandbox {}", name);
+        secure_remove_dir(&entry.path());
+    }
+}
+
+/// 安全删除：先用 0 覆盖文件内容再删除
+pub fn secure_remove_dir(dir: &Path) {
+    fn wipe(path: &Path) {
+        let Ok(entries) = fs::read_dir(path) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let p = entry.path();
+            match entry.metadata() {
+                Ok(m) if m.is_dir() => wipe(&p),
+                Ok(m) => {
+                    if let Ok(mut f) = OpenOptions::new().write(true).open(&p) {
+                        let zeros = vec![0u8; 64 * 1024];
+                        let mut left = m.len();
+                        while left > 0 {
+                            let n = left.min(zeros.len() as u64) as usize;
+                            if f.write_all(&zeros[..n]).is_err() {
+                                break;
+                            }
+                            left -= n as u64;
+                        }
+                        let _ = f.sync_all();
+                    }
+                }
+                Err(_) => {}
+            }
+        }
+    }
+
+    wipe(dir);
+    if let Err(e) = fs::remove_dir_all(dir) {
+        eprintln!("[ephemeral] remove {} failed: {}", dir.display(), e);
+    }
+}
+
+/// 是否处于临时模式
+pub fn is_enabled(state: &AppState) -> bool {
+    state
+        .ephemeral_dir
+        .read()
+        .map(|g| g.is_some())
+        .unwrap_or(false)
+}
+
+/// 若处于临时模式，返回沙箱下分类目录中与原路径对应的位置（保留原路径的各级目录，
+/// 同一分类下不同的原路径不会冲突）；否则原样返回
+pub fn redirect(state: &AppState, category: &str, path: PathBuf) -> PathBuf {
+    match state.ephemeral_dir.read().ok().and_then(|g| g.clone()) {
+        Some(root) => sandboxed(&root, category, &path),
+        None => path,
+    }
+}
+
+/// 把路径映射到沙箱的分类目录下：只保留普通路径段（去掉盘符、根和 . / ..）
+fn sandboxed(root: &Path, category: &str, path: &Path) -> PathBuf {
+    let relative: PathBuf = path
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(part),
+            _ => None,
+        })
+        .collect();
+    root.join(category).join(relative)
+}
+
+/// 用户自选路径（导出等）在临时模式下需要显式 allow_persistent
+pub fn check_persist(state: &AppState, allow_persistent: bool) -> Result<(), String> {
+    let enabled = state
+        .ephemeral_dir
+        .read()
+        .map_err(|e| format!("lock error: {}", e))?
+        .is_some();
+    if enabled && !allow_persistent {
+        return Err("ephemeral mode: writing outside the sandbox requires allow_persistent".into());
+    }
+    Ok(())
+}
+
+pub fn status(state: &AppState) -> EphemeralStatus {
+    let dir = state.ephemeral_dir.read().ok().and_then(|g| g.clone());
+    let expires_in_secs = state
+        .ephemeral_deadline
+        .lock()
+        .ok()
+        .and_then(|g| *g)
+        .map(|t| t.saturating_duration_since(std::time::Instant::now()).as_secs());
+    EphemeralStatus {
+        enabled: dir.is_some(),
+        sandbox_dir: dir.map(|d| d.to_string_lossy().into_owned()),
+        expires_in_secs,
+    }
+}
+
+/**
+ * 访客/演示模式（临时模式）
+ *
+ * 只能通过启动参数 `--ephemeral` 开启，运行时没有开启的命令：
+ * - 后端自己的写入路径（图片缓存、截图历史、用户词典、同步状态等）重定向到系统临时目录下的沙箱；
+ * - 日志只写到沙箱，stronghold 的 salt 放在沙箱中，密钥存储只保存在内存中；
+ * - 应用 identifier 改为带 pid 的临时 identifier（见 sandbox_identifier），
+ *   SQLite、store、stronghold vault 等插件按应用目录解析的文件因此与正常模式分开。
+ * 插件在 Builder 阶段就已按应用目录初始化，运行时切换无法保证之前的写入不落盘，因此不提供运行时开启。
+ * 退出时沙箱和临时应用目录都会被安全擦除；若上一次退出不正常，下次启动时清理残留。
+ *
+ * 本命令为已开启的临时模式设置时限，到期后发送 "ephemeral:expired" 并退出应用；
+ * 未带 `--ephemeral` 启动时返回错误，时限只能设置一次
+ */
+#[tauri::command]
+pub fn set_ephemeral_time_limit(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    duration_secs: u64,
+) -> Result<EphemeralStatus, String> {
+    if !is_enabled(&state) {
+        return Err(format!(
+            "ephemeral mode is not enabled; start the app with {}",
+            EPHEMERAL_FLAG
+        ));
+    }
+
+    let mut deadline = state
+        .ephemeral_deadline
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?;
+    if deadline.is_none() {
+        *deadline = Some(std::time::Instant::now() + Duration::from_secs(duration_secs));
+        let app_for_thread = app.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_secs(duration_secs));
+            println!("[ephemeral] session expired after {}s", duration_secs);
+            let _ = events::emit_event(
+                &app_for_thread,
+                events::Event::EphemeralExpired(events::EphemeralExpired { duration_secs }),
+            );
+            // 退出时在 RunEvent::Exit 中擦除沙箱
+            app_for_thread.exit(0);
+        });
+    }
+    drop(deadline);
+
+    Ok(status(&state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redirect_keeps_per_call_subpath() {
+        let root = Path::new("/tmp/lucky-ephemeral-1");
+        let a = sandboxed(root, "cache", Path::new("/home/u/images"));
+        let b = sandboxed(root, "cache", Path::new("/home/u/avatars"));
+        assert_eq!(a, root.join("cache").join("home/u/images"));
+        assert_ne!(a, b);
+        assert!(sandboxed(root, "cache", Path::new("../x")).starts_with(root.join("cache")));
+    }
+}
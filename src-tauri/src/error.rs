@@ -0,0 +1,157 @@
+use crate::capture_backend::{CaptureUnsupported, ClipboardWriteFailed};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::fmt;
+use tauri_plugin_http::reqwest;
+
+/**
+ * 命令的结构化错误
+ *
+ * 序列化为 { kind, message }，前端按 kind 区分错误类别，message 可直接展示给用户；
+ * captureUnsupported / clipboardWriteFailed 另带 hint（处理建议）。
+ * kind: "io" | "network" | "decode" | "notFound" | "cancelled" |
+ *       "captureUnsupported" | "clipboardWriteFailed" | "other"
+ */
+#[derive(Clone, Debug)]
+pub enum CommandError {
+    /// 文件读写失败
+    Io(String),
+    /// 网络请求失败
+    Network(String),
+    /// 数据无法解析（图片解码、base64、校验值不匹配等）
+    Decode(String),
+    /// 请求的屏幕、窗口等不存在
+    NotFound(String),
+    /// 操作被取消
+    Cancelled(String),
+    /// 当前环境无法截图
+    CaptureUnsupported(CaptureUnsupported),
+    /// 截图已完成但写入剪贴板失败
+    ClipboardWriteFailed(ClipboardWriteFailed),
+    Other(String),
+}
+
+impl CommandError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CommandError::Io(_) => "io",
+            CommandError::Network(_) => "network",
+            CommandError::Decode(_) => "decode",
+            CommandError::NotFound(_) => "notFound",
+            CommandError::Cancelled(_) => "cancelled",
+            CommandError::CaptureUnsupported(_) => "captureUnsupported",
+            CommandError::ClipboardWriteFailed(_) => "clipboardWriteFailed",
+            CommandError::Other(_) => "other",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            CommandError::Io(message)
+            | CommandError::Network(message)
+            | CommandError::Decode(message)
+            | CommandError::NotFound(message)
+            | CommandError::Cancelled(message)
+            | CommandError::Other(message) => message,
+            CommandError::CaptureUnsupported(u) => &u.reason,
+            CommandError::ClipboardWriteFailed(c) => &c.reason,
+        }
+    }
+
+    fn hint(&self) -> Option<&str> {
+        match self {
+            CommandError::CaptureUnsupported(u) => Some(&u.hint),
+            CommandError::ClipboardWriteFailed(c) => Some(&c.hint),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.hint() {
+            Some(hint) => write!(f, "{} ({})", self.message(), hint),
+            None => f.write_str(self.message()),
+        }
+    }
+}
+
+impl Serialize for CommandError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hint = self.hint();
+        let len = if hint.is_some() { 3 } else { 2 };
+        let mut s = serializer.serialize_struct("CommandError", len)?;
+        s.serialize_field("kind", self.kind())?;
+        s.serialize_field("message", self.message())?;
+        if let Some(hint) = hint {
+            s.serialize_field("hint", hint)?;
+        }
+        s.end()
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::Other(message)
+    }
+}
+
+impl From<&str> for CommandError {
+    fn from(message: &str) -> Self {
+        CommandError::Other(message.to_string())
+    }
+}
+
+impl From<std::io::Error> for CommandError {
+    fn from(error: std::io::Error) -> Self {
+        match error.kind() {
+            std::io::ErrorKind::NotFound => CommandError::NotFound(error.to_string()),
+            _ => CommandError::Io(error.to_string()),
+        }
+    }
+}
+
+impl From<reqwest::Error> for CommandError {
+    fn from(error: reqwest::Error) -> Self {
+        CommandError::Network(error.to_string())
+    }
+}
+
+impl From<image::ImageError> for CommandError {
+    fn from(error: image::ImageError) -> Self {
+        CommandError::Decode(format!("decode error: {}", error))
+    }
+}
+
+impl From<CommandError> for String {
+    fn from(error: CommandError) -> Self {
+        error.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_kind_and_message() {
+        let json = serde_json::to_value(CommandError::NotFound("Screen 3 not found".into()));
+        assert_eq!(
+            json.unwrap(),
+            serde_json::json!({ "kind": "notFound", "message": "Screen 3 not found" })
+        );
+
+        let unsupported = CommandError::CaptureUnsupported(CaptureUnsupported {
+            reason: "no display".into(),
+            hint: "log in".into(),
+        });
+        assert_eq!(
+            serde_json::to_value(&unsupported).unwrap(),
+            serde_json::json!({
+                "kind": "captureUnsupported",
+                "message": "no display",
+                "hint": "log in"
+            })
+        );
+        assert_eq!(unsupported.to_string(), "no display (log in)");
+    }
+}
@@ -0,0 +1,216 @@
+/*!
+ * 事件注册表
+ *
+ * 所有发往前端的事件都在这里登记：事件名、负载类型和 schema_version。
+ * 后端一律通过 emit_event / emit_event_to 发送，负载会带上 schema_version 字段。
+ * 修改负载结构时必须同时提升版本号，否则 schema 快照测试会失败。
+ */
+
+use crate::cache::CacheVerifyProgress;
+use crate::clipboard_monitor::ClipboardChanged;
+use crate::commands::{MousePos, ScreenCapture};
+use crate::delayed::CaptureCountdown;
+use crate::dict::DictLoaded;
+use crate::disk::FolderScanProgress;
+use crate::display::{DisplayPowerEvent, DisplaysChanged};
+use crate::fs_watch::FsChanged;
+use crate::input::ReplayProgress;
+use crate::print::PrintStatus;
+use crate::startup::StartupSection;
+use crate::sync::SyncItem;
+use crate::upload::DownloadProgress;
+use schemars::JsonSchema;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+/// 带版本号的事件负载（负载字段平铺在同一层）
+#[derive(Clone, Serialize, JsonSchema)]
+struct Versioned<T> {
+    schema_version: u32,
+    #[serde(flatten)]
+    payload: T,
+}
+
+/// 单个事件的 JSON Schema
+#[derive(Serialize)]
+pub struct EventSchema {
+    pub name: String,
+    pub schema_version: u32,
+    pub schema: serde_json::Value,
+}
+
+/// "ephemeral:expired" 事件负载
+#[derive(Serialize, Clone, JsonSchema)]
+pub struct EphemeralExpired {
+    pub duration_secs: u64,
+}
+
+/// "hotkey:capture" 事件负载：截图结果或错误信息
+#[derive(Serialize, Clone, JsonSchema)]
+#[serde(untagged)]
+pub enum HotkeyCapture {
+    Captured(ScreenCapture),
+    Failed { error: String },
+}
+
+fn schema_entry<T: JsonSchema>(name: &str, version: u32) -> EventSchema {
+    let schema = schemars::schema_for!(Versioned<T>);
+    EventSchema {
+        name: name.to_string(),
+        schema_version: version,
+        schema: serde_json::to_value(schema).unwrap_or_default(),
+    }
+}
+
+macro_rules! events {
+    ($($variant:ident($payload:ty) => $name:literal, $version:literal;)*) => {
+        /// 后端发出的全部事件
+        pub enum Event {
+            $($variant($payload),)*
+        }
+
+        impl Event {
+            fn emit(self, app: &AppHandle, target: Option<&str>) -> tauri::Result<()> {
+                match self {
+                    $(Event::$variant(payload) => {
+                        let payload = Versioned {
+                            schema_version: $version,
+                            payload,
+                        };
+                        match target {
+                            Some(label) => app.emit_to(label.to_string(), $name, payload),
+                            None => app.emit($name, payload),
+                        }
+                    })*
+                }
+            }
+        }
+
+        /// 所有事件的 JSON Schema（按登记顺序）
+        pub fn schemas() -> Vec<EventSchema> {
+            vec![$(schema_entry::<$payload>($name, $version),)*]
+        }
+    };
+}
+
+events! {
+    MousePosition(MousePos) => "mouse:position", 2;
+    MouseReplayProgress(ReplayProgress) => "mouse:replay-progress", 1;
+    HotkeyCapture(HotkeyCapture) => "hotkey:capture", 5;
+    JiebaDictLoaded(DictLoaded) => "jieba:dict-loaded", 1;
+    FolderScan(FolderScanProgress) => "folder:scan", 1;
+    DisplaySleep(DisplayPowerEvent) => "display:sleep", 1;
+    DisplayWake(DisplayPowerEvent) => "display:wake", 1;
+    DisplaysChanged(DisplaysChanged) => "displays:changed", 2;
+    EphemeralExpired(EphemeralExpired) => "ephemeral:expired", 1;
+    PrintStatus(PrintStatus) => "print:status", 1;
+    SyncItemUpdated(SyncItem) => "sync:item-updated", 1;
+    DownloadProgress(DownloadProgress) => "download:progress", 1;
+    CacheVerify(CacheVerifyProgress) => "cache:verify", 1;
+    CaptureCountdown(CaptureCountdown) => "capture:countdown", 1;
+    CaptureDone(ScreenCapture) => "capture:done", 3;
+    StartupSection(StartupSection) => "startup:section", 1;
+    ClipboardChanged(ClipboardChanged) => "clipboard:changed", 1;
+    FsChanged(FsChanged) => "fs:changed", 1;
+}
+
+/// 广播事件
+pub fn emit_event(app: &AppHandle, event: Event) -> tauri::Result<()> {
+    event.emit(app, None)
+}
+
+/// 发送事件到指定窗口
+pub fn emit_event_to(app: &AppHandle, label: &str, event: Event) -> tauri::Result<()> {
+    event.emit(app, Some(label))
+}
+
+/**
+ * 获取所有事件负载的 JSON Schema，供前端测试校验
+ */
+#[tauri::command]
+pub fn get_event_schemas() -> Vec<EventSchema> {
+    schemas()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeMap, HashSet};
+    use std::path::PathBuf;
+
+    /// schema 快照；设置 UPDATE_EVENT_SCHEMAS=1 运行测试可重新生成
+    fn snapshot_path() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("event-schemas.json")
+    }
+
+    fn current() -> BTreeMap<String, serde_json::Value> {
+        schemas()
+            .into_iter()
+            .map(|s| {
+                let value = serde_json::json!({
+                    "schema_version": s.schema_version,
+                    "schema": s.schema,
+                });
+                (s.name, value)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn event_names_are_unique() {
+        let all = schemas();
+        let names: HashSet<&str> = all.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names.len(), all.len());
+        assert!(all.iter().all(|s| s.schema_version >= 1));
+    }
+
+    #[test]
+    fn schemas_match_snapshot() {
+        let current = current();
+        let path = snapshot_path();
+
+        if std::env::var_os("UPDATE_EVENT_SCHEMAS").is_some() {
+            let json = serde_json::to_string_pretty(&current).unwrap();
+            std::fs::write(&path, json + "\n").unwrap();
+            return;
+        }
+
+        // 快照随代码提交；缺失时失败，而不是静默生成新快照
+        let text = std::fs::read_to_string(&path).unwrap_or_else(|e| {
+            panic!(
+                "{} is missing ({}); rerun with UPDATE_EVENT_SCHEMAS=1 and commit it",
+                path.display(),
+                e
+            )
+        });
+        let snapshot: BTreeMap<String, serde_json::Value> = serde_json::from_str(&text).unwrap();
+
+        for (name, entry) in &current {
+            let Some(old) = snapshot.get(name) else {
+                panic!(
+                    "event {} is not in the snapshot; rerun with UPDATE_EVENT_SCHEMAS=1",
+                    name
+                );
+            };
+            if old["schema_version"] == entry["schema_version"] {
+                assert_eq!(
+                    old["schema"], entry["schema"],
+                    "payload of {} changed without bumping its schema_version",
+                    name
+                );
+            } else {
+                panic!(
+                    "schema_version of {} changed; rerun with UPDATE_EVENT_SCHEMAS=1 to refresh the snapshot",
+                    name
+                );
+            }
+        }
+        for name in snapshot.keys() {
+            assert!(
+                current.contains_key(name),
+                "event {} was removed; rerun with UPDATE_EVENT_SCHEMAS=1",
+                name
+            );
+        }
+    }
+}
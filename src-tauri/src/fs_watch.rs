@@ -0,0 +1,327 @@
+/*!
+ * 监听目录中的文件变化
+ *
+ * 每个监听对应一个 notify watcher（保存在 AppState.fs_watchers）和一个去抖线程：
+ * 同一路径在 DEBOUNCE 内的连续事件合并为一次，"create" 后紧跟的 "modify"（写入新文件）
+ * 只报告 "create"，创建后又很快删除的临时文件不报告。各平台的重命名事件（成对的
+ * from / to 或单个 both）统一合并为带 old_path 的 "rename"。
+ * stop_watching_directory 移除 watcher 后事件通道关闭，去抖线程随之退出。
+ */
+
+use crate::AppState;
+use crate::events;
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, State};
+
+/// 同一路径的事件在这段时间内没有新事件后才发送
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+static WATCH_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// "fs:changed" 事件负载
+#[derive(Serialize, Clone, JsonSchema)]
+pub struct FsChanged {
+    pub watch_id: String,
+    /// "create" | "modify" | "delete" | "rename"
+    pub event_type: String,
+    pub path: String,
+    /// 重命名前的路径，仅 "rename" 有
+    pub old_path: Option<String>,
+    pub timestamp_ms: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum ChangeKind {
+    Create,
+    Modify,
+    Delete,
+    Rename,
+}
+
+impl ChangeKind {
+    fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "create" => Ok(ChangeKind::Create),
+            "modify" => Ok(ChangeKind::Modify),
+            "delete" => Ok(ChangeKind::Delete),
+            "rename" => Ok(ChangeKind::Rename),
+            other => Err(format!("unknown fs event type: {}", other)),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            ChangeKind::Create => "create",
+            ChangeKind::Modify => "modify",
+            ChangeKind::Delete => "delete",
+            ChangeKind::Rename => "rename",
+        }
+    }
+}
+
+/// 等待合并的变化
+#[derive(Debug)]
+struct PendingChange {
+    kind: ChangeKind,
+    old_path: Option<PathBuf>,
+    last_seen: Instant,
+}
+
+/// 按路径合并短时间内的连续事件
+#[derive(Default)]
+struct Debouncer {
+    pending: HashMap<PathBuf, PendingChange>,
+    /// 还没等到对应 to 事件的 rename from 路径
+    rename_from: Option<PathBuf>,
+}
+
+impl Debouncer {
+    fn push(&mut self, kind: ChangeKind, path: PathBuf, old_path: Option<PathBuf>, now: Instant) {
+        if let Some(old) = &old_path {
+            // 重命名前路径上等待中的 delete（来自 from 事件）由 rename 取代
+            self.pending.remove(old);
+        }
+        let kind = match (self.pending.get(&path).map(|p| p.kind), kind) {
+            // 新文件的写入只报告创建
+            (Some(ChangeKind::Create), ChangeKind::Modify) => ChangeKind::Create,
+            (Some(ChangeKind::Rename), ChangeKind::Modify) => ChangeKind::Rename,
+            // 创建后又删除的临时文件不报告
+            (Some(ChangeKind::Create), ChangeKind::Delete) => {
+                self.pending.remove(&path);
+                return;
+            }
+            (_, kind) => kind,
+        };
+        let old_path = match kind {
+            ChangeKind::Rename => {
+                old_path.or_else(|| self.pending.get(&path).and_then(|p| p.old_path.clone()))
+            }
+            _ => None,
+        };
+        self.pending.insert(
+            path,
+            PendingChange {
+                kind,
+                old_path,
+                last_seen: now,
+            },
+        );
+    }
+
+    /// 把 notify 事件转换为变化
+    fn push_event(&mut self, event: Event, now: Instant) {
+        let mut paths = event.paths.into_iter();
+        match event.kind {
+            EventKind::Create(_) => {
+                for path in paths {
+                    self.push(ChangeKind::Create, path, None, now);
+                }
+            }
+            EventKind::Remove(_) => {
+                for path in paths {
+                    self.push(ChangeKind::Delete, path, None, now);
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(mode)) => match mode {
+                RenameMode::Both => {
+                    if let (Some(old), Some(new)) = (paths.next(), paths.next()) {
+                        self.rename_from = None;
+                        self.push(ChangeKind::Rename, new, Some(old), now);
+                    }
+                }
+                RenameMode::From => {
+                    for path in paths {
+                        self.rename_from_path(path, now);
+                    }
+                }
+                RenameMode::To => {
+                    for path in paths {
+                        self.rename_to_path(path, now);
+                    }
+                }
+                // 平台没有区分方向（如 macOS）：路径还存在说明是新名字
+                _ => {
+                    for path in paths {
+                        if path.exists() {
+                            self.rename_to_path(path, now);
+                        } else {
+                            self.rename_from_path(path, now);
+                        }
+                    }
+                }
+            },
+            EventKind::Modify(_) => {
+                for path in paths {
+                    self.push(ChangeKind::Modify, path, None, now);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// 单独的 from 事件先按删除处理，等到 to 事件时再合并为 rename
+    fn rename_from_path(&mut self, path: PathBuf, now: Instant) {
+        self.rename_from = Some(path.clone());
+        self.push(ChangeKind::Delete, path, None, now);
+    }
+
+    /// 单独的 to 事件：有等待中的 from 时为 rename，否则为移入目录（create）
+    fn rename_to_path(&mut self, path: PathBuf, now: Instant) {
+        match self.rename_from.take() {
+            Some(old) => self.push(ChangeKind::Rename, path, Some(old), now),
+            None => self.push(ChangeKind::Create, path, None, now),
+        }
+    }
+
+    /// 取出已超过 DEBOUNCE 没有新事件的变化（按最后事件时间排序）
+    fn drain_ready(&mut self, now: Instant) -> Vec<(PathBuf, PendingChange)> {
+        let ready: Vec<PathBuf> = self
+            .pending
+            .iter()
+            .filter(|(_, change)| now.saturating_duration_since(change.last_seen) >= DEBOUNCE)
+            .map(|(path, _)| path.clone())
+            .collect();
+        let mut changes: Vec<(PathBuf, PendingChange)> = ready
+            .into_iter()
+            .filter_map(|path| self.pending.remove_entry(&path))
+            .collect();
+        changes.sort_by_key(|(_, change)| change.last_seen);
+        changes
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/**
+ * 开始监听目录，返回 watch_id；文件变化时发送 "fs:changed"
+ * （{ watch_id, event_type, path, old_path, timestamp_ms }）
+ * recursive: 是否包含子目录
+ * events: 要报告的事件类型 "create" | "modify" | "delete" | "rename"，为空时报告全部
+ * 同一路径的连续事件在 300ms 内合并为一次（见模块说明），因此事件会有短暂延迟
+ */
+#[tauri::command]
+pub fn watch_directory(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    recursive: bool,
+    events: Vec<String>,
+) -> Result<String, String> {
+    let wanted = events
+        .iter()
+        .map(|name| ChangeKind::parse(name))
+        .collect::<Result<Vec<_>, _>>()?;
+    if !Path::new(&path).is_dir() {
+        return Err("文件夹路径不存在".to_string());
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).map_err(|e| e.to_string())?;
+    let mode = match recursive {
+        true => RecursiveMode::Recursive,
+        false => RecursiveMode::NonRecursive,
+    };
+    watcher
+        .watch(Path::new(&path), mode)
+        .map_err(|e| format!("watch {} failed: {}", path, e))?;
+
+    let watch_id = format!("watch-{}", WATCH_SEQ.fetch_add(1, Ordering::Relaxed));
+    let id = watch_id.clone();
+    thread::spawn(move || {
+        let mut debouncer = Debouncer::default();
+        loop {
+            match rx.recv_timeout(DEBOUNCE) {
+                Ok(Ok(event)) => debouncer.push_event(event, Instant::now()),
+                Ok(Err(e)) => eprintln!("[fs_watch] {} error: {}", id, e),
+                Err(RecvTimeoutError::Timeout) => {}
+                // watcher 已移除
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            for (path, change) in debouncer.drain_ready(Instant::now()) {
+                if !wanted.is_empty() && !wanted.contains(&change.kind) {
+                    continue;
+                }
+                let payload = FsChanged {
+                    watch_id: id.clone(),
+                    event_type: change.kind.as_str().into(),
+                    path: path.to_string_lossy().into_owned(),
+                    old_path: change.old_path.map(|p| p.to_string_lossy().into_owned()),
+                    timestamp_ms: now_millis(),
+                };
+                if let Err(e) = events::emit_event(&app, events::Event::FsChanged(payload)) {
+                    eprintln!("[fs_watch] emit error: {:?}", e);
+                }
+            }
+        }
+        println!("[fs_watch] {} stopped", id);
+    });
+
+    state
+        .fs_watchers
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?
+        .insert(watch_id.clone(), watcher);
+    Ok(watch_id)
+}
+
+/**
+ * 停止监听目录；watch_id 不存在时返回错误
+ */
+#[tauri::command]
+pub fn stop_watching_directory(state: State<'_, AppState>, watch_id: String) -> Result<(), String> {
+    state
+        .fs_watchers
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?
+        .remove(&watch_id)
+        .map(drop)
+        .ok_or_else(|| format!("watch {} not found", watch_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_then_modify_is_reported_once() {
+        let mut debouncer = Debouncer::default();
+        let start = Instant::now();
+        let path = PathBuf::from("/tmp/a.txt");
+        debouncer.push(ChangeKind::Create, path.clone(), None, start);
+        debouncer.push(ChangeKind::Modify, path.clone(), None, start);
+        assert!(debouncer.drain_ready(start).is_empty());
+
+        let changes = debouncer.drain_ready(start + DEBOUNCE);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].0, path);
+        assert_eq!(changes[0].1.kind, ChangeKind::Create);
+    }
+
+    #[test]
+    fn rename_from_and_to_are_paired() {
+        let mut debouncer = Debouncer::default();
+        let now = Instant::now();
+        debouncer.rename_from_path(PathBuf::from("/tmp/old.txt"), now);
+        debouncer.rename_to_path(PathBuf::from("/tmp/new.txt"), now);
+
+        let changes = debouncer.drain_ready(now + DEBOUNCE);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].0, PathBuf::from("/tmp/new.txt"));
+        assert_eq!(changes[0].1.kind, ChangeKind::Rename);
+        assert_eq!(changes[0].1.old_path, Some(PathBuf::from("/tmp/old.txt")));
+    }
+}
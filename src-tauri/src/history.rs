@@ -0,0 +1,334 @@
+/*!
+ * 截图历史
+ *
+ * 启用后，单图截图命令（capture_screen_by_id / capture_screen_at_point / capture_area /
+ * capture_area_logical / capture_window / capture_virtual_desktop）以及 capture_all_screens
+ * 的每个屏幕的结果都会保存到 app_local_data_dir/screenshots/（临时模式下在沙箱中），
+ * 元数据记录在同目录的 index.json。超出数量或总字节上限时从最旧的开始淘汰。
+ * 写入历史失败不影响截图命令本身，只打印日志。
+ */
+
+use crate::AppState;
+use crate::ephemeral;
+use crate::undo;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+
+const HISTORY_DIR: &str = "screenshots";
+const INDEX_FILE: &str = "index.json";
+/// 缺省最多保留的截图数
+const DEFAULT_MAX_COUNT: usize = 100;
+
+static ID_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// 截图历史配置（运行时有效，默认关闭）
+#[derive(Serialize, Clone)]
+pub struct HistoryConfig {
+    pub enabled: bool,
+    pub max_count: Option<usize>,
+    pub max_bytes: Option<u64>,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        HistoryConfig {
+            enabled: false,
+            max_count: Some(DEFAULT_MAX_COUNT),
+            max_bytes: None,
+        }
+    }
+}
+
+/// 一条截图历史记录
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HistoryEntry {
+    pub id: String,
+    pub path: String,
+    /// 产生截图的命令，如 "capture_area"
+    pub source: String,
+    /// 来源屏幕（跨屏拼接或虚拟桌面截图时为 None）
+    pub screen_id: Option<u32>,
+    pub width: u32,
+    pub height: u32,
+    pub format: String,
+    pub size: u64,
+    /// 毫秒时间戳
+    pub created_at: u64,
+}
+
+/// list_capture_history 的列表项；exists 为 false 表示文件已被外部删除
+#[derive(Serialize)]
+pub struct HistoryItem {
+    #[serde(flatten)]
+    pub entry: HistoryEntry,
+    pub exists: bool,
+}
+
+/// get_capture_file 的结果
+#[derive(Serialize)]
+pub struct HistoryFile {
+    pub entry: HistoryEntry,
+    pub data: Vec<u8>,
+}
+
+/// 待记录的截图
+pub struct Captured<'a> {
+    pub source: &'a str,
+    pub screen_id: Option<u32>,
+    pub width: u32,
+    pub height: u32,
+    pub format: &'a str,
+    pub data: &'a [u8],
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn history_dir(app: &AppHandle, state: &AppState) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| format!("path error: {}", e))?
+        .join(HISTORY_DIR);
+    Ok(ephemeral::redirect(state, HISTORY_DIR, dir))
+}
+
+/// 索引按创建时间从旧到新排列
+fn load_index(dir: &Path) -> Vec<HistoryEntry> {
+    fs::read(dir.join(INDEX_FILE))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// 写入临时文件后重命名，避免崩溃时留下半截索引
+fn save_index(dir: &Path, index: &[HistoryEntry]) -> Result<(), String> {
+    let bytes = serde_json::to_vec(index).map_err(|e| format!("serialize error: {}", e))?;
+    let tmp = dir.join(format!("{}.tmp", INDEX_FILE));
+    fs::write(&tmp, bytes).map_err(|e| format!("write error: {}", e))?;
+    fs::rename(&tmp, dir.join(INDEX_FILE)).map_err(|e| format!("rename error: {}", e))
+}
+
+fn remove_file(path: &str) {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            eprintln!("[history] remove {} failed: {}", path, e);
+        }
+        _ => {}
+    }
+}
+
+/// 超出上限时淘汰最旧的记录（至少保留最新一条），返回被淘汰的记录
+fn evict(index: &mut Vec<HistoryEntry>, config: &HistoryConfig) -> Vec<HistoryEntry> {
+    let mut total: u64 = index.iter().map(|e| e.size).sum();
+    let mut count = 0;
+    while index.len() - count > 1 {
+        let over_count = config.max_count.is_some_and(|m| index.len() - count > m);
+        let over_bytes = config.max_bytes.is_some_and(|m| total > m);
+        if !over_count && !over_bytes {
+            break;
+        }
+        total -= index[count].size;
+        count += 1;
+    }
+    index.drain(..count).collect()
+}
+
+fn save(app: &AppHandle, state: &AppState, capture: &Captured) -> Result<(), String> {
+    let config = state
+        .capture_history
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?;
+    if !config.enabled {
+        return Ok(());
+    }
+
+    let dir = history_dir(app, state)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("mkdir error: {}", e))?;
+    let created_at = now_millis();
+    let id = format!("{}-{}", created_at, ID_SEQ.fetch_add(1, Ordering::Relaxed));
+    let path = dir.join(format!("capture-{}.{}", id, capture.format));
+    fs::write(&path, capture.data).map_err(|e| format!("write error: {}", e))?;
+
+    let mut index = load_index(&dir);
+    index.push(HistoryEntry {
+        id,
+        path: path.to_string_lossy().into_owned(),
+        source: capture.source.to_string(),
+        screen_id: capture.screen_id,
+        width: capture.width,
+        height: capture.height,
+        format: capture.format.to_string(),
+        size: capture.data.len() as u64,
+        created_at,
+    });
+    let evicted = evict(&mut index, &config);
+    // 先写索引再删文件，崩溃最多留下没有记录的文件
+    save_index(&dir, &index)?;
+    for entry in evicted {
+        remove_file(&entry.path);
+    }
+    Ok(())
+}
+
+/// 记录一次成功的截图（未启用历史时不做任何事）
+pub fn record(app: &AppHandle, capture: Captured) {
+    let state = app.state::<AppState>();
+    if let Err(e) = save(app, &state, &capture) {
+        eprintln!("[history] record {} failed: {}", capture.source, e);
+    }
+}
+
+/**
+ * 配置截图历史
+ * enabled: 是否记录；max_count / max_bytes: 数量和总字节上限，None 表示不限制（max_count 缺省 100）
+ * 调低上限时立即淘汰超出的旧记录
+ */
+#[tauri::command]
+pub fn configure_capture_history(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    enabled: bool,
+    max_count: Option<usize>,
+    max_bytes: Option<u64>,
+) -> Result<HistoryConfig, String> {
+    let mut config = state
+        .capture_history
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?;
+    *config = HistoryConfig {
+        enabled,
+        max_count,
+        max_bytes,
+    };
+
+    let dir = history_dir(&app, &state)?;
+    let mut index = load_index(&dir);
+    let evicted = evict(&mut index, &config);
+    if !evicted.is_empty() {
+        save_index(&dir, &index)?;
+        for entry in evicted {
+            remove_file(&entry.path);
+        }
+    }
+    Ok(config.clone())
+}
+
+/**
+ * 列出截图历史，按时间从新到旧
+ * limit 缺省 50；文件已不存在的记录照常返回，exists 为 false
+ */
+#[tauri::command]
+pub fn list_capture_history(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<Vec<HistoryItem>, String> {
+    let dir = history_dir(&app, &state)?;
+    let index = {
+        let _guard = state
+            .capture_history
+            .lock()
+            .map_err(|e| format!("lock error: {}", e))?;
+        load_index(&dir)
+    };
+    Ok(index
+        .into_iter()
+        .rev()
+        .skip(offset.unwrap_or(0))
+        .take(limit.unwrap_or(50))
+        .map(|entry| HistoryItem {
+            exists: Path::new(&entry.path).is_file(),
+            entry,
+        })
+        .collect())
+}
+
+/**
+ * 读取一条历史截图的记录和文件内容
+ */
+#[tauri::command]
+pub fn get_capture_file(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<HistoryFile, String> {
+    let dir = history_dir(&app, &state)?;
+    let entry = {
+        let _guard = state
+            .capture_history
+            .lock()
+            .map_err(|e| format!("lock error: {}", e))?;
+        load_index(&dir).into_iter().find(|e| e.id == id)
+    }
+    .ok_or_else(|| format!("capture {} not found", id))?;
+    let data = fs::read(&entry.path).map_err(|e| format!("read error: {}", e))?;
+    Ok(HistoryFile { entry, data })
+}
+
+/// 把记录按创建时间放回索引（撤销删除时使用）
+fn reinsert(index: &mut Vec<HistoryEntry>, entry: HistoryEntry) {
+    let pos = index.partition_point(|e| e.created_at <= entry.created_at);
+    index.insert(pos, entry);
+}
+
+/**
+ * 删除一条历史截图（索引记录和文件，可撤销）
+ * 文件移入暂存区并返回 undo_token，撤销时连同索引记录一起恢复；文件已不存在时只删除记录
+ * 记录不存在时返回错误；confirm 同 clear_image_cache
+ */
+#[tauri::command]
+pub fn delete_capture(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    confirm: Option<bool>,
+) -> Result<undo::DestructiveResult, String> {
+    let dir = history_dir(&app, &state)?;
+    let _guard = state
+        .capture_history
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?;
+    let mut index = load_index(&dir);
+    let pos = index
+        .iter()
+        .position(|e| e.id == id)
+        .ok_or_else(|| format!("capture {} not found", id))?;
+    let entry = index.remove(pos);
+    save_index(&dir, &index)?;
+
+    let restore_dir = dir.clone();
+    let restore_entry = entry.clone();
+    let restore: undo::RestoreHook = Box::new(move |state: &AppState| {
+        let _guard = state
+            .capture_history
+            .lock()
+            .map_err(|e| format!("lock error: {}", e))?;
+        let mut index = load_index(&restore_dir);
+        reinsert(&mut index, restore_entry);
+        save_index(&restore_dir, &index)
+    });
+    let result = undo::stage_with_restore(
+        &app,
+        &state,
+        "delete_capture",
+        vec![PathBuf::from(&entry.path)],
+        confirm.unwrap_or(false),
+        Some(restore),
+    )?;
+    if result.requires_confirm {
+        // 文件没有被删除，放回索引记录
+        reinsert(&mut index, entry);
+        save_index(&dir, &index)?;
+    }
+    Ok(result)
+}
@@ -0,0 +1,49 @@
+use crate::commands;
+use crate::events::{self, HotkeyCapture};
+use enigo::Enigo;
+use tauri::AppHandle;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+
+/**
+ * 注册截图快捷键
+ * accelerator: 如 "CommandOrControl+Shift+A"
+ *
+ * 按下时截取鼠标所在屏幕，通过 "hotkey:capture" 事件发送 ScreenCapture；
+ * 截图失败时事件负载为 { error: String }
+ */
+#[tauri::command]
+pub fn register_capture_hotkey(app: AppHandle, accelerator: String) -> Result<(), String> {
+    let shortcuts = app.global_shortcut();
+    if shortcuts.is_registered(accelerator.as_str()) {
+        return Err(format!("hotkey {} is already registered", accelerator));
+    }
+
+    shortcuts
+        .on_shortcut(accelerator.as_str(), |app, _shortcut, event| {
+            if event.state() != ShortcutState::Pressed {
+                return;
+            }
+            let (x, y) = Enigo::mouse_location();
+            let payload = match commands::capture_screen_at_point(app.clone(), x as i32, y as i32, None, None, None, None, None, None, None) {
+                Ok(capture) => HotkeyCapture::Captured(capture),
+                Err(error) => HotkeyCapture::Failed {
+                    error: error.to_string(),
+                },
+            };
+            let res = events::emit_event(app, events::Event::HotkeyCapture(payload));
+            if let Err(e) = res {
+                eprintln!("[hotkey] emit error: {:?}", e);
+            }
+        })
+        .map_err(|e| format!("register hotkey {} failed: {}", accelerator, e))
+}
+
+/**
+ * 注销截图快捷键
+ */
+#[tauri::command]
+pub fn unregister_capture_hotkey(app: AppHandle, accelerator: String) -> Result<(), String> {
+    app.global_shortcut()
+        .unregister(accelerator.as_str())
+        .map_err(|e| format!("unregister hotkey {} failed: {}", accelerator, e))
+}
@@ -0,0 +1,1838 @@
+/*!
+ * 图片处理相关命令（直方图、增强、缩放、裁剪、旋转等）
+ */
+
+use crate::AppState;
+use crate::commands::{self, CaptureFormat, encode_rgba};
+use crate::ephemeral;
+use crate::error::CommandError;
+use image::imageops::FilterType;
+use image::{ColorType, DynamicImage, GrayImage, ImageFormat, Luma, RgbImage, Rgba, RgbaImage};
+use imageproc::drawing::{draw_text_mut, text_size};
+use rayon::prelude::*;
+use rusttype::{Font, Scale};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{BufWriter, Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::UNIX_EPOCH;
+use tauri::State;
+use tauri_plugin_http::reqwest;
+
+/// 从路径或内存字节加载图片
+pub fn load_image(path: Option<String>, data: Option<Vec<u8>>) -> Result<DynamicImage, String> {
+    match (path, data) {
+        (Some(p), _) => image::open(&p).map_err(|e| format!("decode error: {}", e)),
+        (None, Some(d)) => image::load_from_memory(&d).map_err(|e| format!("decode error: {}", e)),
+        (None, None) => Err("either path or data is required".into()),
+    }
+}
+
+/// 是否为高位深图片（16 位 / 浮点）
+fn is_high_bit_depth(img: &DynamicImage) -> bool {
+    matches!(
+        img.color(),
+        ColorType::L16
+            | ColorType::La16
+            | ColorType::Rgb16
+            | ColorType::Rgba16
+            | ColorType::Rgb32F
+            | ColorType::Rgba32F
+    )
+}
+
+/// Rec.709 亮度
+fn luminance(r: u8, g: u8, b: u8) -> u8 {
+    (0.2126 * r as f64 + 0.7152 * g as f64 + 0.0722 * b as f64).round() as u8
+}
+
+/// 图片基础统计
+#[derive(Serialize, Clone)]
+pub struct ImageStats {
+    pub width: u32,
+    pub height: u32,
+    pub mean_luminance: f64,
+    /// 亮度为 0 的像素占比（%）
+    pub shadows_clipped: f64,
+    /// 亮度为 255 的像素占比（%）
+    pub highlights_clipped: f64,
+}
+
+/// 各通道直方图（256 级）
+#[derive(Serialize)]
+pub struct ImageHistogram {
+    pub red: Vec<u32>,
+    pub green: Vec<u32>,
+    pub blue: Vec<u32>,
+    pub luminance: Vec<u32>,
+    pub bit_depth: u8,
+    pub stats: ImageStats,
+}
+
+fn histogram(rgba: &RgbaImage) -> [[u32; 256]; 4] {
+    let mut h = [[0u32; 256]; 4];
+    for p in rgba.pixels() {
+        let [r, g, b, _] = p.0;
+        h[0][r as usize] += 1;
+        h[1][g as usize] += 1;
+        h[2][b as usize] += 1;
+        h[3][luminance(r, g, b) as usize] += 1;
+    }
+    h
+}
+
+fn stats(rgba: &RgbaImage, luma: &[u32; 256]) -> ImageStats {
+    let total = (rgba.width() as u64 * rgba.height() as u64).max(1) as f64;
+    let sum: f64 = luma
+        .iter()
+        .enumerate()
+        .map(|(v, &n)| v as f64 * n as f64)
+        .sum();
+    ImageStats {
+        width: rgba.width(),
+        height: rgba.height(),
+        mean_luminance: sum / total,
+        shadows_clipped: luma[0] as f64 * 100.0 / total,
+        highlights_clipped: luma[255] as f64 * 100.0 / total,
+    }
+}
+
+/**
+ * 计算图片直方图与基础统计
+ * path / data 二选一
+ * 16 位图片按比例缩放到 8 位统计（bit_depth 字段为 16）
+ */
+#[tauri::command]
+pub fn image_histogram(
+    path: Option<String>,
+    data: Option<Vec<u8>>,
+) -> Result<ImageHistogram, String> {
+    let img = load_image(path, data)?;
+    let bit_depth = if is_high_bit_depth(&img) { 16 } else { 8 };
+    let rgba = img.to_rgba8();
+    let [r, g, b, l] = histogram(&rgba);
+    Ok(ImageHistogram {
+        red: r.to_vec(),
+        green: g.to_vec(),
+        blue: b.to_vec(),
+        luminance: l.to_vec(),
+        bit_depth,
+        stats: stats(&rgba, &l),
+    })
+}
+
+/// 自动增强选项
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct EnhanceOptions {
+    /// 各通道是否参与色阶拉伸
+    pub red: bool,
+    pub green: bool,
+    pub blue: bool,
+    /// 两端忽略的像素百分比（避免个别极值像素影响拉伸）
+    pub clip_percent: f32,
+    /// 锐化强度（高斯 sigma），None 不锐化
+    pub sharpen: Option<f32>,
+    /// 允许 dest 与 src 相同（原地覆盖）
+    pub in_place: bool,
+}
+
+impl Default for EnhanceOptions {
+    fn default() -> Self {
+        EnhanceOptions {
+            red: true,
+            green: true,
+            blue: true,
+            clip_percent: 0.5,
+            sharpen: None,
+            in_place: false,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct EnhanceResult {
+    pub before: ImageStats,
+    pub after: ImageStats,
+}
+
+/// 根据直方图求 [low, high] 百分位
+fn percentile_bounds(hist: &[u32; 256], total: u64, clip_percent: f32) -> (u8, u8) {
+    let cut = (total as f64 * clip_percent.clamp(0.0, 49.0) as f64 / 100.0) as u64;
+    let mut acc = 0u64;
+    let mut low = 0u8;
+    for (v, &n) in hist.iter().enumerate() {
+        acc += n as u64;
+        if acc > cut {
+            low = v as u8;
+            break;
+        }
+    }
+    acc = 0;
+    let mut high = 255u8;
+    for (v, &n) in hist.iter().enumerate().rev() {
+        acc += n as u64;
+        if acc > cut {
+            high = v as u8;
+            break;
+        }
+    }
+    (low, high)
+}
+
+/// 生成色阶拉伸查找表
+fn stretch_lut(low: u8, high: u8) -> [u8; 256] {
+    let mut lut = [0u8; 256];
+    for (v, out) in lut.iter_mut().enumerate() {
+        *out = if high <= low {
+            v as u8
+        } else {
+            let x = (v as f32 - low as f32) * 255.0 / (high as f32 - low as f32);
+            x.clamp(0.0, 255.0).round() as u8
+        };
+    }
+    lut
+}
+
+/**
+ * 自动增强（色阶 / 对比度拉伸 + 可选锐化）
+ * 不会原地覆盖 src，除非 options.in_place 为 true
+ * 16 位图片直接拒绝，避免静默降为 8 位
+ * 返回增强前后的统计数据
+ * 临时模式下需 allow_persistent: true
+ */
+#[tauri::command]
+pub fn auto_enhance_image(
+    state: State<'_, AppState>,
+    src: String,
+    dest: String,
+    options: Option<EnhanceOptions>,
+    allow_persistent: Option<bool>,
+) -> Result<EnhanceResult, String> {
+    ephemeral::check_persist(&state, allow_persistent.unwrap_or(false))?;
+    let options = options.unwrap_or_default();
+
+    let same_file = match (Path::new(&src).canonicalize(), Path::new(&dest).canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => src == dest,
+    };
+    if same_file && !options.in_place {
+        return Err("dest is the same as src; set in_place to overwrite".into());
+    }
+
+    let img = image::open(&src).map_err(|e| format!("decode error: {}", e))?;
+    if is_high_bit_depth(&img) {
+        return Err("16-bit images are not supported by auto_enhance_image".into());
+    }
+
+    let mut rgba = img.to_rgba8();
+    let hist = histogram(&rgba);
+    let before = stats(&rgba, &hist[3]);
+    let total = rgba.width() as u64 * rgba.height() as u64;
+
+    let enabled = [options.red, options.green, options.blue];
+    let luts: Vec<Option<[u8; 256]>> = enabled
+        .iter()
+        .enumerate()
+        .map(|(c, &on)| {
+            on.then(|| {
+                let (low, high) = percentile_bounds(&hist[c], total, options.clip_percent);
+                stretch_lut(low, high)
+            })
+        })
+        .collect();
+
+    for p in rgba.pixels_mut() {
+        for (c, lut) in luts.iter().enumerate() {
+            if let Some(lut) = lut {
+                p.0[c] = lut[p.0[c] as usize];
+            }
+        }
+    }
+
+    if let Some(sigma) = options.sharpen.filter(|s| *s > 0.0) {
+        rgba = image::imageops::unsharpen(&rgba, sigma, 1);
+    }
+
+    let after = stats(&rgba, &histogram(&rgba)[3]);
+    rgba.save(&dest).map_err(|e| format!("save error: {}", e))?;
+
+    Ok(EnhanceResult { before, after })
+}
+
+/// 解析缩放滤镜："nearest" | "bilinear" | "lanczos3"（默认）
+fn filter_type(filter: Option<&str>) -> Result<FilterType, String> {
+    match filter.unwrap_or("lanczos3") {
+        "nearest" => Ok(FilterType::Nearest),
+        "bilinear" => Ok(FilterType::Triangle),
+        "lanczos3" => Ok(FilterType::Lanczos3),
+        other => Err(format!(
+            "unknown filter: {} (expected nearest, bilinear or lanczos3)",
+            other
+        )),
+    }
+}
+
+/// 计算缩放后的尺寸；keep_aspect 时等比缩放，使宽高都不超过 width x height
+fn target_size(src: (u32, u32), width: u32, height: u32, keep_aspect: bool) -> (u32, u32) {
+    if !keep_aspect || src.0 == 0 || src.1 == 0 {
+        return (width, height);
+    }
+    let scale = (width as f64 / src.0 as f64).min(height as f64 / src.1 as f64);
+    (
+        ((src.0 as f64 * scale).round() as u32).clamp(1, width),
+        ((src.1 as f64 * scale).round() as u32).clamp(1, height),
+    )
+}
+
+fn resize(
+    img: &DynamicImage,
+    width: u32,
+    height: u32,
+    filter: Option<&str>,
+    keep_aspect: bool,
+) -> Result<RgbaImage, String> {
+    if width == 0 || height == 0 {
+        return Err("width and height must be greater than 0".into());
+    }
+    let filter = filter_type(filter)?;
+    let (w, h) = target_size((img.width(), img.height()), width, height, keep_aspect);
+    Ok(image::imageops::resize(&img.to_rgba8(), w, h, filter))
+}
+
+fn encode_png(rgba: &RgbaImage) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    rgba.write_to(&mut Cursor::new(&mut out), ImageFormat::Png)
+        .map_err(|e| format!("encode error: {}", e))?;
+    Ok(out)
+}
+
+/**
+ * 缩放图片（PNG / JPEG 等字节），返回 PNG
+ * filter: "nearest" | "bilinear" | "lanczos3"（默认，质量最好）
+ * keep_aspect: 为 true 时等比缩放，宽高都不超过 width x height；否则拉伸到指定尺寸
+ */
+#[tauri::command]
+pub async fn resize_image(
+    input: Vec<u8>,
+    width: u32,
+    height: u32,
+    filter: Option<String>,
+    keep_aspect: bool,
+) -> Result<Vec<u8>, String> {
+    tokio::task::spawn_blocking(move || {
+        let img = image::load_from_memory(&input).map_err(|e| format!("decode error: {}", e))?;
+        let resized = resize(&img, width, height, filter.as_deref(), keep_aspect)?;
+        encode_png(&resized)
+    })
+    .await
+    .map_err(|e| format!("resize task error: {}", e))?
+}
+
+/// 处理磁盘图片的结果：写入文件时 path 为输出路径，否则 data 为 PNG 字节
+#[derive(Serialize)]
+pub struct ImageFileResult {
+    pub width: u32,
+    pub height: u32,
+    pub path: Option<String>,
+    pub data: Option<Vec<u8>>,
+}
+
+/// 把处理后的图片写入 output_path（格式由扩展名决定），未指定时返回 PNG 字节
+fn file_result(rgba: RgbaImage, output_path: Option<String>) -> Result<ImageFileResult, String> {
+    let (width, height) = rgba.dimensions();
+    match output_path {
+        Some(output) => {
+            rgba.save(&output)
+                .map_err(|e| format!("save error: {}", e))?;
+            Ok(ImageFileResult {
+                width,
+                height,
+                path: Some(output),
+                data: None,
+            })
+        }
+        None => Ok(ImageFileResult {
+            width,
+            height,
+            path: None,
+            data: Some(encode_png(&rgba)?),
+        }),
+    }
+}
+
+/**
+ * 缩放磁盘上的图片，避免通过 IPC 传输大文件
+ * output_path: 写入该路径（格式由扩展名决定，可与 path 相同以覆盖原图）；不传则返回 PNG 字节
+ *              临时模式下写入文件需 allow_persistent: true
+ * filter / keep_aspect 同 resize_image
+ */
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn resize_image_file(
+    state: State<'_, AppState>,
+    path: String,
+    width: u32,
+    height: u32,
+    output_path: Option<String>,
+    filter: Option<String>,
+    keep_aspect: bool,
+    allow_persistent: Option<bool>,
+) -> Result<ImageFileResult, String> {
+    if output_path.is_some() {
+        ephemeral::check_persist(&state, allow_persistent.unwrap_or(false))?;
+    }
+    tokio::task::spawn_blocking(move || {
+        let img = image::open(&path).map_err(|e| format!("decode error: {}", e))?;
+        let resized = resize(&img, width, height, filter.as_deref(), keep_aspect)?;
+        file_result(resized, output_path)
+    })
+    .await
+    .map_err(|e| format!("resize task error: {}", e))?
+}
+
+/// 把裁剪区域限制在图片范围内；限制后面积为 0 时返回错误
+fn clamp_rect(
+    img: &DynamicImage,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+) -> Result<(u32, u32, u32, u32), String> {
+    let x = x.min(img.width());
+    let y = y.min(img.height());
+    let width = width.min(img.width() - x);
+    let height = height.min(img.height() - y);
+    if width == 0 || height == 0 {
+        return Err("crop rectangle is outside of the image".into());
+    }
+    Ok((x, y, width, height))
+}
+
+fn crop(img: &DynamicImage, x: u32, y: u32, width: u32, height: u32) -> Result<RgbaImage, String> {
+    let (x, y, width, height) = clamp_rect(img, x, y, width, height)?;
+    Ok(img.crop_imm(x, y, width, height).to_rgba8())
+}
+
+/**
+ * 裁剪图片（PNG / JPEG 等字节），返回 PNG
+ * 区域超出图片时裁剪到图片边界，不报错
+ */
+#[tauri::command]
+pub async fn crop_image(
+    input: Vec<u8>,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>, String> {
+    tokio::task::spawn_blocking(move || {
+        let img = image::load_from_memory(&input).map_err(|e| format!("decode error: {}", e))?;
+        encode_png(&crop(&img, x, y, width, height)?)
+    })
+    .await
+    .map_err(|e| format!("crop task error: {}", e))?
+}
+
+/**
+ * 裁剪磁盘上的图片
+ * output_path / allow_persistent 同 resize_image_file；区域处理同 crop_image
+ */
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn crop_image_file(
+    state: State<'_, AppState>,
+    path: String,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    output_path: Option<String>,
+    allow_persistent: Option<bool>,
+) -> Result<ImageFileResult, String> {
+    if output_path.is_some() {
+        ephemeral::check_persist(&state, allow_persistent.unwrap_or(false))?;
+    }
+    tokio::task::spawn_blocking(move || {
+        let img = image::open(&path).map_err(|e| format!("decode error: {}", e))?;
+        file_result(crop(&img, x, y, width, height)?, output_path)
+    })
+    .await
+    .map_err(|e| format!("crop task error: {}", e))?
+}
+
+/// 按比例裁剪、放置水印时的锚点：(水平, 垂直)，0 = 靠左/上，1 = 居中，2 = 靠右/下
+fn parse_anchor(anchor: &str) -> Result<(u32, u32), String> {
+    match anchor {
+        "top-left" => Ok((0, 0)),
+        "top" => Ok((1, 0)),
+        "top-right" => Ok((2, 0)),
+        "left" => Ok((0, 1)),
+        "center" => Ok((1, 1)),
+        "right" => Ok((2, 1)),
+        "bottom-left" => Ok((0, 2)),
+        "bottom" => Ok((1, 2)),
+        "bottom-right" => Ok((2, 2)),
+        other => Err(format!("unknown anchor: {}", other)),
+    }
+}
+
+/// 计算 numerator:denominator 比例下能容纳的最大裁剪区域
+fn aspect_rect(
+    (src_w, src_h): (u32, u32),
+    numerator: u32,
+    denominator: u32,
+    (ax, ay): (u32, u32),
+) -> (u32, u32, u32, u32) {
+    let (num, den) = (numerator as u64, denominator as u64);
+    // 先按宽度算高度，放不下再按高度算宽度
+    let (w, h) = if src_w as u64 * den / num <= src_h as u64 {
+        (src_w, (src_w as u64 * den / num) as u32)
+    } else {
+        ((src_h as u64 * num / den) as u32, src_h)
+    };
+    let x = (src_w - w) * ax / 2;
+    let y = (src_h - h) * ay / 2;
+    (x, y, w.max(1), h.max(1))
+}
+
+/**
+ * 按宽高比裁剪（如 16:9、1:1 头像），自动计算能容纳的最大区域
+ * anchor: "center" | "top-left" | "top" | "top-right" | "left" | "right" |
+ *         "bottom-left" | "bottom" | "bottom-right"，决定保留图片的哪一部分
+ * 返回 PNG
+ */
+#[tauri::command]
+pub async fn crop_to_aspect_ratio(
+    input: Vec<u8>,
+    numerator: u32,
+    denominator: u32,
+    anchor: String,
+) -> Result<Vec<u8>, String> {
+    if numerator == 0 || denominator == 0 {
+        return Err("aspect ratio must be greater than 0".into());
+    }
+    let anchor = parse_anchor(&anchor)?;
+    tokio::task::spawn_blocking(move || {
+        let img = image::load_from_memory(&input).map_err(|e| format!("decode error: {}", e))?;
+        let (x, y, w, h) = aspect_rect((img.width(), img.height()), numerator, denominator, anchor);
+        encode_png(&crop(&img, x, y, w, h)?)
+    })
+    .await
+    .map_err(|e| format!("crop task error: {}", e))?
+}
+
+/// 取像素，超出图片时返回 fill
+fn pixel_or(img: &RgbaImage, x: i64, y: i64, fill: Rgba<u8>) -> Rgba<u8> {
+    if x < 0 || y < 0 || x >= img.width() as i64 || y >= img.height() as i64 {
+        return fill;
+    }
+    *img.get_pixel(x as u32, y as u32)
+}
+
+/// 双线性插值取样（像素中心在 +0.5 处），边缘与 fill 混合以避免锯齿
+fn sample_bilinear(img: &RgbaImage, x: f64, y: f64, fill: Rgba<u8>) -> Rgba<u8> {
+    let (x, y) = (x - 0.5, y - 0.5);
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let (x0, y0) = (x0 as i64, y0 as i64);
+    let p00 = pixel_or(img, x0, y0, fill);
+    let p10 = pixel_or(img, x0 + 1, y0, fill);
+    let p01 = pixel_or(img, x0, y0 + 1, fill);
+    let p11 = pixel_or(img, x0 + 1, y0 + 1, fill);
+    let mut out = [0u8; 4];
+    for (c, v) in out.iter_mut().enumerate() {
+        let top = p00.0[c] as f64 * (1.0 - fx) + p10.0[c] as f64 * fx;
+        let bottom = p01.0[c] as f64 * (1.0 - fx) + p11.0[c] as f64 * fx;
+        *v = (top * (1.0 - fy) + bottom * fy).round().clamp(0.0, 255.0) as u8;
+    }
+    Rgba(out)
+}
+
+/// 顺时针旋转任意角度；画布扩大到能完整容纳旋转后的图片，空白处填充 fill
+fn rotate_any(img: &RgbaImage, degrees: f64, fill: Rgba<u8>) -> RgbaImage {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let (w, h) = (img.width() as f64, img.height() as f64);
+    let new_w = (w * cos.abs() + h * sin.abs()).round().max(1.0) as u32;
+    let new_h = (w * sin.abs() + h * cos.abs()).round().max(1.0) as u32;
+    let (cx, cy) = (w / 2.0, h / 2.0);
+    let (ncx, ncy) = (new_w as f64 / 2.0, new_h as f64 / 2.0);
+
+    RgbaImage::from_fn(new_w, new_h, |x, y| {
+        // 输出像素中心逆向旋转回原图坐标
+        let dx = x as f64 + 0.5 - ncx;
+        let dy = y as f64 + 0.5 - ncy;
+        let sx = dx * cos + dy * sin + cx;
+        let sy = -dx * sin + dy * cos + cy;
+        sample_bilinear(img, sx, sy, fill)
+    })
+}
+
+/**
+ * 旋转图片（顺时针），返回 PNG
+ * 90 / 180 / 270 度为无损旋转；其他角度用双线性插值，画布扩大以容纳整张图片（不裁掉四角）
+ * fill_color: 空白处填充色 [r, g, b, a]，默认透明
+ */
+#[tauri::command]
+pub async fn rotate_image(
+    input: Vec<u8>,
+    degrees: f32,
+    fill_color: Option<[u8; 4]>,
+) -> Result<Vec<u8>, String> {
+    tokio::task::spawn_blocking(move || {
+        if !degrees.is_finite() {
+            return Err("degrees must be a finite number".into());
+        }
+        let img = image::load_from_memory(&input)
+            .map_err(|e| format!("decode error: {}", e))?
+            .to_rgba8();
+        let fill = Rgba(fill_color.unwrap_or([0, 0, 0, 0]));
+        let degrees = (degrees as f64).rem_euclid(360.0);
+        let is = |angle: f64| (degrees - angle).abs() < 1e-6;
+        let rotated = if is(0.0) || is(360.0) {
+            img
+        } else if is(90.0) {
+            image::imageops::rotate90(&img)
+        } else if is(180.0) {
+            image::imageops::rotate180(&img)
+        } else if is(270.0) {
+            image::imageops::rotate270(&img)
+        } else {
+            rotate_any(&img, degrees, fill)
+        };
+        encode_png(&rotated)
+    })
+    .await
+    .map_err(|e| format!("rotate task error: {}", e))?
+}
+
+/**
+ * 翻转图片，返回 PNG
+ * horizontal: 左右翻转；vertical: 上下翻转；都为 false 时原样重新编码
+ */
+#[tauri::command]
+pub async fn flip_image(
+    input: Vec<u8>,
+    horizontal: bool,
+    vertical: bool,
+) -> Result<Vec<u8>, String> {
+    tokio::task::spawn_blocking(move || {
+        let mut img = image::load_from_memory(&input)
+            .map_err(|e| format!("decode error: {}", e))?
+            .to_rgba8();
+        if horizontal {
+            image::imageops::flip_horizontal_in_place(&mut img);
+        }
+        if vertical {
+            image::imageops::flip_vertical_in_place(&mut img);
+        }
+        encode_png(&img)
+    })
+    .await
+    .map_err(|e| format!("flip task error: {}", e))?
+}
+
+/// 滤镜管线中的一步；params 同 apply_image_filter
+#[derive(Deserialize, Clone)]
+pub struct FilterStep {
+    pub filter: String,
+    pub params: Option<serde_json::Value>,
+}
+
+/// 读取滤镜的数值参数；参数存在但不是数字时报错
+fn filter_param(params: Option<&serde_json::Value>, name: &str) -> Result<Option<f32>, String> {
+    match params.and_then(|p| p.get(name)) {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(value) => value
+            .as_f64()
+            .map(|v| Some(v as f32))
+            .ok_or_else(|| format!("param {} must be a number", name)),
+    }
+}
+
+fn required_param(params: Option<&serde_json::Value>, name: &str) -> Result<f32, String> {
+    filter_param(params, name)?.ok_or_else(|| format!("missing param {}", name))
+}
+
+/// 对每个像素的 RGB 做变换，保留 alpha
+fn map_rgb(img: &mut RgbaImage, f: impl Fn([f32; 3]) -> [f32; 3]) {
+    for pixel in img.pixels_mut() {
+        let [r, g, b, _] = pixel.0;
+        let out = f([r as f32, g as f32, b as f32]);
+        for (channel, v) in pixel.0.iter_mut().zip(out) {
+            *channel = v.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+}
+
+/// 应用单个滤镜
+fn apply_filter(
+    mut img: RgbaImage,
+    filter: &str,
+    params: Option<&serde_json::Value>,
+) -> Result<RgbaImage, String> {
+    match filter {
+        "grayscale" => Ok(DynamicImage::ImageRgba8(img).grayscale().to_rgba8()),
+        "sepia" => {
+            map_rgb(&mut img, |[r, g, b]| {
+                [
+                    0.393 * r + 0.769 * g + 0.189 * b,
+                    0.349 * r + 0.686 * g + 0.168 * b,
+                    0.272 * r + 0.534 * g + 0.131 * b,
+                ]
+            });
+            Ok(img)
+        }
+        "blur" => {
+            let sigma = filter_param(params, "sigma")?.unwrap_or(2.0);
+            if sigma <= 0.0 {
+                return Err("param sigma must be greater than 0".into());
+            }
+            Ok(image::imageops::blur(&img, sigma))
+        }
+        "sharpen" => {
+            let sigma = filter_param(params, "sigma")?.unwrap_or(1.0);
+            let threshold = filter_param(params, "threshold")?.unwrap_or(0.0);
+            Ok(image::imageops::unsharpen(&img, sigma, threshold as i32))
+        }
+        "invert" => {
+            image::imageops::invert(&mut img);
+            Ok(img)
+        }
+        "brightness" => {
+            let delta = required_param(params, "delta")?;
+            Ok(image::imageops::brighten(&img, delta.round() as i32))
+        }
+        "contrast" => {
+            // 以中灰为中心拉伸，1 为不变，0 为全灰
+            let factor = required_param(params, "factor")?;
+            if factor < 0.0 {
+                return Err("param factor must not be negative".into());
+            }
+            map_rgb(&mut img, |c| c.map(|v| (v - 128.0) * factor + 128.0));
+            Ok(img)
+        }
+        other => Err(format!("unknown filter: {}", other)),
+    }
+}
+
+/**
+ * 对图片应用滤镜，返回 PNG（保留透明通道）
+ * filter: "grayscale" | "sepia" | "blur" | "sharpen" | "invert" | "brightness" | "contrast"
+ * params: 滤镜参数（JSON 对象）
+ *   blur: { sigma }（高斯模糊半径，默认 2）
+ *   sharpen: { sigma, threshold }（USM 锐化，默认 1 / 0）
+ *   brightness: { delta }（-255..255，必填）
+ *   contrast: { factor }（1 为不变，大于 1 增强，必填）
+ */
+#[tauri::command]
+pub async fn apply_image_filter(
+    input: Vec<u8>,
+    filter: String,
+    params: Option<serde_json::Value>,
+) -> Result<Vec<u8>, String> {
+    tokio::task::spawn_blocking(move || {
+        let img = image::load_from_memory(&input)
+            .map_err(|e| format!("decode error: {}", e))?
+            .to_rgba8();
+        encode_png(&apply_filter(img, &filter, params.as_ref())?)
+    })
+    .await
+    .map_err(|e| format!("filter task error: {}", e))?
+}
+
+/**
+ * 依次应用多个滤镜，只解码和编码一次，返回 PNG
+ * steps: [{ filter, params }]，同 apply_image_filter；为空时原样重新编码
+ */
+#[tauri::command]
+pub async fn apply_image_filters_pipeline(
+    input: Vec<u8>,
+    steps: Vec<FilterStep>,
+) -> Result<Vec<u8>, String> {
+    tokio::task::spawn_blocking(move || {
+        let mut img = image::load_from_memory(&input)
+            .map_err(|e| format!("decode error: {}", e))?
+            .to_rgba8();
+        for (i, step) in steps.iter().enumerate() {
+            img = apply_filter(img, &step.filter, step.params.as_ref())
+                .map_err(|e| format!("step {} ({}): {}", i, step.filter, e))?;
+        }
+        encode_png(&img)
+    })
+    .await
+    .map_err(|e| format!("filter task error: {}", e))?
+}
+
+/// 内置等宽字体（DejaVu Sans Mono，许可见 assets/fonts）
+const WATERMARK_FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSansMono.ttf");
+
+/// 水印与图片边缘的距离（像素）
+const WATERMARK_MARGIN: i64 = 16;
+
+/// 水印配置：带 text 为文字水印，带 overlay_image 为图片水印
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+pub enum WatermarkConfig {
+    Text {
+        text: String,
+        /// 字号（像素）
+        font_size: u32,
+        /// RGBA
+        color: [u8; 4],
+        opacity: f32,
+        position: String,
+    },
+    Image {
+        /// 水印图片（PNG / JPEG 等字节），建议带透明通道
+        overlay_image: Vec<u8>,
+        opacity: f32,
+        position: String,
+        /// 相对水印图片原始尺寸的缩放比例，1 为不缩放
+        scale: f32,
+    },
+}
+
+/// 文字水印图层：字形覆盖率乘以颜色 alpha 和 opacity 作为透明度
+fn text_layer(
+    text: &str,
+    font_size: u32,
+    color: [u8; 4],
+    opacity: f32,
+) -> Result<RgbaImage, String> {
+    if font_size == 0 {
+        return Err("font_size must be greater than 0".into());
+    }
+    let font = Font::try_from_bytes(WATERMARK_FONT).ok_or("failed to load watermark font")?;
+    let scale = Scale::uniform(font_size as f32);
+    let (w, h) = text_size(scale, &font, text);
+    if w <= 0 || h <= 0 {
+        return Err("watermark text is empty".into());
+    }
+    let mut coverage = GrayImage::new(w as u32, h as u32);
+    draw_text_mut(&mut coverage, Luma([255]), 0, 0, scale, &font, text);
+    let alpha = color[3] as f32 / 255.0 * opacity;
+    Ok(RgbaImage::from_fn(w as u32, h as u32, |x, y| {
+        let a = coverage.get_pixel(x, y).0[0] as f32 * alpha;
+        Rgba([color[0], color[1], color[2], a.round() as u8])
+    }))
+}
+
+/// 图片水印图层：按 scale 缩放后把 alpha 乘以 opacity
+fn image_layer(overlay_image: &[u8], opacity: f32, scale: f32) -> Result<RgbaImage, String> {
+    if !scale.is_finite() || scale <= 0.0 {
+        return Err("scale must be greater than 0".into());
+    }
+    let mut layer = image::load_from_memory(overlay_image)
+        .map_err(|e| format!("overlay decode error: {}", e))?
+        .to_rgba8();
+    if scale != 1.0 {
+        let w = ((layer.width() as f32 * scale).round() as u32).max(1);
+        let h = ((layer.height() as f32 * scale).round() as u32).max(1);
+        layer = image::imageops::resize(&layer, w, h, FilterType::Lanczos3);
+    }
+    for pixel in layer.pixels_mut() {
+        pixel.0[3] = (pixel.0[3] as f32 * opacity).round() as u8;
+    }
+    Ok(layer)
+}
+
+/// 按锚点计算水印左上角位置；水印比图片大时可为负，超出部分被裁掉
+fn watermark_origin(base: (u32, u32), mark: (u32, u32), (ax, ay): (u32, u32)) -> (i64, i64) {
+    let place = |base: u32, mark: u32, anchor: u32| {
+        let free = base as i64 - mark as i64;
+        match anchor {
+            0 => WATERMARK_MARGIN,
+            1 => free / 2,
+            _ => free - WATERMARK_MARGIN,
+        }
+    };
+    (place(base.0, mark.0, ax), place(base.1, mark.1, ay))
+}
+
+/**
+ * 给图片加文字或图片水印，返回 PNG
+ * watermark:
+ *   文字：{ text, font_size, color: [r, g, b, a], opacity, position }，使用内置等宽字体，单行
+ *   图片：{ overlay_image, opacity, position, scale }
+ * opacity: 0-1，与水印自身的 alpha 相乘后按 alpha 混合到原图
+ * position: 同 crop_to_aspect_ratio 的 anchor（"top-left" | "top" | ... | "center" | ... | "bottom-right"），
+ *           距边缘 16 像素
+ */
+#[tauri::command]
+pub fn embed_image_watermark(
+    base_image: Vec<u8>,
+    watermark: WatermarkConfig,
+) -> Result<Vec<u8>, String> {
+    let (opacity, position) = match &watermark {
+        WatermarkConfig::Text {
+            opacity, position, ..
+        }
+        | WatermarkConfig::Image {
+            opacity, position, ..
+        } => (*opacity, position),
+    };
+    if !(0.0..=1.0).contains(&opacity) {
+        return Err("opacity must be between 0 and 1".into());
+    }
+    let anchor = parse_anchor(position)?;
+    let layer = match &watermark {
+        WatermarkConfig::Text {
+            text,
+            font_size,
+            color,
+            ..
+        } => text_layer(text, *font_size, *color, opacity)?,
+        WatermarkConfig::Image {
+            overlay_image,
+            scale,
+            ..
+        } => image_layer(overlay_image, opacity, *scale)?,
+    };
+    let mut img = image::load_from_memory(&base_image)
+        .map_err(|e| format!("decode error: {}", e))?
+        .to_rgba8();
+    let (x, y) = watermark_origin(img.dimensions(), layer.dimensions(), anchor);
+    image::imageops::overlay(&mut img, &layer, x, y);
+    encode_png(&img)
+}
+/// convert_image_format 的缺省有损质量
+const DEFAULT_CONVERT_QUALITY: u8 = 85;
+
+/**
+ * 把图片转换为指定格式："png" | "jpeg"（"jpg"）| "webp" | "bmp" | "gif"
+ * quality: jpeg / webp 的质量 1-100，缺省 85；png / bmp / gif 忽略
+ * jpeg 不支持透明通道，转换时丢弃 alpha
+ */
+#[tauri::command]
+pub async fn convert_image_format(
+    input: Vec<u8>,
+    output_format: String,
+    quality: Option<u8>,
+) -> Result<Vec<u8>, String> {
+    tokio::task::spawn_blocking(move || {
+        let img = image::load_from_memory(&input).map_err(|e| format!("decode error: {}", e))?;
+        let quality = quality.unwrap_or(DEFAULT_CONVERT_QUALITY).clamp(1, 100);
+        let mut out = Vec::new();
+        let res = match output_format.to_ascii_lowercase().as_str() {
+            "png" => img.write_to(&mut Cursor::new(&mut out), ImageFormat::Png),
+            "jpeg" | "jpg" => image::codecs::jpeg::JpegEncoder::new_with_quality(&mut out, quality)
+                .encode_image(&img.to_rgb8()),
+            // image 0.24 把有损 WebP（libwebp）标记为弃用，但 quality 参数依赖它
+            #[allow(deprecated)]
+            "webp" => {
+                let rgba = img.to_rgba8();
+                image::codecs::webp::WebPEncoder::new_with_quality(
+                    &mut out,
+                    image::codecs::webp::WebPQuality::lossy(quality),
+                )
+                .encode(
+                    rgba.as_raw(),
+                    rgba.width(),
+                    rgba.height(),
+                    ColorType::Rgba8,
+                )
+            }
+            // bmp / gif 编码器只接受 8 位数据
+            "bmp" => DynamicImage::ImageRgba8(img.to_rgba8())
+                .write_to(&mut Cursor::new(&mut out), ImageFormat::Bmp),
+            "gif" => DynamicImage::ImageRgba8(img.to_rgba8())
+                .write_to(&mut Cursor::new(&mut out), ImageFormat::Gif),
+            other => return Err(format!("unsupported output format: {}", other)),
+        };
+        res.map_err(|e| format!("encode error: {}", e))?;
+        Ok(out)
+    })
+    .await
+    .map_err(|e| format!("convert task error: {}", e))?
+}
+
+/// 主色提取最多采样的像素数
+const MAX_PALETTE_SAMPLES: usize = 10_000;
+/// k-means 最大迭代次数
+const KMEANS_ITERATIONS: usize = 20;
+
+/// 调色板中的一种颜色；frequency 为该颜色覆盖的采样像素比例（0-1）
+#[derive(Serialize, Clone)]
+pub struct ColorEntry {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub hex: String,
+    pub frequency: f32,
+}
+
+/// 均匀采样不超过 MAX_PALETTE_SAMPLES 个像素，跳过完全透明的像素
+fn palette_samples(img: &RgbaImage) -> Vec<[u8; 3]> {
+    let total = img.width() as usize * img.height() as usize;
+    let step = total.div_ceil(MAX_PALETTE_SAMPLES).max(1);
+    img.pixels()
+        .step_by(step)
+        .filter(|p| p.0[3] > 0)
+        .map(|p| [p.0[0], p.0[1], p.0[2]])
+        .collect()
+}
+
+fn mean_color(pixels: &[[u8; 3]]) -> [u8; 3] {
+    let mut sum = [0u64; 3];
+    for p in pixels {
+        for (s, &v) in sum.iter_mut().zip(p) {
+            *s += v as u64;
+        }
+    }
+    let n = pixels.len().max(1) as u64;
+    [(sum[0] / n) as u8, (sum[1] / n) as u8, (sum[2] / n) as u8]
+}
+
+/// 通道取值范围最大的通道及其范围
+fn widest_channel(pixels: &[[u8; 3]]) -> (usize, u8) {
+    (0..3)
+        .map(|c| {
+            let min = pixels.iter().map(|p| p[c]).min().unwrap_or(0);
+            let max = pixels.iter().map(|p| p[c]).max().unwrap_or(0);
+            (c, max - min)
+        })
+        .max_by_key(|&(_, range)| range)
+        .unwrap_or((0, 0))
+}
+
+/// 中位切分：反复把范围最大的颜色盒沿最宽通道从中位数处一分为二
+fn median_cut(pixels: Vec<[u8; 3]>, k: usize) -> Vec<([u8; 3], usize)> {
+    let mut boxes = vec![pixels];
+    while boxes.len() < k {
+        let Some((index, channel)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.len() > 1)
+            .map(|(i, b)| (i, widest_channel(b)))
+            .filter(|(_, (_, range))| *range > 0)
+            .max_by_key(|(_, (_, range))| *range)
+            .map(|(i, (channel, _))| (i, channel))
+        else {
+            break;
+        };
+        let mut b = boxes.swap_remove(index);
+        b.sort_unstable_by_key(|p| p[channel]);
+        // 在中位数处切分，但不把同一取值分到两边
+        let median = b[b.len() / 2][channel];
+        let mut at = b.partition_point(|p| p[channel] < median);
+        if at == 0 {
+            at = b.partition_point(|p| p[channel] <= median);
+        }
+        let upper = b.split_off(at);
+        boxes.push(b);
+        boxes.push(upper);
+    }
+    boxes
+        .into_iter()
+        .map(|b| (mean_color(&b), b.len()))
+        .collect()
+}
+
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    (0..3)
+        .map(|c| {
+            let d = a[c] as i32 - b[c] as i32;
+            (d * d) as u32
+        })
+        .sum()
+}
+
+/// k-means：初始中心取整体均值，之后依次取离已有中心最远的像素（结果可复现）
+fn kmeans(pixels: &[[u8; 3]], k: usize) -> Vec<([u8; 3], usize)> {
+    let mut centers = vec![mean_color(pixels)];
+    while centers.len() < k {
+        let Some(&farthest) = pixels
+            .iter()
+            .max_by_key(|&&p| centers.iter().map(|&c| distance(p, c)).min().unwrap_or(0))
+        else {
+            break;
+        };
+        if centers.contains(&farthest) {
+            break;
+        }
+        centers.push(farthest);
+    }
+
+    let nearest = |p: [u8; 3], centers: &[[u8; 3]]| {
+        (0..centers.len())
+            .min_by_key(|&i| distance(p, centers[i]))
+            .unwrap_or(0)
+    };
+    let mut assignment = vec![usize::MAX; pixels.len()];
+    for _ in 0..KMEANS_ITERATIONS {
+        let mut changed = false;
+        for (slot, &p) in assignment.iter_mut().zip(pixels) {
+            let i = nearest(p, &centers);
+            if *slot != i {
+                *slot = i;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+        for (i, center) in centers.iter_mut().enumerate() {
+            let members: Vec<[u8; 3]> = pixels
+                .iter()
+                .zip(&assignment)
+                .filter(|&(_, &a)| a == i)
+                .map(|(&p, _)| p)
+                .collect();
+            if !members.is_empty() {
+                *center = mean_color(&members);
+            }
+        }
+    }
+
+    let mut counts = vec![0usize; centers.len()];
+    for &a in &assignment {
+        counts[a] += 1;
+    }
+    centers
+        .into_iter()
+        .zip(counts)
+        .filter(|&(_, n)| n > 0)
+        .collect()
+}
+
+/**
+ * 提取图片中最主要的 k 种颜色（1-32），按占比从高到低排列
+ * algorithm: "median_cut"（默认）| "kmeans"
+ * 最多均匀采样 10000 个像素，完全透明的像素不计入
+ */
+#[tauri::command]
+pub async fn get_image_dominant_colors(
+    input: Vec<u8>,
+    k: u8,
+    algorithm: Option<String>,
+) -> Result<Vec<ColorEntry>, String> {
+    tokio::task::spawn_blocking(move || {
+        let img = image::load_from_memory(&input)
+            .map_err(|e| format!("decode error: {}", e))?
+            .to_rgba8();
+        let k = k.clamp(1, 32) as usize;
+        let pixels = palette_samples(&img);
+        if pixels.is_empty() {
+            return Ok(Vec::new());
+        }
+        let total = pixels.len() as f32;
+
+        let mut clusters = match algorithm.as_deref().unwrap_or("median_cut") {
+            "median_cut" => median_cut(pixels, k),
+            "kmeans" => kmeans(&pixels, k),
+            other => return Err(format!("unknown algorithm: {}", other)),
+        };
+        clusters.sort_by_key(|&(_, n)| std::cmp::Reverse(n));
+        Ok(clusters
+            .into_iter()
+            .map(|([r, g, b], n)| ColorEntry {
+                r,
+                g,
+                b,
+                hex: format!("#{:02x}{:02x}{:02x}", r, g, b),
+                frequency: n as f32 / total,
+            })
+            .collect())
+    })
+    .await
+    .map_err(|e| format!("palette task error: {}", e))?
+}
+
+/// extract_palette 的取色来源：屏幕区域（全局逻辑坐标）或本地图片文件
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+pub enum PaletteSource {
+    Region {
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    },
+    File {
+        path: String,
+    },
+}
+
+/// 调色板中的一种颜色；percentage 为覆盖的采样像素比例（0-100）
+#[derive(Serialize, Clone)]
+pub struct PaletteColor {
+    pub hex: String,
+    pub percentage: f32,
+}
+
+/// extract_palette 的结果
+#[derive(Serialize, Clone)]
+pub struct Palette {
+    /// 按占比从高到低排列
+    pub colors: Vec<PaletteColor>,
+    /// 占比最高的颜色；图片完全透明时为 None
+    pub dominant: Option<String>,
+}
+
+/**
+ * 提取屏幕区域或图片文件的主色调（中位切分），用于按背景给界面着色
+ * source: { x, y, width, height }（截取逻辑同 capture_area）| { path }
+ * colors: 最多返回的颜色数（1-32）
+ * 最多均匀采样 10000 个像素，不逐像素统计；图片文件中完全透明的像素不计入
+ */
+#[tauri::command]
+pub async fn extract_palette(source: PaletteSource, colors: u8) -> Result<Palette, String> {
+    let k = colors.clamp(1, 32) as usize;
+    tokio::task::spawn_blocking(move || {
+        let img = match source {
+            PaletteSource::Region {
+                x,
+                y,
+                width,
+                height,
+            } => commands::area_rgba(x, y, width, height)?,
+            PaletteSource::File { path } => image::open(&path)
+                .map_err(|e| format!("decode {} failed: {}", path, e))?
+                .to_rgba8(),
+        };
+        let pixels = palette_samples(&img);
+        let total = pixels.len() as f32;
+        let mut clusters = median_cut(pixels, k);
+        clusters.retain(|&(_, n)| n > 0);
+        clusters.sort_by_key(|&(_, n)| std::cmp::Reverse(n));
+        let colors: Vec<PaletteColor> = clusters
+            .into_iter()
+            .map(|([r, g, b], n)| PaletteColor {
+                hex: format!("#{:02x}{:02x}{:02x}", r, g, b),
+                percentage: n as f32 / total * 100.0,
+            })
+            .collect();
+        Ok(Palette {
+            dominant: colors.first().map(|c| c.hex.clone()),
+            colors,
+        })
+    })
+    .await
+    .map_err(|e| format!("palette task error: {}", e))?
+}
+
+/// 缩略图的 JPEG 质量
+const THUMBNAIL_QUALITY: u8 = 80;
+
+/**
+ * 为磁盘上的图片生成 JPEG 缩略图（质量 80），返回缩略图路径，图片数据不经过 IPC
+ * crop_to_fit: 为 true 时居中裁剪，正好填满 max_width x max_height；
+ *              为 false 时等比缩放后居中放在 max_width x max_height 的黑色画布上（letterbox）
+ * output_path: 不传时写到原图同目录下的 {hash}_thumb_{w}x{h}.jpg，
+ *              hash 取原图文件名（不含扩展名），即 cache_image_to_path 缓存文件名中的 hash
+ * 临时模式下需 allow_persistent: true
+ */
+#[tauri::command]
+pub async fn generate_image_thumbnail(
+    state: State<'_, AppState>,
+    source_path: String,
+    max_width: u32,
+    max_height: u32,
+    crop_to_fit: bool,
+    output_path: Option<String>,
+    allow_persistent: Option<bool>,
+) -> Result<String, String> {
+    ephemeral::check_persist(&state, allow_persistent.unwrap_or(false))?;
+    if max_width == 0 || max_height == 0 {
+        return Err("max_width and max_height must be greater than 0".into());
+    }
+    tokio::task::spawn_blocking(move || {
+        let img = image::open(&source_path).map_err(|e| format!("decode error: {}", e))?;
+        let src = (img.width(), img.height());
+        let thumb = if crop_to_fit {
+            let (x, y, w, h) = aspect_rect(src, max_width, max_height, (1, 1));
+            let cropped = img.crop_imm(x, y, w, h).to_rgb8();
+            image::imageops::resize(&cropped, max_width, max_height, FilterType::Lanczos3)
+        } else {
+            let (w, h) = target_size(src, max_width, max_height, true);
+            let scaled = image::imageops::resize(&img.to_rgb8(), w, h, FilterType::Lanczos3);
+            let mut canvas = RgbImage::new(max_width, max_height);
+            let (x, y) = ((max_width - w) / 2, (max_height - h) / 2);
+            image::imageops::overlay(&mut canvas, &scaled, x as i64, y as i64);
+            canvas
+        };
+
+        let output = match output_path {
+            Some(path) => PathBuf::from(path),
+            None => {
+                let source = Path::new(&source_path);
+                let hash = source
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                source.with_file_name(format!("{}_thumb_{}x{}.jpg", hash, max_width, max_height))
+            }
+        };
+        let file = File::create(&output).map_err(|e| format!("file create: {}", e))?;
+        image::codecs::jpeg::JpegEncoder::new_with_quality(BufWriter::new(file), THUMBNAIL_QUALITY)
+            .encode_image(&thumb)
+            .map_err(|e| format!("encode error: {}", e))?;
+        Ok(output.to_string_lossy().into_owned())
+    })
+    .await
+    .map_err(|e| format!("thumbnail task error: {}", e))?
+}
+
+/// 批量生成本地缩略图的最大并行线程数
+const MAX_THUMBNAIL_THREADS: usize = 4;
+
+/// generate_thumbnail 的结果：缩略图路径与原图尺寸
+#[derive(Serialize, Clone)]
+pub struct LocalThumbnail {
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// generate_thumbnails 中单个文件的结果；失败时 thumbnail 为 None，error 为原因
+#[derive(Serialize)]
+pub struct BatchThumbnail {
+    pub src_path: String,
+    pub thumbnail: Option<LocalThumbnail>,
+    pub error: Option<String>,
+}
+
+/// 解析缩略图格式："jpeg"（默认）| "webp"
+fn thumbnail_format(format: Option<&str>) -> Result<CaptureFormat, String> {
+    match CaptureFormat::parse(Some(format.unwrap_or("jpeg")))? {
+        CaptureFormat::Png => Err("thumbnail format must be jpeg or webp".into()),
+        format => Ok(format),
+    }
+}
+
+/// local_thumbnail 临时文件序号
+static TMP_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// 生成（或复用）单个本地图片的缩略图
+fn local_thumbnail(
+    src_path: &str,
+    max_edge: u32,
+    dest_dir: &Path,
+    format: CaptureFormat,
+) -> Result<LocalThumbnail, String> {
+    let modified = fs::metadata(src_path)
+        .and_then(|m| m.modified())
+        .map_err(|e| format!("stat {} failed: {}", src_path, e))?;
+    let mtime = modified
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let mut hasher = Sha256::new();
+    hasher.update(src_path.as_bytes());
+    hasher.update(format!("@{}@{}", mtime, max_edge).as_bytes());
+    let path = dest_dir.join(format!("{:x}.{}", hasher.finalize(), format.as_str()));
+
+    // 原图未修改过的缩略图直接复用，只读取原图文件头获取尺寸
+    if path.is_file() {
+        let (width, height) =
+            image::image_dimensions(src_path).map_err(|e| format!("decode error: {}", e))?;
+        return Ok(LocalThumbnail {
+            path: path.to_string_lossy().into_owned(),
+            width,
+            height,
+        });
+    }
+
+    let img = image::open(src_path).map_err(|e| format!("decode error: {}", e))?;
+    let (width, height) = (img.width(), img.height());
+    // 不放大小图
+    let edge = max_edge.min(width.max(height));
+    let (w, h) = target_size((width, height), edge, edge, true);
+    let thumb = image::imageops::resize(&img.to_rgba8(), w, h, FilterType::Triangle);
+    let data = encode_rgba(&thumb, format, Some(THUMBNAIL_QUALITY))?;
+
+    // 先写临时文件再重命名，其他请求不会读到半截文件；临时文件名含 pid 和序号，
+    // 同一进程内并发生成同一缩略图时各写各的。重命名失败但目标已存在，
+    // 说明另一个请求已写好（Windows 上目标存在时 rename 会失败），直接复用
+    let seq = TMP_SEQ.fetch_add(1, Ordering::Relaxed);
+    let tmp = path.with_extension(format!("{}-{}.tmp", std::process::id(), seq));
+    fs::write(&tmp, data).map_err(|e| format!("write error: {}", e))?;
+    if let Err(e) = fs::rename(&tmp, &path) {
+        let _ = fs::remove_file(&tmp);
+        if !path.is_file() {
+            return Err(format!("rename error: {}", e));
+        }
+    }
+    Ok(LocalThumbnail {
+        path: path.to_string_lossy().into_owned(),
+        width,
+        height,
+    })
+}
+
+/**
+ * 为本地图片生成缩略图（聊天文件列表使用，避免在 webview 中解码原图）
+ * 等比缩小到最长边为 max_edge（小图不放大），写入 dest_dir，返回缩略图路径和原图尺寸
+ * 文件名取 (原图路径, 修改时间, max_edge) 的 hash，原图未变时直接返回已有缩略图，不重新编码
+ * format: "jpeg"（默认）| "webp"
+ * 临时模式下需 allow_persistent: true
+ */
+#[tauri::command]
+pub async fn generate_thumbnail(
+    state: State<'_, AppState>,
+    src_path: String,
+    max_edge: u32,
+    dest_dir: String,
+    format: Option<String>,
+    allow_persistent: Option<bool>,
+) -> Result<LocalThumbnail, String> {
+    ephemeral::check_persist(&state, allow_persistent.unwrap_or(false))?;
+    if max_edge == 0 {
+        return Err("max_edge must be greater than 0".into());
+    }
+    let format = thumbnail_format(format.as_deref())?;
+    fs::create_dir_all(&dest_dir).map_err(|e| format!("mkdir error: {}", e))?;
+    tokio::task::spawn_blocking(move || {
+        local_thumbnail(&src_path, max_edge, Path::new(&dest_dir), format)
+    })
+    .await
+    .map_err(|e| format!("thumbnail task error: {}", e))?
+}
+
+/**
+ * 批量生成本地图片缩略图，参数同 generate_thumbnail
+ * 在最多 4 个线程上并行处理，结果与 src_paths 顺序一致；单个文件失败不影响其他文件
+ */
+#[tauri::command]
+pub async fn generate_thumbnails(
+    state: State<'_, AppState>,
+    src_paths: Vec<String>,
+    max_edge: u32,
+    dest_dir: String,
+    format: Option<String>,
+    allow_persistent: Option<bool>,
+) -> Result<Vec<BatchThumbnail>, String> {
+    ephemeral::check_persist(&state, allow_persistent.unwrap_or(false))?;
+    if max_edge == 0 {
+        return Err("max_edge must be greater than 0".into());
+    }
+    let format = thumbnail_format(format.as_deref())?;
+    fs::create_dir_all(&dest_dir).map_err(|e| format!("mkdir error: {}", e))?;
+    let threads = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(MAX_THUMBNAIL_THREADS);
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .map_err(|e| format!("thread pool error: {}", e))?;
+
+    tokio::task::spawn_blocking(move || {
+        pool.install(|| {
+            src_paths
+                .into_par_iter()
+                .map(|src_path| {
+                    match local_thumbnail(&src_path, max_edge, Path::new(&dest_dir), format) {
+                        Ok(thumbnail) => BatchThumbnail {
+                            src_path,
+                            thumbnail: Some(thumbnail),
+                            error: None,
+                        },
+                        Err(e) => BatchThumbnail {
+                            src_path,
+                            thumbnail: None,
+                            error: Some(e),
+                        },
+                    }
+                })
+                .collect()
+        })
+    })
+    .await
+    .map_err(|e| format!("thumbnail task error: {}", e))
+}
+
+/// EXIF 中的一项（raw 列表用）；value 为带单位的可读文本
+#[derive(Serialize, Clone)]
+pub struct ExifEntry {
+    pub tag: String,
+    /// 0 为主图，1 为缩略图
+    pub ifd: u16,
+    pub value: String,
+}
+
+/// 常用的 EXIF 信息；图片没有 EXIF 时全部为 None，raw 为空
+#[derive(Serialize, Clone, Default)]
+pub struct ExifData {
+    pub make: Option<String>,
+    pub model: Option<String>,
+    pub datetime: Option<String>,
+    pub gps_lat: Option<f64>,
+    pub gps_lon: Option<f64>,
+    pub orientation: Option<u16>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub raw: Vec<ExifEntry>,
+}
+
+fn exif_string(exif: &exif::Exif, tag: exif::Tag) -> Option<String> {
+    match &exif.get_field(tag, exif::In::PRIMARY)?.value {
+        exif::Value::Ascii(values) => values
+            .first()
+            .map(|v| String::from_utf8_lossy(v).trim().to_string())
+            .filter(|v| !v.is_empty()),
+        _ => None,
+    }
+}
+
+fn exif_uint(exif: &exif::Exif, tag: exif::Tag) -> Option<u32> {
+    exif.get_field(tag, exif::In::PRIMARY)?.value.get_uint(0)
+}
+
+/// 度分秒坐标转为十进制度数，ref_tag 为 "S" / "W" 时取负
+fn exif_coordinate(exif: &exif::Exif, tag: exif::Tag, ref_tag: exif::Tag) -> Option<f64> {
+    let exif::Value::Rational(dms) = &exif.get_field(tag, exif::In::PRIMARY)?.value else {
+        return None;
+    };
+    let degrees = dms
+        .iter()
+        .take(3)
+        .zip([1.0, 60.0, 3600.0])
+        .map(|(r, div)| r.to_f64() / div)
+        .sum::<f64>();
+    if !degrees.is_finite() {
+        return None;
+    }
+    match exif_string(exif, ref_tag).as_deref() {
+        Some("S") | Some("W") => Some(-degrees),
+        _ => Some(degrees),
+    }
+}
+
+fn parse_exif(reader: &mut (impl std::io::BufRead + std::io::Seek)) -> Result<ExifData, String> {
+    let exif = match exif::Reader::new().read_from_container(reader) {
+        Ok(exif) => exif,
+        // 格式支持但没有 EXIF（如 PNG）
+        Err(exif::Error::NotFound(_)) | Err(exif::Error::BlankValue(_)) => {
+            return Ok(ExifData::default());
+        }
+        Err(e) => return Err(format!("exif error: {}", e)),
+    };
+
+    let raw = exif
+        .fields()
+        .map(|f| ExifEntry {
+            tag: f.tag.to_string(),
+            ifd: f.ifd_num.index(),
+            value: f.display_value().with_unit(&exif).to_string(),
+        })
+        .collect();
+    Ok(ExifData {
+        make: exif_string(&exif, exif::Tag::Make),
+        model: exif_string(&exif, exif::Tag::Model),
+        datetime: exif_string(&exif, exif::Tag::DateTimeOriginal)
+            .or_else(|| exif_string(&exif, exif::Tag::DateTime)),
+        gps_lat: exif_coordinate(&exif, exif::Tag::GPSLatitude, exif::Tag::GPSLatitudeRef),
+        gps_lon: exif_coordinate(&exif, exif::Tag::GPSLongitude, exif::Tag::GPSLongitudeRef),
+        orientation: exif_uint(&exif, exif::Tag::Orientation).map(|v| v as u16),
+        width: exif_uint(&exif, exif::Tag::PixelXDimension)
+            .or_else(|| exif_uint(&exif, exif::Tag::ImageWidth)),
+        height: exif_uint(&exif, exif::Tag::PixelYDimension)
+            .or_else(|| exif_uint(&exif, exif::Tag::ImageLength)),
+        raw,
+    })
+}
+
+/**
+ * 读取图片（JPEG / TIFF / HEIF / PNG / WebP）的 EXIF 信息
+ * datetime 优先取拍摄时间（DateTimeOriginal）；gps_lat / gps_lon 为十进制度数，南纬 / 西经为负
+ * 图片没有 EXIF 时返回全部为空的结果，不报错
+ */
+#[tauri::command]
+pub fn extract_image_exif(input: Vec<u8>) -> Result<ExifData, String> {
+    parse_exif(&mut Cursor::new(input))
+}
+
+/**
+ * 读取磁盘上图片的 EXIF 信息，结果同 extract_image_exif
+ */
+#[tauri::command]
+pub fn extract_image_exif_from_path(path: String) -> Result<ExifData, String> {
+    let file = File::open(&path).map_err(|e| format!("open {} failed: {}", path, e))?;
+    parse_exif(&mut std::io::BufReader::new(file))
+}
+
+/// 感知哈希的边长（8x8 = 64 位）
+const HASH_SIZE: usize = 8;
+/// pHash 做 DCT 前缩小到的边长
+const PHASH_SIZE: usize = 32;
+
+/// dHash：缩小为 9x8 灰度图，每个像素与右侧相邻像素比较亮度
+fn dhash(img: &DynamicImage) -> u64 {
+    let gray = img
+        .resize_exact(HASH_SIZE as u32 + 1, HASH_SIZE as u32, FilterType::Triangle)
+        .to_luma8();
+    let mut hash = 0u64;
+    for y in 0..HASH_SIZE as u32 {
+        for x in 0..HASH_SIZE as u32 {
+            let brighter = gray.get_pixel(x, y)[0] > gray.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | brighter as u64;
+        }
+    }
+    hash
+}
+
+/// pHash：缩小为 32x32 灰度图做二维 DCT，左上 8x8 低频系数与其中位数（不含直流分量）比较
+fn phash(img: &DynamicImage) -> u64 {
+    let (n, k) = (PHASH_SIZE, HASH_SIZE);
+    let gray = img
+        .resize_exact(n as u32, n as u32, FilterType::Triangle)
+        .to_luma8();
+    let pixels: Vec<f64> = gray.pixels().map(|p| p[0] as f64).collect();
+    // cos[u * n + x] = cos((2x + 1)uπ / 2n)，只需要前 k 个频率
+    let cos: Vec<f64> = (0..k * n)
+        .map(|i| {
+            let (u, x) = ((i / n) as f64, (i % n) as f64);
+            ((2.0 * x + 1.0) * u * std::f64::consts::PI / (2 * n) as f64).cos()
+        })
+        .collect();
+    // 先按行、再按列做一维 DCT
+    let rows: Vec<f64> = (0..n * k)
+        .map(|i| {
+            let (y, u) = (i / k, i % k);
+            (0..n).map(|x| pixels[y * n + x] * cos[u * n + x]).sum()
+        })
+        .collect();
+    let coeffs: Vec<f64> = (0..k * k)
+        .map(|i| {
+            let (v, u) = (i / k, i % k);
+            (0..n).map(|y| rows[y * k + u] * cos[v * n + y]).sum()
+        })
+        .collect();
+
+    let mut sorted = coeffs[1..].to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let median = sorted[sorted.len() / 2];
+    coeffs
+        .iter()
+        .fold(0u64, |hash, &c| (hash << 1) | (c > median) as u64)
+}
+
+/// 解析感知哈希算法："dhash"（缺省）| "phash"，返回计算 64 位哈希的函数
+pub fn hash_algorithm(algorithm: Option<&str>) -> Result<fn(&DynamicImage) -> u64, String> {
+    match algorithm.unwrap_or("dhash") {
+        "dhash" => Ok(dhash),
+        "phash" => Ok(phash),
+        other => Err(format!("unknown algorithm: {}", other)),
+    }
+}
+
+/// 解析 16 位十六进制的哈希字符串
+pub fn parse_hash(hash: &str) -> Result<u64, String> {
+    let hash = hash.trim();
+    if hash.len() != 16 {
+        return Err(format!("invalid hash: {}", hash));
+    }
+    u64::from_str_radix(hash, 16).map_err(|_| format!("invalid hash: {}", hash))
+}
+
+/**
+ * 计算图片的感知哈希，返回 16 位十六进制字符串，用于查找重复或相似的图片
+ * algorithm: "dhash"（默认，差异哈希）| "phash"（基于 DCT，对亮度、压缩变化更稳定，较慢）
+ * 不同算法的哈希不能互相比较
+ */
+#[tauri::command]
+pub async fn compute_image_perceptual_hash(
+    input: Vec<u8>,
+    algorithm: Option<String>,
+) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || {
+        let hash = hash_algorithm(algorithm.as_deref())?;
+        let img = image::load_from_memory(&input).map_err(|e| format!("decode error: {}", e))?;
+        Ok(format!("{:016x}", hash(&img)))
+    })
+    .await
+    .map_err(|e| format!("hash task error: {}", e))?
+}
+
+/**
+ * 两个感知哈希的汉明距离（0-64），越小越相似；dHash 一般 10 以内可视为同一张图
+ */
+#[tauri::command]
+pub fn image_hash_distance(hash_a: String, hash_b: String) -> Result<u32, String> {
+    Ok((parse_hash(&hash_a)? ^ parse_hash(&hash_b)?).count_ones())
+}
+
+/// probe_image 最多读取的字节数：足以覆盖常见格式的头部（JPEG 的尺寸可能位于较大的 EXIF 之后）
+const PROBE_BYTES: usize = 256 * 1024;
+
+/// probe_image 的结果
+#[derive(Serialize, Clone, Debug)]
+pub struct ImageProbe {
+    pub width: u32,
+    pub height: u32,
+    /// "png" | "jpeg" | "gif" | "webp" | "bmp" | ...
+    pub format: String,
+    /// 文件大小；远程图片的服务器没有返回长度时为 None
+    pub bytes: Option<u64>,
+    pub is_animated: bool,
+}
+
+/// 根据文件头判断是否为动图：GIF 看 NETSCAPE2.0 循环扩展，WebP 看 VP8X 头的动画标记位
+fn is_animated(format: ImageFormat, header: &[u8]) -> bool {
+    match format {
+        ImageFormat::Gif => header.windows(11).any(|w| w == b"NETSCAPE2.0"),
+        ImageFormat::WebP => {
+            header.get(12..16) == Some(&b"VP8X"[..])
+                && header.get(20).is_some_and(|f| f & 0x02 != 0)
+        }
+        _ => false,
+    }
+}
+
+/// 从图片开头的字节解析格式和尺寸（动图为画布尺寸，即第一帧的显示尺寸）
+pub fn probe_header(header: &[u8]) -> Result<(u32, u32, ImageFormat), CommandError> {
+    let reader = image::io::Reader::new(Cursor::new(header)).with_guessed_format()?;
+    let format = reader
+        .format()
+        .ok_or_else(|| CommandError::Decode("unknown image format".into()))?;
+    let (width, height) = reader
+        .into_dimensions()
+        .map_err(|e| CommandError::Decode(format!("corrupt image header: {}", e)))?;
+    Ok((width, height, format))
+}
+
+/// 读取远程图片开头的 PROBE_BYTES 字节（优先用 Range 请求），返回数据和图片总大小
+async fn fetch_header(
+    client: &reqwest::Client,
+    url: &str,
+) -> Result<(Vec<u8>, Option<u64>), CommandError> {
+    let mut resp = client
+        .get(url)
+        .header(
+            reqwest::header::RANGE,
+            format!("bytes=0-{}", PROBE_BYTES - 1),
+        )
+        .send()
+        .await?
+        .error_for_status()?;
+    let total = match resp.status() {
+        // Content-Range: bytes 0-262143/1048576
+        reqwest::StatusCode::PARTIAL_CONTENT => resp
+            .headers()
+            .get(reqwest::header::CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.rsplit('/').next())
+            .and_then(|v| v.parse().ok()),
+        // 服务器不支持 Range 时读够头部就断开，不下载整张图
+        _ => resp.content_length(),
+    };
+    let mut header = Vec::new();
+    while header.len() < PROBE_BYTES {
+        match resp.chunk().await? {
+            Some(chunk) => header.extend_from_slice(&chunk),
+            None => break,
+        }
+    }
+    header.truncate(PROBE_BYTES);
+    Ok((header, total))
+}
+
+/**
+ * 读取图片的格式和尺寸而不解码像素（用于在加载前排版占位）
+ * source: 本地文件路径或 http(s) URL；只读取开头最多 256 KB，远程图片优先用 Range 请求
+ * 动图（GIF / WebP）返回画布尺寸，is_animated 为 true
+ * 无法识别的格式或损坏的文件头返回 kind 为 "decode" 的错误；
+ * 其他错误同 url_to_rgba（network / io / notFound）
+ */
+#[tauri::command]
+pub async fn probe_image(
+    state: State<'_, AppState>,
+    source: String,
+) -> Result<ImageProbe, CommandError> {
+    let (header, bytes) = if source.starts_with("http://") || source.starts_with("https://") {
+        fetch_header(&state.http, &source).await?
+    } else {
+        let file = File::open(&source)?;
+        let bytes = file.metadata()?.len();
+        let mut header = Vec::new();
+        file.take(PROBE_BYTES as u64).read_to_end(&mut header)?;
+        (header, Some(bytes))
+    };
+    let (width, height, format) = probe_header(&header)?;
+    Ok(ImageProbe {
+        width,
+        height,
+        format: format!("{:?}", format).to_lowercase(),
+        bytes,
+        is_animated: is_animated(format, &header),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(width: u32, height: u32, color: [u8; 4]) -> RgbaImage {
+        RgbaImage::from_pixel(width, height, Rgba(color))
+    }
+
+    /// 左暗右亮的水平渐变
+    fn gradient(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |x, _| {
+            let v = (x * 255 / (width - 1)) as u8;
+            Rgba([v, v, v, 255])
+        }))
+    }
+
+    #[test]
+    fn invert_and_grayscale_filters() {
+        let img = solid(2, 2, [10, 200, 30, 128]);
+        let inverted = apply_filter(img.clone(), "invert", None).unwrap();
+        assert_eq!(inverted.get_pixel(0, 0).0, [245, 55, 225, 128]);
+
+        let gray = apply_filter(img, "grayscale", None).unwrap();
+        let [r, g, b, a] = gray.get_pixel(1, 1).0;
+        assert!(r == g && g == b);
+        assert_eq!(a, 128);
+    }
+
+    #[test]
+    fn filter_params_are_validated() {
+        let img = solid(1, 1, [0, 0, 0, 255]);
+        assert!(apply_filter(img.clone(), "emboss", None).is_err());
+        assert!(apply_filter(img.clone(), "brightness", None).is_err());
+        let params = serde_json::json!({ "sigma": "wide" });
+        assert!(apply_filter(img.clone(), "blur", Some(&params)).is_err());
+
+        let params = serde_json::json!({ "delta": 20 });
+        let brighter = apply_filter(img, "brightness", Some(&params)).unwrap();
+        assert_eq!(brighter.get_pixel(0, 0).0, [20, 20, 20, 255]);
+    }
+
+    #[test]
+    fn kmeans_separates_color_clusters() {
+        let mut pixels = vec![[250, 10, 10]; 30];
+        pixels.extend(vec![[10, 10, 250]; 10]);
+        let mut clusters = kmeans(&pixels, 2);
+        clusters.sort_by_key(|&(_, n)| std::cmp::Reverse(n));
+        assert_eq!(clusters, vec![([250, 10, 10], 30), ([10, 10, 250], 10)]);
+        // 颜色种类少于 k 时不会产生重复的中心
+        assert_eq!(kmeans(&pixels[..30], 4), vec![([250, 10, 10], 30)]);
+    }
+
+    #[test]
+    fn perceptual_hashes_track_similarity() {
+        let img = gradient(64, 64);
+        let mut inverted = img.clone();
+        inverted.invert();
+        for algorithm in ["dhash", "phash"] {
+            let hash = hash_algorithm(Some(algorithm)).unwrap();
+            let resized = img.resize_exact(48, 48, FilterType::Triangle);
+            assert!((hash(&img) ^ hash(&resized)).count_ones() <= 8);
+            assert!((hash(&img) ^ hash(&inverted)).count_ones() > 16);
+        }
+        assert!(hash_algorithm(Some("ahash")).is_err());
+    }
+
+    #[test]
+    fn parse_hash_round_trips() {
+        let value = 0x0123_4567_89ab_cdefu64;
+        assert_eq!(parse_hash(&format!("{:016x}", value)), Ok(value));
+        assert!(parse_hash("1234").is_err());
+        assert!(parse_hash("zzzzzzzzzzzzzzzz").is_err());
+    }
+}
@@ -0,0 +1,589 @@
+use crate::AppState;
+use crate::events;
+use enigo::{Enigo, Key, KeyboardControllable, MouseButton, MouseControllable};
+use schemars::JsonSchema;
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+/*
+ * 线程约束：
+ * Enigo 持有平台相关的句柄（X11 display / CGEventSource 等），不能安全地跨线程共享，
+ * 因此不放进 AppState，也不与 mouse_poller 线程共用。每个命令（或回放线程）
+ * 在自己的线程内通过 make_enigo 新建一个实例，用完即丢弃。
+ *
+ * 安全提示：
+ * 本模块中的 move / click / simulate_* 命令会真实操作用户桌面（移动光标、点击、输入），
+ * 前端只应在用户明确授权的自动化场景中调用。
+ */
+
+/// 创建 Enigo 实例；无图形环境（如 CI）时返回 Err 而不是 panic
+/// enigo 0.0.x 没有 Settings 配置项，使用默认构造
+pub fn make_enigo() -> Result<Enigo, String> {
+    #[cfg(target_os = "linux")]
+    if std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none() {
+        return Err("no display available for input simulation".into());
+    }
+    std::panic::catch_unwind(Enigo::new)
+        .map_err(|_| "failed to initialise input controller".to_string())
+}
+
+/// 录制的鼠标轨迹点：(x, y, 距离开始录制的毫秒数)
+pub type MousePathPoint = (i32, i32, u64);
+
+static RECORDING_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// 一次鼠标轨迹录制；采样线程按 id 识别自己的录制，
+/// stop 后立即 start 时旧线程不会写入新录制的缓冲
+pub struct MouseRecording {
+    id: u64,
+    path: Vec<MousePathPoint>,
+    limit: usize,
+    /// 达到 max_events 后停止采样，轨迹保留到 stop_mouse_recording 取走
+    finished: bool,
+}
+
+impl MouseRecording {
+    /// 记录一个点（与上一个点位置相同时跳过），返回是否还需继续采样
+    fn record(&mut self, point: MousePathPoint) -> bool {
+        if self.finished {
+            return false;
+        }
+        let moved = self
+            .path
+            .last()
+            .is_none_or(|&(x, y, _)| (x, y) != (point.0, point.1));
+        if moved && self.path.len() < self.limit {
+            self.path.push(point);
+        }
+        self.finished = self.path.len() >= self.limit;
+        !self.finished
+    }
+}
+
+/// 回放进度事件负载
+#[derive(Serialize, Clone, JsonSchema)]
+pub struct ReplayProgress {
+    pub index: usize,
+    pub total: usize,
+    pub percent: f32,
+}
+
+/**
+ * 开始录制鼠标轨迹
+ * max_events: 最多记录的点数，达到后自动停止采样（默认不限制）；
+ * 停止采样后已记录的轨迹仍需 stop_mouse_recording 取走，此时可以开始新的录制
+ *
+ * 采样线程每 10ms 读取一次鼠标位置，只在位置变化时记录
+ */
+#[tauri::command]
+pub fn start_mouse_recording(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    max_events: Option<usize>,
+) -> Result<(), String> {
+    let mut guard = state
+        .mouse_recording
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?;
+    if guard.as_ref().is_some_and(|rec| !rec.finished) {
+        return Err("recording already in progress".into());
+    }
+    let id = RECORDING_SEQ.fetch_add(1, Ordering::Relaxed);
+    let limit = max_events.unwrap_or(usize::MAX);
+    *guard = Some(MouseRecording {
+        id,
+        path: Vec::new(),
+        limit,
+        finished: limit == 0,
+    });
+    drop(guard);
+
+    thread::spawn(move || {
+        let state = app.state::<AppState>();
+        let started = Instant::now();
+
+        loop {
+            let (x, y) = Enigo::mouse_location();
+            let point = (x as i32, y as i32, started.elapsed().as_millis() as u64);
+            {
+                let Ok(mut guard) = state.mouse_recording.lock() else {
+                    break;
+                };
+                // 已被 stop_mouse_recording 取走，或已开始了新的录制
+                let Some(rec) = guard.as_mut().filter(|rec| rec.id == id) else {
+                    break;
+                };
+                if !rec.record(point) {
+                    break;
+                }
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        println!("[mouse_recording] sampler {} exiting", id);
+    });
+
+    Ok(())
+}
+
+/**
+ * 停止录制并返回轨迹（包括因 max_events 已自动停止的录制）
+ */
+#[tauri::command]
+pub fn stop_mouse_recording(state: State<'_, AppState>) -> Result<Vec<MousePathPoint>, String> {
+    state
+        .mouse_recording
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?
+        .take()
+        .map(|rec| rec.path)
+        .ok_or_else(|| "not recording".to_string())
+}
+
+/**
+ * 回放鼠标轨迹
+ * speed_factor: 回放速度倍率，2.0 表示两倍速
+ *
+ * 在独立线程中回放，通过 "mouse:replay-progress" 事件上报进度
+ */
+#[tauri::command]
+pub fn replay_mouse_path(
+    app: AppHandle,
+    path: Vec<MousePathPoint>,
+    speed_factor: f32,
+) -> Result<(), String> {
+    if !speed_factor.is_finite() || speed_factor <= 0.0 {
+        return Err("speed_factor must be positive".into());
+    }
+    if path.is_empty() {
+        return Ok(());
+    }
+
+    // 先在当前线程探测一次，失败时直接返回错误
+    make_enigo()?;
+
+    thread::spawn(move || {
+        // Enigo 实例只在回放线程内创建和使用
+        let Ok(mut enigo) = make_enigo() else {
+            return;
+        };
+        let total = path.len();
+        let mut prev_ms = path[0].2;
+
+        for (index, &(x, y, ms)) in path.iter().enumerate() {
+            let wait = ms.saturating_sub(prev_ms) as f32 / speed_factor;
+            if wait > 0.0 {
+                thread::sleep(Duration::from_secs_f32(wait / 1000.0));
+            }
+            prev_ms = ms;
+
+            enigo.mouse_move_to(x, y);
+
+            let payload = ReplayProgress {
+                index,
+                total,
+                percent: (index + 1) as f32 * 100.0 / total as f32,
+            };
+            if let Err(e) = events::emit_event(&app, events::Event::MouseReplayProgress(payload)) {
+                eprintln!("[mouse_replay] emit error: {:?}", e);
+            }
+        }
+        println!("[mouse_replay] finished {} points", total);
+    });
+
+    Ok(())
+}
+
+/// 解析鼠标按键名称："left" / "right" / "middle"
+fn parse_button(button: &str) -> Result<MouseButton, String> {
+    match button.to_ascii_lowercase().as_str() {
+        "left" => Ok(MouseButton::Left),
+        "right" => Ok(MouseButton::Right),
+        "middle" => Ok(MouseButton::Middle),
+        other => Err(format!("unknown mouse button: {}", other)),
+    }
+}
+
+/**
+ * 移动鼠标
+ * absolute: true 移动到屏幕绝对坐标，false 相对当前位置偏移
+ */
+#[tauri::command]
+pub fn move_mouse(x: i32, y: i32, absolute: bool) -> Result<(), String> {
+    let mut enigo = make_enigo()?;
+    if absolute {
+        enigo.mouse_move_to(x, y);
+    } else {
+        enigo.mouse_move_relative(x, y);
+    }
+    Ok(())
+}
+
+/**
+ * 在当前位置点击鼠标
+ * button: "left" / "right" / "middle"
+ * count: 连击次数（2 即双击）
+ */
+#[tauri::command]
+pub fn click_mouse(button: String, count: u32) -> Result<(), String> {
+    let button = parse_button(&button)?;
+    let mut enigo = make_enigo()?;
+    for _ in 0..count {
+        enigo.mouse_click(button);
+    }
+    Ok(())
+}
+
+/**
+ * 滚动鼠标滚轮
+ * dx: 水平滚动格数，dy: 垂直滚动格数（正数向下/向右）
+ */
+#[tauri::command]
+pub fn scroll_mouse(dx: i32, dy: i32) -> Result<(), String> {
+    let mut enigo = make_enigo()?;
+    if dx != 0 {
+        enigo.mouse_scroll_x(dx);
+    }
+    if dy != 0 {
+        enigo.mouse_scroll_y(dy);
+    }
+    Ok(())
+}
+
+/**
+ * 模拟鼠标移动到绝对坐标
+ * smooth: 为 true 时按步插值移动（每步间隔 5ms），更接近真人操作
+ */
+#[tauri::command]
+pub fn simulate_mouse_move(x: i32, y: i32, smooth: bool) -> Result<(), String> {
+    let mut enigo = make_enigo()?;
+    if smooth {
+        let (sx, sy) = Enigo::mouse_location();
+        let (sx, sy) = (sx as i32, sy as i32);
+        // 约每 10 像素一步，最多 100 步
+        let steps = ((x - sx).abs().max((y - sy).abs()) / 10).clamp(1, 100);
+        for i in 1..=steps {
+            let px = sx + (x - sx) * i / steps;
+            let py = sy + (y - sy) * i / steps;
+            enigo.mouse_move_to(px, py);
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+    enigo.mouse_move_to(x, y);
+    Ok(())
+}
+
+/**
+ * 模拟鼠标点击
+ * button: "left" / "right" / "middle"
+ * x, y: 可选，先移动到该位置再点击
+ * double: 是否双击
+ */
+#[tauri::command]
+pub fn simulate_mouse_click(
+    button: String,
+    x: Option<i32>,
+    y: Option<i32>,
+    double: bool,
+) -> Result<(), String> {
+    let button = parse_button(&button)?;
+    let mut enigo = make_enigo()?;
+    if let (Some(x), Some(y)) = (x, y) {
+        enigo.mouse_move_to(x, y);
+    }
+    enigo.mouse_click(button);
+    if double {
+        enigo.mouse_click(button);
+    }
+    Ok(())
+}
+
+/// 解析按键名称，如 "a"、"Return"、"Escape"、"F5"
+fn parse_key(key: &str) -> Result<Key, String> {
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(Key::Layout(c));
+    }
+    let k = match key.to_ascii_lowercase().as_str() {
+        "return" | "enter" => Key::Return,
+        "escape" | "esc" => Key::Escape,
+        "tab" => Key::Tab,
+        "space" => Key::Space,
+        "backspace" => Key::Backspace,
+        "delete" | "del" => Key::Delete,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "up" | "uparrow" => Key::UpArrow,
+        "down" | "downarrow" => Key::DownArrow,
+        "left" | "leftarrow" => Key::LeftArrow,
+        "right" | "rightarrow" => Key::RightArrow,
+        "capslock" => Key::CapsLock,
+        "f1" => Key::F1,
+        "f2" => Key::F2,
+        "f3" => Key::F3,
+        "f4" => Key::F4,
+        "f5" => Key::F5,
+        "f6" => Key::F6,
+        "f7" => Key::F7,
+        "f8" => Key::F8,
+        "f9" => Key::F9,
+        "f10" => Key::F10,
+        "f11" => Key::F11,
+        "f12" => Key::F12,
+        other => return Err(format!("unknown key: {}", other)),
+    };
+    Ok(k)
+}
+
+/// 解析修饰键："ctrl" / "shift" / "alt" / "meta"
+fn parse_modifier(modifier: &str) -> Result<Key, String> {
+    match modifier.to_ascii_lowercase().as_str() {
+        "ctrl" | "control" => Ok(Key::Control),
+        "shift" => Ok(Key::Shift),
+        "alt" | "option" => Ok(Key::Alt),
+        "meta" | "cmd" | "command" | "super" | "win" => Ok(Key::Meta),
+        other => Err(format!("unknown modifier: {}", other)),
+    }
+}
+
+/**
+ * 模拟按键（可带修饰键组合，如 ctrl+shift+a）
+ * 修饰键按顺序按下，主键点击后逆序释放
+ */
+#[tauri::command]
+pub fn simulate_key_press(key: String, modifiers: Vec<String>) -> Result<(), String> {
+    let key = parse_key(&key)?;
+    let modifiers = modifiers
+        .iter()
+        .map(|m| parse_modifier(m))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut enigo = make_enigo()?;
+    for m in &modifiers {
+        enigo.key_down(*m);
+    }
+    enigo.key_click(key);
+    for m in modifiers.iter().rev() {
+        enigo.key_up(*m);
+    }
+    Ok(())
+}
+
+/**
+ * 逐字符输入文本
+ * delay_ms: 每个字符之间的间隔，避免目标程序丢键（默认 0）
+ */
+#[tauri::command]
+pub fn simulate_key_sequence(text: String, delay_ms: Option<u64>) -> Result<(), String> {
+    let mut enigo = make_enigo()?;
+    let delay = Duration::from_millis(delay_ms.unwrap_or(0));
+    let mut buf = [0u8; 4];
+    for c in text.chars() {
+        enigo.key_sequence(c.encode_utf8(&mut buf));
+        if !delay.is_zero() {
+            thread::sleep(delay);
+        }
+    }
+    Ok(())
+}
+
+/// 修饰键状态
+#[derive(Serialize, Default, Debug)]
+pub struct ModifierState {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub meta: bool,
+    pub caps_lock: bool,
+    pub num_lock: bool,
+    /// 平台 API 不可用时说明原因（此时其余字段均为 false）
+    pub warning: Option<String>,
+}
+
+/**
+ * 获取当前修饰键（Ctrl/Shift/Alt/Meta）及 CapsLock/NumLock 状态
+ * 只读查询，不需要 Enigo 实例；平台 API 不可用时返回全 false 并附带 warning
+ */
+#[tauri::command]
+pub fn get_keyboard_modifier_state() -> Result<ModifierState, String> {
+    Ok(modifier_state::query())
+}
+
+#[cfg(target_os = "windows")]
+mod modifier_state {
+    use super::ModifierState;
+
+    #[link(name = "user32")]
+    unsafe extern "system" {
+        fn GetAsyncKeyState(vkey: i32) -> i16;
+        fn GetKeyState(vkey: i32) -> i16;
+    }
+
+    const VK_SHIFT: i32 = 0x10;
+    const VK_CONTROL: i32 = 0x11;
+    const VK_MENU: i32 = 0x12;
+    const VK_CAPITAL: i32 = 0x14;
+    const VK_LWIN: i32 = 0x5B;
+    const VK_RWIN: i32 = 0x5C;
+    const VK_NUMLOCK: i32 = 0x90;
+
+    fn down(vkey: i32) -> bool {
+        // 最高位表示当前按下
+        unsafe { GetAsyncKeyState(vkey) as u16 & 0x8000 != 0 }
+    }
+
+    fn toggled(vkey: i32) -> bool {
+        unsafe { GetKeyState(vkey) & 1 != 0 }
+    }
+
+    pub fn query() -> ModifierState {
+        ModifierState {
+            ctrl: down(VK_CONTROL),
+            shift: down(VK_SHIFT),
+            alt: down(VK_MENU),
+            meta: down(VK_LWIN) || down(VK_RWIN),
+            caps_lock: toggled(VK_CAPITAL),
+            num_lock: toggled(VK_NUMLOCK),
+            warning: None,
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod modifier_state {
+    use super::ModifierState;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    unsafe extern "C" {
+        fn CGEventSourceFlagsState(state_id: i32) -> u64;
+    }
+
+    const COMBINED_SESSION_STATE: i32 = 0;
+    const FLAG_ALPHA_SHIFT: u64 = 0x0001_0000;
+    const FLAG_SHIFT: u64 = 0x0002_0000;
+    const FLAG_CONTROL: u64 = 0x0004_0000;
+    const FLAG_ALTERNATE: u64 = 0x0008_0000;
+    const FLAG_COMMAND: u64 = 0x0010_0000;
+
+    pub fn query() -> ModifierState {
+        let flags = unsafe { CGEventSourceFlagsState(COMBINED_SESSION_STATE) };
+        ModifierState {
+            ctrl: flags & FLAG_CONTROL != 0,
+            shift: flags & FLAG_SHIFT != 0,
+            alt: flags & FLAG_ALTERNATE != 0,
+            meta: flags & FLAG_COMMAND != 0,
+            caps_lock: flags & FLAG_ALPHA_SHIFT != 0,
+            // macOS 没有 NumLock
+            num_lock: false,
+            warning: None,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod modifier_state {
+    use super::ModifierState;
+    use std::os::raw::{c_char, c_int, c_uint, c_ulong, c_void};
+
+    #[link(name = "X11")]
+    unsafe extern "C" {
+        fn XOpenDisplay(name: *const c_char) -> *mut c_void;
+        fn XCloseDisplay(display: *mut c_void) -> c_int;
+        fn XQueryKeymap(display: *mut c_void, keys: *mut c_char) -> c_int;
+        fn XKeysymToKeycode(display: *mut c_void, keysym: c_ulong) -> u8;
+        fn XkbGetIndicatorState(display: *mut c_void, device: c_uint, state: *mut c_uint) -> c_int;
+    }
+
+    const XKB_USE_CORE_KBD: c_uint = 0x0100;
+    const XK_SHIFT_L: c_ulong = 0xffe1;
+    const XK_SHIFT_R: c_ulong = 0xffe2;
+    const XK_CONTROL_L: c_ulong = 0xffe3;
+    const XK_CONTROL_R: c_ulong = 0xffe4;
+    const XK_META_L: c_ulong = 0xffe7;
+    const XK_ALT_L: c_ulong = 0xffe9;
+    const XK_ALT_R: c_ulong = 0xffea;
+    const XK_SUPER_L: c_ulong = 0xffeb;
+    const XK_SUPER_R: c_ulong = 0xffec;
+
+    pub fn query() -> ModifierState {
+        if std::env::var_os("DISPLAY").is_none() {
+            return ModifierState {
+                warning: Some("no X11 display available (Wayland without XWayland?)".into()),
+                ..Default::default()
+            };
+        }
+
+        unsafe {
+            let display = XOpenDisplay(std::ptr::null());
+            if display.is_null() {
+                return ModifierState {
+                    warning: Some("failed to open X11 display".into()),
+                    ..Default::default()
+                };
+            }
+
+            let mut keys = [0 as c_char; 32];
+            XQueryKeymap(display, keys.as_mut_ptr());
+            let down = |keysym: c_ulong| {
+                let code = XKeysymToKeycode(display, keysym) as usize;
+                code != 0 && (keys[code / 8] as u8 >> (code % 8)) & 1 != 0
+            };
+
+            let mut indicators: c_uint = 0;
+            XkbGetIndicatorState(display, XKB_USE_CORE_KBD, &mut indicators);
+
+            let state = ModifierState {
+                ctrl: down(XK_CONTROL_L) || down(XK_CONTROL_R),
+                shift: down(XK_SHIFT_L) || down(XK_SHIFT_R),
+                alt: down(XK_ALT_L) || down(XK_ALT_R),
+                meta: down(XK_SUPER_L) || down(XK_SUPER_R) || down(XK_META_L),
+                caps_lock: indicators & 0x1 != 0,
+                num_lock: indicators & 0x2 != 0,
+                warning: None,
+            };
+            XCloseDisplay(display);
+            state
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+mod modifier_state {
+    use super::ModifierState;
+
+    pub fn query() -> ModifierState {
+        ModifierState {
+            warning: Some("modifier state is not supported on this platform".into()),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recording_finishes_at_max_events() {
+        let mut rec = MouseRecording {
+            id: 0,
+            path: Vec::new(),
+            limit: 2,
+            finished: false,
+        };
+        assert!(rec.record((0, 0, 0)));
+        // 位置未变化不记录
+        assert!(rec.record((0, 0, 10)));
+        assert!(!rec.record((5, 5, 20)));
+        assert!(rec.finished);
+        assert!(!rec.record((9, 9, 30)));
+        assert_eq!(rec.path, vec![(0, 0, 0), (5, 5, 20)]);
+    }
+}
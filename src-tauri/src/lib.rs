@@ -1,18 +1,102 @@
+mod cache;
+mod capture_backend;
+mod capture_store;
+mod clipboard_monitor;
 mod commands;
+mod cursor;
+mod delayed;
+mod dict;
+mod display;
 mod disk;
+mod ephemeral;
+mod error;
+mod events;
+mod fs_watch;
+mod history;
+mod hotkey;
+mod imaging;
+mod input;
+mod mouse_ws;
+mod ocr;
+mod own_windows;
+mod pin;
+mod print;
+mod screen_stream;
+mod secrets;
+mod startup;
+mod sync;
+mod text;
+mod text_util;
+mod undo;
 mod upload;
+mod window_list;
 use jieba_rs::Jieba;
 use tauri::Manager;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::RwLock;
 use std::{
     sync::atomic::AtomicBool,
     sync::{Arc, Mutex},
     thread::JoinHandle,
+    time::Instant,
 };
+use tauri_plugin_log::{Target, TargetKind};
 
 struct AppState {
     jieba: RwLock<Jieba>,
-    mouse_poller: Mutex<Option<(Arc<AtomicBool>, JoinHandle<()>)>>,
+    // 运行时添加的用户词条（按词排序，便于导出）
+    user_words: Mutex<BTreeMap<String, dict::UserWord>>,
+    // 运行时加载过的词典文件，重建词典时重新加载
+    loaded_dicts: Mutex<Vec<PathBuf>>,
+    // 串行化词典的修改（新词典在 jieba 锁外构建，构建期间不阻塞分词）
+    dict_update: Mutex<()>,
+    // 本次启动后通过 add_jieba_word 添加的词
+    custom_words: RwLock<HashSet<String>>,
+    // 自定义词典文件（setup 时初始化为 app_local_data_dir/custom_jieba.txt）
+    custom_dict_path: RwLock<Option<PathBuf>>,
+    mouse_poller: Mutex<Option<commands::MousePoller>>,
+    // 鼠标位置 WebSocket 服务（None 表示未启动）
+    mouse_ws: Mutex<Option<mouse_ws::MouseWebSocket>>,
+    // 临时模式沙箱目录（None 表示正常模式）
+    ephemeral_dir: RwLock<Option<PathBuf>>,
+    ephemeral_deadline: Mutex<Option<Instant>>,
+    // 鼠标轨迹录制缓冲（None 表示未在录制）
+    mouse_recording: Mutex<Option<input::MouseRecording>>,
+    // 串行化同步状态文件的读写
+    sync_lock: Mutex<()>,
+    // 串行化图片缓存清单的读写
+    cache_lock: Mutex<()>,
+    // 进行中的文件夹扫描取消标记（scan_id -> flag）
+    folder_scans: Mutex<HashMap<String, Arc<AtomicBool>>>,
+    // 进行中的下载（download_id -> 取消标记）
+    downloads: Mutex<HashMap<String, Arc<AtomicBool>>>,
+    // 可撤销操作的暂存记录（undo_token -> entry）
+    undo_journal: Mutex<HashMap<String, undo::UndoEntry>>,
+    // 剪贴板变化监听线程
+    clipboard_monitor: Mutex<Option<(Arc<AtomicBool>, JoinHandle<()>)>>,
+    // 显示器休眠监听线程
+    display_watch: Mutex<Option<(Arc<AtomicBool>, JoinHandle<()>)>>,
+    // 显示器热插拔/分辨率变化监听线程（setup 时启动）
+    screens_watch: Mutex<Option<(Arc<AtomicBool>, JoinHandle<()>)>>,
+    // 显示器是否休眠（后台截图任务据此暂停）
+    display_asleep: Arc<AtomicBool>,
+    // 截图历史配置（同时串行化历史索引的读写）
+    capture_history: Mutex<history::HistoryConfig>,
+    // 进行中的屏幕画面流（stream_id -> stream）
+    screen_streams: Mutex<HashMap<String, screen_stream::ScreenStream>>,
+    // 进行中的延时截图取消标记
+    delayed_capture: Mutex<Option<Arc<AtomicBool>>>,
+    // keep_capture 保留的截图像素（capture_id -> 像素）
+    kept_captures: Mutex<capture_store::KeptCaptures>,
+    // 打开中的钉图窗口及其图片
+    pins: Mutex<pin::Pins>,
+    // 目录监听（watch_id -> watcher），移除即停止监听
+    fs_watchers: Mutex<HashMap<String, notify::RecommendedWatcher>>,
+    // 共享的 HTTP 客户端（复用连接池）
+    http: tauri_plugin_http::reqwest::Client,
+    // 已解锁的密钥存储（None 表示尚未调用 unlock_secret_store）
+    secrets: Mutex<Option<tauri_plugin_stronghold::stronghold::Stronghold>>,
 }
 
 // Learn more about Tauri commands at https://tauri.app/v1/guides/features/command
@@ -23,21 +107,89 @@ fn greet(name: &str) -> String {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // 清理上次非正常退出遗留的临时沙箱
+    ephemeral::purge_stale_sandboxes();
+    let sandbox = if ephemeral::flag_from_args() {
+        Some(ephemeral::create_sandbox().expect("could not create ephemeral sandbox"))
+    } else {
+        None
+    };
+
+    // 临时模式下日志只写到沙箱
+    let log_plugin = match &sandbox {
+        Some(dir) => tauri_plugin_log::Builder::new()
+            .clear_targets()
+            .target(Target::new(TargetKind::Stdout))
+            .target(Target::new(TargetKind::Folder {
+                path: dir.join("logs"),
+                file_name: None,
+            }))
+            .build(),
+        None => tauri_plugin_log::Builder::new().build(),
+    };
+    let stronghold_dir = sandbox.clone();
+
+    let mut context = tauri::generate_context!();
+    let identifier = context.config().identifier.clone();
+    if sandbox.is_some() {
+        // 插件（SQLite、store、stronghold vault、webview 数据）按 identifier 解析应用目录
+        context.config_mut().identifier = ephemeral::sandbox_identifier(&identifier);
+    }
+
     let state = AppState {
         jieba: RwLock::new(Jieba::new()),
+        user_words: Mutex::new(BTreeMap::new()),
+        loaded_dicts: Mutex::new(Vec::new()),
+        dict_update: Mutex::new(()),
+        custom_words: RwLock::new(HashSet::new()),
+        custom_dict_path: RwLock::new(None),
         mouse_poller: Mutex::new(None),
+        mouse_ws: Mutex::new(None),
+        ephemeral_dir: RwLock::new(sandbox),
+        ephemeral_deadline: Mutex::new(None),
+        mouse_recording: Mutex::new(None),
+        sync_lock: Mutex::new(()),
+        cache_lock: Mutex::new(()),
+        folder_scans: Mutex::new(HashMap::new()),
+        downloads: Mutex::new(HashMap::new()),
+        undo_journal: Mutex::new(HashMap::new()),
+        clipboard_monitor: Mutex::new(None),
+        display_watch: Mutex::new(None),
+        screens_watch: Mutex::new(None),
+        display_asleep: Arc::new(AtomicBool::new(false)),
+        capture_history: Mutex::new(history::HistoryConfig::default()),
+        screen_streams: Mutex::new(HashMap::new()),
+        delayed_capture: Mutex::new(None),
+        kept_captures: Mutex::new(capture_store::KeptCaptures::default()),
+        pins: Mutex::new(pin::Pins::default()),
+        fs_watchers: Mutex::new(HashMap::new()),
+        http: upload::http_client(),
+        secrets: Mutex::new(None),
     };
-    tauri::Builder::default().setup(|app| { 
-         let salt_path = app
-            .path()
-            .app_local_data_dir()
-            .expect("could not resolve app local data path")
-            .join("salt.txt");
+    tauri::Builder::default().setup(move |app| { 
+         // 临时模式下 salt 放在沙箱中，vault 随沙箱一起销毁
+         let salt_path = match &stronghold_dir {
+            Some(dir) => dir.join("salt.txt"),
+            None => app
+                .path()
+                .app_local_data_dir()
+                .expect("could not resolve app local data path")
+                .join("salt.txt"),
+         };
         app.handle().plugin(tauri_plugin_stronghold::Builder::with_argon2(&salt_path).build())?;
+        // 清理上次非正常退出遗留的临时模式应用目录
+        ephemeral::purge_stale_app_dirs(app.handle(), &identifier);
+        // 恢复上次保存的用户词典
+        dict::load_persisted(app.handle());
+        // 清理上次非正常退出遗留的撤销暂存
+        undo::purge_all(app.handle());
+        // 监听显示器热插拔，发送 "displays:changed"
+        display::start_screens_watch(app.handle());
         Ok(())
         })
         .plugin(tauri_plugin_positioner::init())
         .manage(state)
+        .register_uri_scheme_protocol(pin::PIN_SCHEME, pin::protocol)
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_clipboard_manager::init())
@@ -47,12 +199,12 @@ pub fn run() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_sql::Builder::default().build())
-        .plugin(tauri_plugin_log::Builder::new().build())
+        .plugin(log_plugin)
         .plugin(tauri_plugin_upload::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
-        //.plugin(tauri_plugin_websokcet::init())
+        .plugin(tauri_plugin_websocket::init())
         .invoke_handler(tauri::generate_handler![
             greet,
             commands::get_mouse_position,
@@ -60,19 +212,169 @@ pub fn run() {
             commands::get_display_info,
             commands::get_all_screens,
             commands::capture_all_screens,
+            commands::capture_all_screens_stream,
+            commands::capture_virtual_desktop,
             commands::capture_screen_by_id,
             commands::capture_screen_at_point,
             commands::capture_area,
+            commands::capture_area_logical,
+            commands::capture_screen_raw,
+            commands::capture_area_raw,
+            capture_store::crop_capture,
+            capture_store::release_capture,
+            capture_store::compare_captures,
+            capture_store::capture_and_compare,
+            pin::pin_capture,
+            pin::close_pin,
+            pin::list_pins,
+            commands::capture_area_to_clipboard,
+            commands::capture_window,
+            commands::get_pixel_color,
+            commands::get_pixel_colors,
+            capture_backend::get_capture_backend_info,
+            screen_stream::start_screen_stream,
+            screen_stream::ack_screen_frame,
+            screen_stream::stop_screen_stream,
+            delayed::capture_after_delay,
+            delayed::cancel_delayed_capture,
+            history::configure_capture_history,
+            history::list_capture_history,
+            history::get_capture_file,
+            history::delete_capture,
+            window_list::get_visible_window_rects,
+            commands::capture_screen_to_file,
             commands::segment_text,
+            commands::segment_text_search,
             commands::batch_segment_text,
+            text_util::get_default_stop_words,
+            commands::segment_text_stream,
             commands::cache_image_to_path,
+            commands::cache_image_thumbnail,
+            commands::clear_image_cache,
+            cache::verify_image_cache,
+            cache::cache_usage_by_tag,
+            cache::clear_cache_by_tag,
+            cache::find_similar_cached_images,
+            undo::undo_operation,
             commands::url_to_rgba,
             commands::clipboard_image,
+            commands::read_clipboard_image,
+            commands::write_clipboard_text,
+            commands::read_clipboard_text,
+            commands::clear_clipboard,
+            commands::write_clipboard_html,
+            commands::read_clipboard_html,
+            commands::read_clipboard_files,
+            commands::write_clipboard_files,
+            clipboard_monitor::start_clipboard_monitor,
+            clipboard_monitor::stop_clipboard_monitor,
             commands::control_mouse_poller,
+            commands::mouse_poller_status,
+            mouse_ws::start_mouse_websocket,
+            mouse_ws::stop_mouse_websocket,
+            commands::get_system_info,
+            ephemeral::set_ephemeral_time_limit,
+            events::get_event_schemas,
+            startup::get_startup_snapshot,
+            hotkey::register_capture_hotkey,
+            hotkey::unregister_capture_hotkey,
+            imaging::image_histogram,
+            imaging::auto_enhance_image,
+            imaging::resize_image,
+            imaging::resize_image_file,
+            imaging::crop_image,
+            imaging::crop_image_file,
+            imaging::crop_to_aspect_ratio,
+            imaging::rotate_image,
+            imaging::flip_image,
+            imaging::apply_image_filter,
+            imaging::apply_image_filters_pipeline,
+            imaging::embed_image_watermark,
+            imaging::convert_image_format,
+            imaging::get_image_dominant_colors,
+            imaging::extract_palette,
+            imaging::generate_image_thumbnail,
+            imaging::generate_thumbnail,
+            imaging::generate_thumbnails,
+            imaging::extract_image_exif,
+            imaging::extract_image_exif_from_path,
+            imaging::compute_image_perceptual_hash,
+            imaging::image_hash_distance,
+            imaging::probe_image,
+            ocr::quick_ocr_at_cursor,
+            input::start_mouse_recording,
+            input::stop_mouse_recording,
+            input::replay_mouse_path,
+            input::move_mouse,
+            input::click_mouse,
+            input::scroll_mouse,
+            input::simulate_mouse_move,
+            input::simulate_mouse_click,
+            input::simulate_key_press,
+            input::simulate_key_sequence,
+            input::get_keyboard_modifier_state,
+            display::start_display_watch,
+            display::stop_display_watch,
+            display::start_display_watcher,
+            display::stop_display_watcher,
+            dict::export_user_dict,
+            dict::import_user_dict,
+            dict::load_jieba_dict,
+            dict::add_jieba_word,
+            dict::remove_jieba_word,
+            dict::remove_user_word,
+            dict::reset_jieba_dict,
+            dict::list_custom_words,
+            dict::reset_custom_dict,
+            print::list_printers,
+            print::print_image,
+            text::text_similarity,
+            text::rank_by_similarity,
+            text::segment_mixed,
+            text::extract_keywords_tfidf,
+            text::extract_keywords_textrank,
+            text::batch_extract_keywords_tfidf,
+            text::tag_text,
+            text::batch_tag_text,
+            sync::sync_configure,
+            sync::sync_record_change,
+            sync::sync_push,
+            sync::sync_pull,
+            sync::sync_status,
+            sync::sync_clear_conflicts,
             disk::get_drive_size,
+            disk::list_drives,
             disk::get_folder_size,
-            // upload::file_download,
+            disk::get_folder_size_progress,
+            disk::cancel_folder_scan,
+            disk::find_large_files,
+            disk::get_folder_contents,
+            disk::compute_file_hash,
+            disk::compute_bytes_hash,
+            disk::verify_file_hash,
+            fs_watch::watch_directory,
+            fs_watch::stop_watching_directory,
+            upload::file_download,
+            upload::cancel_download,
+            secrets::unlock_secret_store,
+            secrets::store_secret,
+            secrets::get_secret,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(context)
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                // 撤销暂存只在本次会话有效
+                undo::purge_all(app);
+                display::stop_screens_watch(&app.state::<AppState>());
+                screen_stream::stop_all(&app.state::<AppState>());
+                // 退出时擦除临时沙箱
+                let state = app.state::<AppState>();
+                let dir = state.ephemeral_dir.write().ok().and_then(|mut g| g.take());
+                if let Some(dir) = dir {
+                    ephemeral::secure_remove_dir(&dir);
+                    ephemeral::remove_app_dirs(app);
+                }
+            }
+        });
 }
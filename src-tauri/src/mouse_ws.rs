@@ -0,0 +1,166 @@
+/*!
+ * 通过本地 WebSocket 推送鼠标位置（远程控制原型用）
+ *
+ * 坐标来自鼠标轮询线程（control_mouse_poller），轮询间隔、min_move、throttle_ms 同样生效：
+ * 轮询线程每发送一次 "mouse:position" 事件，就向所有已连接的客户端广播一帧 JSON 文本（同事件负载 {"x","y","vx","vy","speed"}）。
+ * 客户端接收过慢时丢弃积压的旧坐标，只保留最新的。
+ */
+
+use crate::AppState;
+use crate::commands::MousePos;
+use futures_util::{SinkExt, StreamExt};
+use std::net::SocketAddr;
+use tauri::State;
+use tauri::async_runtime::JoinHandle;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tokio_tungstenite::tungstenite::Message;
+
+/// 每个客户端最多积压的帧数
+const CLIENT_BACKLOG: usize = 64;
+
+/// 运行中的 WebSocket 服务
+pub struct MouseWebSocket {
+    addr: SocketAddr,
+    frames: broadcast::Sender<String>,
+    accept: JoinHandle<()>,
+}
+
+/// 广播一次鼠标位置（服务未启动或没有客户端时不做任何事）
+pub fn broadcast(state: &AppState, pos: MousePos) {
+    let Ok(guard) = state.mouse_ws.lock() else {
+        return;
+    };
+    let Some(server) = guard.as_ref() else {
+        return;
+    };
+    if server.frames.receiver_count() == 0 {
+        return;
+    }
+    match serde_json::to_string(&pos) {
+        Ok(frame) => {
+            let _ = server.frames.send(frame);
+        }
+        Err(e) => eprintln!("[mouse_ws] serialize error: {}", e),
+    }
+}
+
+async fn serve_client(stream: TcpStream, mut frames: broadcast::Receiver<String>) {
+    let peer = stream.peer_addr().ok();
+    let ws = match tokio_tungstenite::accept_async(stream).await {
+        Ok(ws) => ws,
+        Err(e) => {
+            eprintln!("[mouse_ws] handshake with {:?} failed: {}", peer, e);
+            return;
+        }
+    };
+    let (mut sink, mut incoming) = ws.split();
+    loop {
+        tokio::select! {
+            frame = frames.recv() => match frame {
+                Ok(frame) => {
+                    if sink.send(Message::Text(frame.into())).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                // 服务已停止
+                Err(broadcast::error::RecvError::Closed) => {
+                    let _ = sink.send(Message::Close(None)).await;
+                    break;
+                }
+            },
+            message = incoming.next() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    println!("[mouse_ws] client {:?} disconnected", peer);
+}
+
+fn ensure_stopped(server: &Option<MouseWebSocket>) -> Result<(), String> {
+    match server {
+        Some(server) => Err(format!(
+            "mouse websocket already running on {}",
+            server.addr
+        )),
+        None => Ok(()),
+    }
+}
+
+/**
+ * 启动鼠标位置 WebSocket 服务，返回实际监听的地址（bind_addr 端口为 0 时由系统分配）
+ * bind_addr: 如 "127.0.0.1:9001"；绑定到非回环地址会把鼠标位置暴露给局域网
+ * 需同时用 control_mouse_poller 启动鼠标轮询才会有数据；端口被占用时返回错误
+ */
+#[tauri::command]
+pub async fn start_mouse_websocket(
+    state: State<'_, AppState>,
+    bind_addr: String,
+) -> Result<String, String> {
+    {
+        let guard = state
+            .mouse_ws
+            .lock()
+            .map_err(|e| format!("lock error: {}", e))?;
+        ensure_stopped(&guard)?;
+    }
+    let listener = TcpListener::bind(&bind_addr)
+        .await
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::AddrInUse => format!("address {} is already in use", bind_addr),
+            _ => format!("bind {} failed: {}", bind_addr, e),
+        })?;
+    let addr = listener
+        .local_addr()
+        .map_err(|e| format!("bind {} failed: {}", bind_addr, e))?;
+    let (frames, _) = broadcast::channel(CLIENT_BACKLOG);
+
+    let mut guard = state
+        .mouse_ws
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?;
+    // 等待绑定期间可能已被另一次调用启动
+    ensure_stopped(&guard)?;
+    let sender = frames.clone();
+    let accept = tauri::async_runtime::spawn(async move {
+        println!("[mouse_ws] listening on {}", addr);
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tauri::async_runtime::spawn(serve_client(stream, sender.subscribe()));
+                }
+                Err(e) => eprintln!("[mouse_ws] accept error: {}", e),
+            }
+        }
+    });
+    *guard = Some(MouseWebSocket {
+        addr,
+        frames,
+        accept,
+    });
+    Ok(addr.to_string())
+}
+
+/**
+ * 停止鼠标位置 WebSocket 服务并断开所有客户端，返回服务是否在运行
+ */
+#[tauri::command]
+pub fn stop_mouse_websocket(state: State<'_, AppState>) -> Result<bool, String> {
+    let server = state
+        .mouse_ws
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?
+        .take();
+    match server {
+        Some(server) => {
+            server.accept.abort();
+            // drop frames 后各客户端任务收到 Closed，发送关闭帧后退出
+            drop(server.frames);
+            println!("[mouse_ws] stopped ({})", server.addr);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
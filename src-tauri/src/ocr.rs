@@ -0,0 +1,325 @@
+/*!
+ * 光标处快速 OCR（取词翻译弹窗的数据通路）
+ *
+ * 以鼠标为中心截取小区域，边缘仍有文字时向外扩展，直到边缘为空白或达到上限；
+ * 识别交给 tesseract 命令行（需在 PATH 中，并安装对应语言包），
+ * 返回光标所在行的文本及其在屏幕上的逻辑坐标。
+ * 截图前做与其他截图命令相同的权限检查；截图后端不可用时同样降级到 portal（不再扩展区域）。
+ */
+
+use crate::capture_backend;
+use crate::commands::{self, CaptureFormat};
+use crate::error::CommandError;
+use crate::text::is_cjk;
+use enigo::Enigo;
+use image::RgbaImage;
+use screenshots::Screen;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// 默认截取半径（逻辑像素）
+const DEFAULT_RADIUS: u32 = 40;
+/// 扩展上限（半宽 / 半高，逻辑像素）
+const MAX_HALF_WIDTH: u32 = 400;
+const MAX_HALF_HEIGHT: u32 = 120;
+/// 最多扩展次数，控制整体延迟
+const MAX_EXPANSIONS: usize = 4;
+/// 边缘亮度极差低于该值视为空白
+const BLANK_RANGE: u8 = 40;
+const OCR_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 识别结果；kind 为 "found" 或 "noTextFound"
+#[derive(Serialize, Debug)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum QuickOcrResult {
+    /// x/y/width/height 为文字在屏幕上的逻辑坐标
+    Found {
+        text: String,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        confidence: f32,
+    },
+    /// 区域内没有识别到文字，x/y 为光标位置
+    NoTextFound { x: i32, y: i32 },
+}
+
+/// 截取的区域（逻辑坐标）及其图像
+struct Region {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    image: RgbaImage,
+    png: Vec<u8>,
+}
+
+fn luma(p: &image::Rgba<u8>) -> u8 {
+    ((p.0[0] as u32 * 299 + p.0[1] as u32 * 587 + p.0[2] as u32 * 114) / 1000) as u8
+}
+
+/// 一组像素的亮度极差是否足够小（没有文字笔画）
+fn is_blank<'a>(pixels: impl Iterator<Item = &'a image::Rgba<u8>>) -> bool {
+    let (mut min, mut max) = (u8::MAX, u8::MIN);
+    for p in pixels {
+        let l = luma(p);
+        min = min.min(l);
+        max = max.max(l);
+    }
+    max.saturating_sub(min) < BLANK_RANGE
+}
+
+fn capture_region(
+    screen: &Screen,
+    cx: i32,
+    cy: i32,
+    half_w: u32,
+    half_h: u32,
+) -> Result<Region, String> {
+    let d = screen.display_info;
+    // 限制在当前屏幕内
+    let left = (cx - half_w as i32).max(d.x);
+    let top = (cy - half_h as i32).max(d.y);
+    let right = (cx + half_w as i32).min(d.x + d.width as i32);
+    let bottom = (cy + half_h as i32).min(d.y + d.height as i32);
+    if right <= left || bottom <= top {
+        return Err("cursor is outside of the screen".into());
+    }
+    let (width, height) = ((right - left) as u32, (bottom - top) as u32);
+
+    let captured = screen
+        .capture_area(left - d.x, top - d.y, width, height)
+        .map_err(|e| e.to_string())?;
+    let png = captured.buffer().to_vec();
+    let image = image::load_from_memory(&png)
+        .map_err(|e| format!("decode error: {}", e))?
+        .to_rgba8();
+    Ok(Region {
+        x: left,
+        y: top,
+        width,
+        height,
+        image,
+        png,
+    })
+}
+
+/// portal 降级时从整个桌面的截图中裁出光标附近的区域（按原点 0,0、不缩放）
+fn desktop_region(
+    desktop: RgbaImage,
+    cx: i32,
+    cy: i32,
+    half_w: u32,
+    half_h: u32,
+) -> Result<Region, String> {
+    let left = (cx - half_w as i32).max(0);
+    let top = (cy - half_h as i32).max(0);
+    let right = (cx + half_w as i32).min(desktop.width() as i32);
+    let bottom = (cy + half_h as i32).min(desktop.height() as i32);
+    if right <= left || bottom <= top {
+        return Err("cursor is outside of the desktop".into());
+    }
+    let (width, height) = ((right - left) as u32, (bottom - top) as u32);
+    let image =
+        image::imageops::crop_imm(&desktop, left as u32, top as u32, width, height).to_image();
+    let png = commands::encode_rgba(&image, CaptureFormat::Png, None)?;
+    Ok(Region {
+        x: left,
+        y: top,
+        width,
+        height,
+        image,
+        png,
+    })
+}
+
+/// 从光标处开始截取，边缘有文字时向外扩展
+fn capture_around(cx: i32, cy: i32, radius: u32) -> Result<Region, CommandError> {
+    capture_backend::preflight()?;
+    let (mut half_w, mut half_h) = (radius.max(8), (radius / 2).max(8));
+
+    let captured = Screen::from_point(cx, cy)
+        .map_err(|e| e.to_string())
+        .and_then(|screen| Ok((capture_region(&screen, cx, cy, half_w, half_h)?, screen)));
+    let (mut region, screen) = match captured {
+        Ok(captured) => captured,
+        Err(raw) => {
+            return capture_backend::fallback(raw, |desktop| {
+                desktop_region(desktop, cx, cy, half_w, half_h)
+            });
+        }
+    };
+    for _ in 0..MAX_EXPANSIONS {
+        let img = &region.image;
+        let (w, h) = img.dimensions();
+        let edge_x = !is_blank((0..h).map(|y| img.get_pixel(0, y)))
+            || !is_blank((0..h).map(|y| img.get_pixel(w - 1, y)));
+        let edge_y = !is_blank((0..w).map(|x| img.get_pixel(x, 0)))
+            || !is_blank((0..w).map(|x| img.get_pixel(x, h - 1)));
+
+        let grow_x = edge_x && half_w < MAX_HALF_WIDTH;
+        let grow_y = edge_y && half_h < MAX_HALF_HEIGHT;
+        if !grow_x && !grow_y {
+            break;
+        }
+        if grow_x {
+            half_w = (half_w * 2).min(MAX_HALF_WIDTH);
+        }
+        if grow_y {
+            half_h = (half_h * 2).min(MAX_HALF_HEIGHT);
+        }
+        region = capture_region(&screen, cx, cy, half_w, half_h)?;
+    }
+    Ok(region)
+}
+
+/// tesseract TSV 输出中的一个词（像素坐标）
+struct OcrWord {
+    text: String,
+    left: u32,
+    top: u32,
+    width: u32,
+    height: u32,
+    conf: f32,
+}
+
+/// 解析 tesseract TSV，按 (block, par, line) 分组
+fn parse_tsv(tsv: &str) -> BTreeMap<(u32, u32, u32), Vec<OcrWord>> {
+    let mut lines: BTreeMap<(u32, u32, u32), Vec<OcrWord>> = BTreeMap::new();
+    for row in tsv.lines().skip(1) {
+        let cols: Vec<&str> = row.split('\t').collect();
+        if cols.len() < 12 || cols[0] != "5" {
+            continue;
+        }
+        let text = cols[11].trim();
+        let conf: f32 = cols[10].parse().unwrap_or(-1.0);
+        if text.is_empty() || conf < 0.0 {
+            continue;
+        }
+        let num = |i: usize| cols[i].parse::<u32>().unwrap_or(0);
+        lines
+            .entry((num(2), num(3), num(4)))
+            .or_default()
+            .push(OcrWord {
+                text: text.to_string(),
+                left: num(6),
+                top: num(7),
+                width: num(8),
+                height: num(9),
+                conf,
+            });
+    }
+    lines
+}
+
+/// 拼接一行中的词：CJK 之间不加空格
+fn join_words(words: &[OcrWord]) -> String {
+    let mut out = String::new();
+    for w in words {
+        let cjk_boundary =
+            out.chars().last().is_some_and(is_cjk) && w.text.chars().next().is_some_and(is_cjk);
+        if !out.is_empty() && !cjk_boundary {
+            out.push(' ');
+        }
+        out.push_str(&w.text);
+    }
+    out
+}
+
+async fn run_tesseract(png: &[u8], lang: &str) -> Result<String, String> {
+    let mut child = Command::new("tesseract")
+        .args(["stdin", "stdout", "-l", lang, "--psm", "6", "tsv"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                "OCR engine unavailable: tesseract not found in PATH".to_string()
+            }
+            _ => format!("start tesseract failed: {}", e),
+        })?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(png)
+            .await
+            .map_err(|e| format!("write to tesseract failed: {}", e))?;
+    }
+    let output = tokio::time::timeout(OCR_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| "OCR timed out".to_string())?
+        .map_err(|e| format!("tesseract failed: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "tesseract failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/**
+ * 识别光标附近的文字
+ * radius: 初始截取半径（逻辑像素，默认 40），边缘有文字时自动扩展
+ * lang: tesseract 语言（默认 "chi_sim+eng"）
+ * 返回光标所在（或最近的）一行文字及其屏幕坐标；没有文字时返回 NoTextFound
+ * 没有屏幕录制权限或截图后端不可用时返回 kind 为 "captureUnsupported" 的错误
+ */
+#[tauri::command]
+pub async fn quick_ocr_at_cursor(
+    radius: Option<u32>,
+    lang: Option<String>,
+) -> Result<QuickOcrResult, CommandError> {
+    let (cx, cy) = Enigo::mouse_location();
+    let radius = radius.unwrap_or(DEFAULT_RADIUS);
+    let region = tokio::task::spawn_blocking(move || capture_around(cx, cy, radius))
+        .await
+        .map_err(|e| format!("capture task error: {}", e))??;
+
+    let lang = lang.unwrap_or_else(|| "chi_sim+eng".to_string());
+    let tsv = run_tesseract(&region.png, &lang).await?;
+    let lines = parse_tsv(&tsv);
+
+    // 物理像素 -> 逻辑坐标的比例（按实际截图尺寸计算，兼容不同缩放的屏幕）
+    let sx = region.image.width() as f32 / region.width as f32;
+    let sy = region.image.height() as f32 / region.height as f32;
+    let (px, py) = ((cx - region.x) as f32 * sx, (cy - region.y) as f32 * sy);
+
+    // 选取与光标距离最近的一行
+    let bounds = |words: &[OcrWord]| {
+        let left = words.iter().map(|w| w.left).min().unwrap_or(0);
+        let top = words.iter().map(|w| w.top).min().unwrap_or(0);
+        let right = words.iter().map(|w| w.left + w.width).max().unwrap_or(0);
+        let bottom = words.iter().map(|w| w.top + w.height).max().unwrap_or(0);
+        (left, top, right, bottom)
+    };
+    let distance = |(l, t, r, b): (u32, u32, u32, u32)| {
+        let dx = (l as f32 - px).max(px - r as f32).max(0.0);
+        let dy = (t as f32 - py).max(py - b as f32).max(0.0);
+        dx * dx + dy * dy
+    };
+    let Some(words) = lines
+        .values()
+        .min_by(|a, b| distance(bounds(a)).total_cmp(&distance(bounds(b))))
+    else {
+        return Ok(QuickOcrResult::NoTextFound { x: cx, y: cy });
+    };
+
+    let (l, t, r, b) = bounds(words);
+    let confidence = words.iter().map(|w| w.conf).sum::<f32>() / words.len() as f32;
+    Ok(QuickOcrResult::Found {
+        text: join_words(words),
+        x: region.x + (l as f32 / sx).round() as i32,
+        y: region.y + (t as f32 / sy).round() as i32,
+        width: ((r - l) as f32 / sx).round() as u32,
+        height: ((b - t) as f32 / sy).round() as u32,
+        confidence,
+    })
+}
@@ -0,0 +1,69 @@
+/*!
+ * 截图时临时隐藏本应用的窗口
+ *
+ * 截图命令的 hide_windows 参数给出要隐藏的 webview 窗口 label。只隐藏当前可见的窗口，
+ * 隐藏后等待合成器刷新一两帧再截图；HiddenWindows 被 drop 时（无论截图是否成功）
+ * 重新显示这些窗口，并把焦点还给原先获得焦点的窗口。
+ */
+
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, WebviewWindow};
+
+/// 隐藏窗口后等待合成器重绘的时间（约两帧）
+const COMPOSITOR_SETTLE: Duration = Duration::from_millis(50);
+
+/// 被临时隐藏的窗口，drop 时恢复
+pub struct HiddenWindows {
+    windows: Vec<WebviewWindow>,
+    focused: Option<WebviewWindow>,
+}
+
+impl HiddenWindows {
+    /// 隐藏 labels 中可见的窗口，返回恢复用的 guard 和提示（如 label 不存在）
+    pub fn hide(app: &AppHandle, labels: Option<&[String]>) -> (Self, Vec<String>) {
+        let mut guard = HiddenWindows {
+            windows: Vec::new(),
+            focused: None,
+        };
+        let mut warnings = Vec::new();
+        for label in labels.unwrap_or_default() {
+            let Some(window) = app.get_webview_window(label) else {
+                warnings.push(format!("window {} not found", label));
+                continue;
+            };
+            if !window.is_visible().unwrap_or(false) {
+                continue;
+            }
+            let focused = window.is_focused().unwrap_or(false);
+            if let Err(e) = window.hide() {
+                warnings.push(format!("could not hide window {}: {}", label, e));
+                continue;
+            }
+            if focused {
+                guard.focused = Some(window.clone());
+            }
+            guard.windows.push(window);
+        }
+        if !guard.windows.is_empty() {
+            thread::sleep(COMPOSITOR_SETTLE);
+        }
+        (guard, warnings)
+    }
+}
+
+impl Drop for HiddenWindows {
+    fn drop(&mut self) {
+        for window in &self.windows {
+            if let Err(e) = window.show() {
+                eprintln!("[own_windows] show {} failed: {}", window.label(), e);
+            }
+        }
+        let Some(window) = &self.focused else {
+            return;
+        };
+        if let Err(e) = window.set_focus() {
+            eprintln!("[own_windows] focus {} failed: {}", window.label(), e);
+        }
+    }
+}
@@ -0,0 +1,292 @@
+/*!
+ * 钉图：把截图固定为置顶的无边框窗口，作为参考
+ *
+ * 每个钉图是一个 label 为 "pin-N" 的 webview 窗口，加载内部协议 pin:// 提供的页面，
+ * 页面只请求本窗口对应的图片（按请求所在 webview 的 label 查找）。图片字节保存在
+ * AppState.pins 中，窗口销毁时（close_pin、页面内双击 / Esc，或被系统关闭）一并移除。
+ */
+
+use crate::AppState;
+use crate::commands::{CaptureFormat, encode_rgba};
+use crate::error::CommandError;
+use crate::imaging;
+use screenshots::Screen;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::http::{Request, Response, StatusCode, header};
+use tauri::{
+    AppHandle, Manager, Runtime, State, UriSchemeContext, Url, WebviewUrl, WebviewWindowBuilder,
+    WindowEvent,
+};
+
+/// 内部页面使用的 URI scheme（需在 Builder 上用 protocol 注册）
+pub const PIN_SCHEME: &str = "pin";
+
+static PIN_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// 钉图页面：图片铺满窗口，可拖动，双击或按 Esc 关闭
+const PIN_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<style>
+html, body { margin: 0; height: 100%; overflow: hidden; background: transparent; }
+img { display: block; width: 100%; height: 100%; user-select: none; -webkit-user-drag: none; }
+</style>
+</head>
+<body>
+<img src="image" data-tauri-drag-region>
+<script>
+const closePin = () => fetch("close");
+addEventListener("dblclick", closePin);
+addEventListener("keydown", (e) => { if (e.key === "Escape") closePin(); });
+</script>
+</body>
+</html>
+"#;
+
+/// 钉图的图片来源：保留的截图（见 capture_store）、图片字节或图片文件路径
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum PinSource {
+    Capture { capture_id: String },
+    Bytes { data: Vec<u8> },
+    File { path: String },
+}
+
+/// 钉住的图片
+pub struct Pin {
+    data: Vec<u8>,
+    content_type: &'static str,
+    width: u32,
+    height: u32,
+}
+
+/// 打开中的钉图：(窗口 label, 图片)，按创建顺序排列
+#[derive(Default)]
+pub struct Pins {
+    entries: Vec<(String, Pin)>,
+}
+
+impl Pins {
+    fn insert(&mut self, label: String, pin: Pin) {
+        self.entries.push((label, pin));
+    }
+
+    fn get(&self, label: &str) -> Option<&Pin> {
+        self.entries
+            .iter()
+            .find(|(l, _)| l == label)
+            .map(|(_, pin)| pin)
+    }
+
+    fn remove(&mut self, label: &str) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|(l, _)| l != label);
+        self.entries.len() != before
+    }
+}
+
+/// list_pins 返回的钉图信息；width / height 为图片像素尺寸
+#[derive(Serialize)]
+pub struct PinInfo {
+    pub label: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// 读取图片来源，返回图片字节及其格式、尺寸
+fn load(state: &AppState, source: PinSource) -> Result<Pin, CommandError> {
+    let data = match source {
+        PinSource::Capture { capture_id } => {
+            let pixels = state
+                .kept_captures
+                .lock()
+                .map_err(|e| format!("lock error: {}", e))?
+                .get(&capture_id)
+                .ok_or_else(|| {
+                    CommandError::NotFound(format!("capture {} not found", capture_id))
+                })?;
+            return Ok(Pin {
+                data: encode_rgba(&pixels, CaptureFormat::Png, None)?,
+                content_type: "image/png",
+                width: pixels.width(),
+                height: pixels.height(),
+            });
+        }
+        PinSource::Bytes { data } => data,
+        PinSource::File { path } => fs::read(&path)?,
+    };
+    let (width, height, format) = imaging::probe_header(&data)?;
+    Ok(Pin {
+        content_type: format.to_mime_type(),
+        data,
+        width,
+        height,
+    })
+}
+
+/// 内部页面的地址（Windows / Android 上自定义协议映射为 http://<scheme>.localhost）
+fn page_url() -> Url {
+    #[cfg(any(windows, target_os = "android"))]
+    let url = format!("http://{}.localhost/", PIN_SCHEME);
+    #[cfg(not(any(windows, target_os = "android")))]
+    let url = format!("{}://localhost/", PIN_SCHEME);
+    Url::parse(&url).expect("valid pin url")
+}
+
+fn respond(status: StatusCode, content_type: &str, body: Vec<u8>) -> Response<Cow<'static, [u8]>> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, content_type)
+        .body(Cow::Owned(body))
+        .unwrap_or_default()
+}
+
+/**
+ * pin:// 协议：
+ *   /       钉图页面
+ *   /image  请求所在窗口的图片
+ *   /close  关闭请求所在的钉图窗口
+ */
+pub fn protocol<R: Runtime>(
+    ctx: UriSchemeContext<'_, R>,
+    request: Request<Vec<u8>>,
+) -> Response<Cow<'static, [u8]>> {
+    let app = ctx.app_handle();
+    let label = ctx.webview_label();
+    match request.uri().path() {
+        "/" => respond(StatusCode::OK, "text/html", PIN_PAGE.as_bytes().to_vec()),
+        "/image" => {
+            let state = app.state::<AppState>();
+            let pins = match state.pins.lock() {
+                Ok(pins) => pins,
+                Err(e) => {
+                    let body = format!("lock error: {}", e).into_bytes();
+                    return respond(StatusCode::INTERNAL_SERVER_ERROR, "text/plain", body);
+                }
+            };
+            match pins.get(label) {
+                Some(pin) => respond(StatusCode::OK, pin.content_type, pin.data.clone()),
+                None => respond(StatusCode::NOT_FOUND, "text/plain", Vec::new()),
+            }
+        }
+        "/close" => {
+            if let Some(window) = app.get_webview_window(label) {
+                let _ = window.close();
+            }
+            respond(StatusCode::NO_CONTENT, "text/plain", Vec::new())
+        }
+        _ => respond(StatusCode::NOT_FOUND, "text/plain", Vec::new()),
+    }
+}
+
+/**
+ * 把图片钉在屏幕上：创建置顶、无边框、不显示在任务栏的窗口，返回窗口 label
+ * image: { capture_id } | { data: 图片字节 } | { path: 图片文件路径 }
+ * x / y: 窗口左上角的全局逻辑坐标（同截图结果中的 x / y）
+ * 窗口按所在屏幕的 scale_factor 换算为逻辑尺寸，图片以原始像素大小显示；
+ * 钉图窗口可拖动，双击或按 Esc 关闭
+ * capture_id 不存在时返回 kind 为 "notFound" 的错误；钉图持有图片的副本，
+ * 之后 release_capture 不影响已钉住的图片
+ */
+#[tauri::command]
+pub async fn pin_capture(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    image: PinSource,
+    x: i32,
+    y: i32,
+) -> Result<String, CommandError> {
+    let pin = load(&state, image)?;
+    let scale = Screen::from_point(x, y)
+        .map(|screen| screen.display_info.scale_factor as f64)
+        .unwrap_or(1.0);
+    let (width, height) = (pin.width as f64 / scale, pin.height as f64 / scale);
+
+    let label = format!("pin-{}", PIN_SEQ.fetch_add(1, Ordering::Relaxed));
+    state
+        .pins
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?
+        .insert(label.clone(), pin);
+
+    let window = WebviewWindowBuilder::new(&app, &label, WebviewUrl::CustomProtocol(page_url()))
+        .title("Pin")
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .resizable(false)
+        .maximizable(false)
+        .shadow(false)
+        .inner_size(width, height)
+        .position(x as f64, y as f64)
+        .build();
+    let window = match window {
+        Ok(window) => window,
+        Err(e) => {
+            if let Ok(mut pins) = state.pins.lock() {
+                pins.remove(&label);
+            }
+            return Err(format!("create pin window failed: {}", e).into());
+        }
+    };
+
+    // 无论窗口如何关闭，都释放图片
+    let app_for_event = app.clone();
+    let pin_label = label.clone();
+    window.on_window_event(move |event| {
+        if !matches!(event, WindowEvent::Destroyed) {
+            return;
+        }
+        if let Ok(mut pins) = app_for_event.state::<AppState>().pins.lock() {
+            pins.remove(&pin_label);
+        }
+    });
+    Ok(label)
+}
+
+/**
+ * 关闭钉图窗口，返回 label 是否为打开中的钉图（其他窗口的 label 不会被关闭）
+ */
+#[tauri::command]
+pub fn close_pin(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    window_label: String,
+) -> Result<bool, String> {
+    let removed = state
+        .pins
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?
+        .remove(&window_label);
+    if !removed {
+        return Ok(false);
+    }
+    if let Some(window) = app.get_webview_window(&window_label) {
+        window.close().map_err(|e| e.to_string())?;
+    }
+    Ok(true)
+}
+
+/**
+ * 列出打开中的钉图（按创建顺序）
+ */
+#[tauri::command]
+pub fn list_pins(state: State<'_, AppState>) -> Result<Vec<PinInfo>, String> {
+    let pins = state
+        .pins
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?;
+    Ok(pins
+        .entries
+        .iter()
+        .map(|(label, pin)| PinInfo {
+            label: label.clone(),
+            width: pin.width,
+            height: pin.height,
+        })
+        .collect())
+}
@@ -0,0 +1,412 @@
+/*!
+ * 打印截图 / 缓存图片
+ *
+ * Windows：通过 PowerShell 调用 System.Drawing.Printing（.NET 打印 API）
+ * Unix：通过 CUPS 的 lp / lpstat，lp 不可用时回退到 lpr
+ */
+
+use crate::events;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use tokio::process::Command;
+
+/// 打印错误（带类型，便于前端区分处理）
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", content = "message")]
+pub enum PrintError {
+    PrinterNotFound(String),
+    PrinterOffline(String),
+    InvalidImage(String),
+    Io(String),
+    Failed(String),
+}
+
+impl std::fmt::Display for PrintError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PrintError::PrinterNotFound(m) => write!(f, "printer not found: {}", m),
+            PrintError::PrinterOffline(m) => write!(f, "printer offline: {}", m),
+            PrintError::InvalidImage(m) => write!(f, "invalid image: {}", m),
+            PrintError::Io(m) => write!(f, "io error: {}", m),
+            PrintError::Failed(m) => write!(f, "print failed: {}", m),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum FitMode {
+    #[default]
+    Fit,
+    Fill,
+    Actual,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Orientation {
+    #[default]
+    Portrait,
+    Landscape,
+}
+
+/// 打印选项
+#[derive(Deserialize, Default, Debug)]
+pub struct PrintOptions {
+    pub copies: Option<u32>,
+    pub fit: Option<FitMode>,
+    pub orientation: Option<Orientation>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PrinterInfo {
+    pub name: String,
+    pub is_default: bool,
+    pub is_online: bool,
+}
+
+/// print:status 事件负载
+#[derive(Serialize, Clone, JsonSchema)]
+pub struct PrintStatus {
+    pub job_id: String,
+    pub status: String,
+}
+
+fn io_err(e: std::io::Error) -> PrintError {
+    PrintError::Io(e.to_string())
+}
+
+/// 提交给打印机的文件；格式转换生成的临时文件在 drop 时删除
+struct PrintFile {
+    path: PathBuf,
+    temporary: bool,
+}
+
+impl Drop for PrintFile {
+    fn drop(&mut self) {
+        if self.temporary {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// CUPS 能直接打印 PNG/JPEG，其余格式先转成 PNG
+fn prepare_image(path: &str) -> Result<PrintFile, PrintError> {
+    let src = Path::new(path);
+    if !src.exists() {
+        return Err(PrintError::Io(format!("{} does not exist", path)));
+    }
+    let ext = src
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if matches!(ext.as_str(), "png" | "jpg" | "jpeg") {
+        return Ok(PrintFile {
+            path: src.to_path_buf(),
+            temporary: false,
+        });
+    }
+
+    let img = image::open(src).map_err(|e| PrintError::InvalidImage(e.to_string()))?;
+    let tmp = PrintFile {
+        path: std::env::temp_dir().join(format!("lucky-print-{}.png", now_millis())),
+        temporary: true,
+    };
+    img.save(&tmp.path)
+        .map_err(|e| PrintError::InvalidImage(e.to_string()))?;
+    Ok(tmp)
+}
+
+fn now_millis() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0)
+}
+
+/**
+ * 列出系统打印机
+ */
+#[tauri::command]
+pub async fn list_printers() -> Result<Vec<PrinterInfo>, PrintError> {
+    platform::list_printers().await
+}
+
+/**
+ * 打印图片
+ * printer: 打印机名称，None 使用系统默认打印机
+ * 返回打印任务 id，并在平台支持时通过 "print:status" 事件上报任务状态
+ */
+#[tauri::command]
+pub async fn print_image(
+    app: AppHandle,
+    path: String,
+    printer: Option<String>,
+    options: Option<PrintOptions>,
+) -> Result<String, PrintError> {
+    let options = options.unwrap_or_default();
+    let printers = platform::list_printers().await?;
+
+    let target = match printer {
+        Some(name) => printers
+            .into_iter()
+            .find(|p| p.name == name)
+            .ok_or(PrintError::PrinterNotFound(name))?,
+        None => printers
+            .into_iter()
+            .find(|p| p.is_default)
+            .ok_or_else(|| PrintError::PrinterNotFound("no default printer".into()))?,
+    };
+    if !target.is_online {
+        return Err(PrintError::PrinterOffline(target.name));
+    }
+
+    let file = prepare_image(&path)?;
+    let job_id = platform::submit(&file.path, &target.name, &options).await?;
+    // lp / lpr 返回时文件已复制到打印队列，PowerShell 的 Print() 返回时已渲染完成，临时文件可以删除
+    drop(file);
+
+    let _ = events::emit_event(
+        &app,
+        events::Event::PrintStatus(PrintStatus {
+            job_id: job_id.clone(),
+            status: "submitted".into(),
+        }),
+    );
+    platform::watch_job(app, job_id.clone());
+
+    Ok(job_id)
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use super::*;
+    use std::time::Duration;
+
+    pub async fn list_printers() -> Result<Vec<PrinterInfo>, PrintError> {
+        let out = Command::new("lpstat")
+            .arg("-p")
+            .output()
+            .await
+            .map_err(io_err)?;
+        let default = Command::new("lpstat")
+            .arg("-d")
+            .output()
+            .await
+            .ok()
+            .and_then(|o| {
+                String::from_utf8_lossy(&o.stdout)
+                    .rsplit(':')
+                    .next()
+                    .map(|s| s.trim().to_string())
+            })
+            .unwrap_or_default();
+
+        // 形如 "printer HP is idle.  enabled since ..." / "printer HP disabled since ..."
+        Ok(String::from_utf8_lossy(&out.stdout)
+            .lines()
+            .filter_map(|line| {
+                let rest = line.strip_prefix("printer ")?;
+                let name = rest.split_whitespace().next()?.to_string();
+                Some(PrinterInfo {
+                    is_default: name == default,
+                    is_online: !rest.contains("disabled"),
+                    name,
+                })
+            })
+            .collect())
+    }
+
+    pub async fn submit(
+        file: &Path,
+        printer: &str,
+        options: &PrintOptions,
+    ) -> Result<String, PrintError> {
+        let scaling = match options.fit.unwrap_or_default() {
+            FitMode::Fit => "print-scaling=fit",
+            FitMode::Fill => "print-scaling=fill",
+            FitMode::Actual => "print-scaling=none",
+        };
+        let orientation = match options.orientation.unwrap_or_default() {
+            Orientation::Portrait => "portrait",
+            Orientation::Landscape => "landscape",
+        };
+        let copies = options.copies.unwrap_or(1).max(1).to_string();
+
+        let lp = Command::new("lp")
+            .args(["-d", printer, "-n", copies.as_str(), "-o", scaling, "-o", orientation])
+            .arg(file)
+            .output()
+            .await;
+
+        match lp {
+            Ok(out) if out.status.success() => {
+                // "request id is HP-42 (1 file(s))"
+                let stdout = String::from_utf8_lossy(&out.stdout);
+                Ok(stdout
+                    .split_whitespace()
+                    .skip_while(|w| *w != "is")
+                    .nth(1)
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| format!("lp-{}", now_millis())))
+            }
+            Ok(out) => Err(PrintError::Failed(
+                String::from_utf8_lossy(&out.stderr).trim().to_string(),
+            )),
+            Err(_) => {
+                // 没有 lp 时回退到 lpr（不返回任务号）
+                let out = Command::new("lpr")
+                    .args(["-P", printer, "-#", copies.as_str(), "-o", scaling, "-o", orientation])
+                    .arg(file)
+                    .output()
+                    .await
+                    .map_err(io_err)?;
+                if !out.status.success() {
+                    return Err(PrintError::Failed(
+                        String::from_utf8_lossy(&out.stderr).trim().to_string(),
+                    ));
+                }
+                Ok(format!("lpr-{}", now_millis()))
+            }
+        }
+    }
+
+    /// 轮询 lpstat，任务离开队列后上报 completed
+    pub fn watch_job(app: AppHandle, job_id: String) {
+        if job_id.starts_with("lpr-") {
+            return;
+        }
+        tauri::async_runtime::spawn(async move {
+            for _ in 0..600 {
+                tokio::time::sleep(Duration::from_secs(2)).await;
+                let Ok(out) = Command::new("lpstat").args(["-o", &job_id]).output().await else {
+                    return;
+                };
+                if !String::from_utf8_lossy(&out.stdout).contains(&job_id) {
+                    let _ = events::emit_event(
+                        &app,
+                        events::Event::PrintStatus(PrintStatus {
+                            job_id,
+                            status: "completed".into(),
+                        }),
+                    );
+                    return;
+                }
+            }
+        });
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::*;
+
+    /// PowerShell 单引号字符串转义
+    fn ps_quote(s: &str) -> String {
+        format!("'{}'", s.replace('\'', "''"))
+    }
+
+    async fn powershell(script: &str) -> Result<std::process::Output, PrintError> {
+        Command::new("powershell")
+            .args(["-NoProfile", "-NonInteractive", "-Command", script])
+            .output()
+            .await
+            .map_err(io_err)
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Win32Printer {
+        name: String,
+        default: Option<bool>,
+        work_offline: Option<bool>,
+    }
+
+    pub async fn list_printers() -> Result<Vec<PrinterInfo>, PrintError> {
+        let out = powershell(
+            "Get-CimInstance Win32_Printer | Select-Object Name,Default,WorkOffline | ConvertTo-Json -Compress",
+        )
+        .await?;
+        let text = String::from_utf8_lossy(&out.stdout);
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(Vec::new());
+        }
+        // 只有一台打印机时 ConvertTo-Json 输出对象而不是数组
+        let list: Vec<Win32Printer> = if text.starts_with('[') {
+            serde_json::from_str(text)
+        } else {
+            serde_json::from_str::<Win32Printer>(text).map(|p| vec![p])
+        }
+        .map_err(|e| PrintError::Failed(e.to_string()))?;
+
+        Ok(list
+            .into_iter()
+            .map(|p| PrinterInfo {
+                name: p.name,
+                is_default: p.default.unwrap_or(false),
+                is_online: !p.work_offline.unwrap_or(false),
+            })
+            .collect())
+    }
+
+    pub async fn submit(
+        file: &Path,
+        printer: &str,
+        options: &PrintOptions,
+    ) -> Result<String, PrintError> {
+        let scale = match options.fit.unwrap_or_default() {
+            FitMode::Fit => "[Math]::Min($sx, $sy)",
+            FitMode::Fill => "[Math]::Max($sx, $sy)",
+            // 页面单位为 1/100 英寸
+            FitMode::Actual => "100 / $img.HorizontalResolution",
+        };
+        let landscape = match options.orientation.unwrap_or_default() {
+            Orientation::Portrait => "$false",
+            Orientation::Landscape => "$true",
+        };
+        let copies = options.copies.unwrap_or(1).max(1);
+
+        let script = format!(
+            r#"
+Add-Type -AssemblyName System.Drawing
+$img = [System.Drawing.Image]::FromFile({file})
+$doc = New-Object System.Drawing.Printing.PrintDocument
+$doc.PrinterSettings.PrinterName = {printer}
+if (-not $doc.PrinterSettings.IsValid) {{ exit 3 }}
+$doc.PrinterSettings.Copies = {copies}
+$doc.DefaultPageSettings.Landscape = {landscape}
+$doc.add_PrintPage({{
+    param($s, $e)
+    $b = $e.MarginBounds
+    $sx = $b.Width / $img.Width
+    $sy = $b.Height / $img.Height
+    $k = {scale}
+    $w = $img.Width * $k
+    $h = $img.Height * $k
+    $e.Graphics.DrawImage($img, $b.X + ($b.Width - $w) / 2, $b.Y + ($b.Height - $h) / 2, $w, $h)
+}})
+$doc.Print()
+$img.Dispose()
+"#,
+            file = ps_quote(&file.to_string_lossy()),
+            printer = ps_quote(printer),
+        );
+
+        let out = powershell(&script).await?;
+        match out.status.code() {
+            Some(0) => Ok(format!("win-{}", now_millis())),
+            Some(3) => Err(PrintError::PrinterNotFound(printer.to_string())),
+            _ => Err(PrintError::Failed(
+                String::from_utf8_lossy(&out.stderr).trim().to_string(),
+            )),
+        }
+    }
+
+    /// Windows 下不跟踪任务状态，只上报 submitted
+    pub fn watch_job(_app: AppHandle, _job_id: String) {}
+}
@@ -0,0 +1,285 @@
+/*!
+ * 屏幕画面流（"屏幕镜像"预览窗口用）
+ *
+ * 每个流一个线程，按目标帧率截取整屏、缩小并编码为 JPEG，通过二进制 Channel 推送：
+ * 每帧先发送一条 JSON 消息（ScreenFrameHeader），紧接着发送一条原始字节消息。
+ * 前端处理完一帧后调用 ack_screen_frame；未确认的帧达到 MAX_IN_FLIGHT 时丢弃新帧而不排队。
+ * 开始推送时自动启动显示器休眠/唤醒监听，显示器休眠时暂停截图，并在暂停和恢复时各发送一条
+ * JSON 消息（ScreenStreamMarker，以 marker 字段区分，后面不跟字节消息）；
+ * 前端关闭 Channel 后线程自行退出。
+ */
+
+use crate::AppState;
+use crate::commands::ImageOutput;
+use crate::display;
+use screenshots::Screen;
+use serde::Serialize;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::ipc::{Channel, InvokeResponseBody};
+use tauri::{AppHandle, State};
+
+/// 允许的最大帧率
+const MAX_FPS: u8 = 30;
+/// 最多允许多少帧已发送但未确认
+const MAX_IN_FLIGHT: u64 = 2;
+/// 帧的 JPEG 质量
+const FRAME_QUALITY: u8 = 70;
+
+static STREAM_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// 进行中的画面流
+pub struct ScreenStream {
+    screen_id: u32,
+    stop: Arc<AtomicBool>,
+    /// 前端已确认的最大帧序号 + 1
+    acked: Arc<AtomicU64>,
+    handle: JoinHandle<()>,
+}
+
+/// 每帧图片数据前发送的 JSON 头
+#[derive(Serialize, Clone)]
+pub struct ScreenFrameHeader {
+    pub seq: u64,
+    pub width: u32,
+    pub height: u32,
+    pub format: String,
+    /// 截图时间（毫秒时间戳）
+    pub captured_at: u64,
+    /// 此前因前端未及时确认而丢弃的帧数（累计）
+    pub dropped: u64,
+}
+
+/// 画面流暂停 / 恢复时发送的 JSON 消息
+#[derive(Serialize, Clone)]
+pub struct ScreenStreamMarker {
+    /// "paused" | "resumed"
+    pub marker: String,
+    /// 暂停原因，目前只有 "display-sleep"
+    pub reason: String,
+    /// 毫秒时间戳
+    pub at: u64,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// 截取并编码一帧
+fn capture_frame(
+    screen: &Screen,
+    output: &ImageOutput,
+    seq: u64,
+    dropped: u64,
+) -> Result<(ScreenFrameHeader, Vec<u8>), String> {
+    let captured_at = now_millis();
+    let image = screen.capture().map_err(|e| e.to_string())?;
+    let (data, resized) = output.render(image.buffer())?;
+    // 未缩放时取截图缓冲区的实际尺寸（物理像素），display_info 的宽高是逻辑尺寸
+    let (width, height) = resized.unwrap_or((image.width(), image.height()));
+    let header = ScreenFrameHeader {
+        seq,
+        width,
+        height,
+        format: output.format.as_str().into(),
+        captured_at,
+        dropped,
+    };
+    Ok((header, data))
+}
+
+fn send_marker(channel: &Channel<InvokeResponseBody>, paused: bool) -> Result<(), String> {
+    let marker = ScreenStreamMarker {
+        marker: if paused { "paused" } else { "resumed" }.into(),
+        reason: "display-sleep".into(),
+        at: now_millis(),
+    };
+    let marker = serde_json::to_string(&marker).map_err(|e| format!("serialize error: {}", e))?;
+    channel
+        .send(InvokeResponseBody::Json(marker))
+        .map_err(|e| format!("channel error: {}", e))
+}
+
+fn send_frame(
+    channel: &Channel<InvokeResponseBody>,
+    header: &ScreenFrameHeader,
+    data: Vec<u8>,
+) -> Result<(), String> {
+    let header = serde_json::to_string(header).map_err(|e| format!("serialize error: {}", e))?;
+    channel
+        .send(InvokeResponseBody::Json(header))
+        .and_then(|_| channel.send(InvokeResponseBody::Raw(data)))
+        .map_err(|e| format!("channel error: {}", e))
+}
+
+/**
+ * 开始推送屏幕画面，返回 stream_id
+ * fps: 目标帧率（1-30），截图跟不上时不补帧；max_width: 帧宽度上限，按比例缩小
+ * 同一屏幕同时只允许一个流，已有时返回 "already streaming" 错误
+ */
+#[tauri::command]
+pub fn start_screen_stream(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    screen_id: u32,
+    fps: u8,
+    max_width: Option<u32>,
+    on_frame: Channel<InvokeResponseBody>,
+) -> Result<String, String> {
+    let output = ImageOutput::new(Some("jpeg"), Some(FRAME_QUALITY), max_width, None)?;
+
+    let mut streams = state
+        .screen_streams
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?;
+    // Channel 关闭后自行退出的流不再占用屏幕
+    streams.retain(|_, s| !s.handle.is_finished());
+    if streams.values().any(|s| s.screen_id == screen_id) {
+        return Err(format!("screen {} is already streaming", screen_id));
+    }
+
+    let screen = Screen::all()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|s| s.display_info.id == screen_id)
+        .ok_or_else(|| format!("Screen {} not found", screen_id))?;
+
+    display::spawn_display_watch(&app, &state, display::DISPLAY_WATCH_INTERVAL)?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let acked = Arc::new(AtomicU64::new(0));
+    let asleep = state.display_asleep.clone();
+    let interval = Duration::from_secs_f64(1.0 / fps.clamp(1, MAX_FPS) as f64);
+    let stream_id = format!("stream-{}", STREAM_SEQ.fetch_add(1, Ordering::Relaxed));
+
+    let (stop_thread, acked_thread, id) = (stop.clone(), acked.clone(), stream_id.clone());
+    let handle = thread::spawn(move || {
+        println!("[screen_stream] {} started (screen {})", id, screen_id);
+        let mut seq = 0u64;
+        let mut dropped = 0u64;
+        let mut next = Instant::now();
+        let mut paused = false;
+
+        while !stop_thread.load(Ordering::Relaxed) {
+            let now = Instant::now();
+            if now < next {
+                thread::sleep(next - now);
+                continue;
+            }
+            // 落后时从当前时刻重新计时，不补帧
+            next = (next + interval).max(now);
+
+            let now_paused = asleep.load(Ordering::Relaxed);
+            if now_paused != paused {
+                paused = now_paused;
+                if let Err(e) = send_marker(&on_frame, paused) {
+                    eprintln!("[screen_stream] {} stopped: {}", id, e);
+                    break;
+                }
+            }
+            if paused {
+                continue;
+            }
+            if seq.saturating_sub(acked_thread.load(Ordering::Relaxed)) >= MAX_IN_FLIGHT {
+                dropped += 1;
+                continue;
+            }
+
+            let (header, data) = match capture_frame(&screen, &output, seq, dropped) {
+                Ok(frame) => frame,
+                Err(e) => {
+                    eprintln!("[screen_stream] {} capture failed: {}", id, e);
+                    continue;
+                }
+            };
+            if let Err(e) = send_frame(&on_frame, &header, data) {
+                // 前端已关闭 Channel
+                eprintln!("[screen_stream] {} stopped: {}", id, e);
+                break;
+            }
+            seq += 1;
+        }
+        println!("[screen_stream] {} exiting", id);
+    });
+
+    streams.insert(
+        stream_id.clone(),
+        ScreenStream {
+            screen_id,
+            stop,
+            acked,
+            handle,
+        },
+    );
+    Ok(stream_id)
+}
+
+/**
+ * 确认已处理完序号为 seq 的帧（及之前的帧），之后才会继续推送新帧
+ */
+#[tauri::command]
+pub fn ack_screen_frame(
+    state: State<'_, AppState>,
+    stream_id: String,
+    seq: u64,
+) -> Result<(), String> {
+    let streams = state
+        .screen_streams
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?;
+    let stream = streams
+        .get(&stream_id)
+        .ok_or_else(|| format!("stream {} not found", stream_id))?;
+    stream.acked.fetch_max(seq + 1, Ordering::Relaxed);
+    Ok(())
+}
+
+/**
+ * 停止画面流，返回是否找到该流
+ */
+#[tauri::command]
+pub fn stop_screen_stream(state: State<'_, AppState>, stream_id: String) -> Result<bool, String> {
+    let stream = state
+        .screen_streams
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?
+        .remove(&stream_id);
+    match stream {
+        Some(stream) => {
+            stream.stop.store(true, Ordering::Relaxed);
+            thread::spawn(move || {
+                let _ = stream.handle.join();
+            });
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// 是否有仍在运行的画面流
+pub fn has_active(state: &AppState) -> Result<bool, String> {
+    let streams = state
+        .screen_streams
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?;
+    Ok(streams.values().any(|s| !s.handle.is_finished()))
+}
+
+/// 停止所有画面流并等待线程退出（应用退出时调用）
+pub fn stop_all(state: &AppState) {
+    let streams: Vec<ScreenStream> = match state.screen_streams.lock() {
+        Ok(mut streams) => streams.drain().map(|(_, s)| s).collect(),
+        Err(_) => return,
+    };
+    for stream in &streams {
+        stream.stop.store(true, Ordering::Relaxed);
+    }
+    for stream in streams {
+        let _ = stream.handle.join();
+    }
+}
@@ -0,0 +1,138 @@
+/*!
+ * 基于 stronghold 的密钥存储（API token 等）
+ *
+ * 快照文件位于 app_local_data_dir/secrets.hold，
+ * 密码经 argon2（与 stronghold 插件共用 salt.txt）派生为快照密钥。
+ * 临时模式下快照只保存在内存中（不写入磁盘），退出后即丢弃。
+ * 需先调用 unlock_secret_store 打开快照，之后 store_secret / get_secret 才可用。
+ */
+
+use crate::AppState;
+use crate::ephemeral;
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_stronghold::kdf::KeyDerivation;
+use tauri_plugin_stronghold::stronghold::Stronghold;
+
+const SNAPSHOT_FILE: &str = "secrets.hold";
+
+/// 未调用 unlock_secret_store 时的错误
+pub const ERR_NOT_INITIALIZED: &str =
+    "secret store is not initialized; call unlock_secret_store first";
+
+fn local_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_local_data_dir()
+        .map_err(|e| format!("path error: {}", e))
+}
+
+/// 与 setup 中初始化 stronghold 插件时使用的 salt 相同
+fn salt_path(app: &AppHandle, state: &AppState) -> Result<PathBuf, String> {
+    match state.ephemeral_dir.read().ok().and_then(|g| g.clone()) {
+        Some(dir) => Ok(dir.join("salt.txt")),
+        None => Ok(local_dir(app)?.join("salt.txt")),
+    }
+}
+
+fn snapshot_path(app: &AppHandle, state: &AppState) -> Result<PathBuf, String> {
+    let dir = ephemeral::redirect(state, "secrets", local_dir(app)?);
+    fs::create_dir_all(&dir).map_err(|e| format!("mkdir error: {}", e))?;
+    Ok(dir.join(SNAPSHOT_FILE))
+}
+
+/// 取得 client：已在会话中则直接使用，否则从快照加载，快照中没有时新建
+fn open_client(stronghold: &Stronghold, name: &str) -> Result<iota_stronghold::Client, String> {
+    stronghold
+        .get_client(name)
+        .or_else(|_| stronghold.load_client(name))
+        .or_else(|_| stronghold.create_client(name))
+        .map_err(|e| format!("stronghold client error: {}", e))
+}
+
+/**
+ * 用密码打开密钥存储（快照不存在时创建）
+ * 密码错误时返回错误；argon2 派生较慢，在后台线程执行
+ */
+#[tauri::command]
+pub async fn unlock_secret_store(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    password: String,
+) -> Result<(), String> {
+    let salt = salt_path(&app, &state)?;
+    let path = snapshot_path(&app, &state)?;
+    let stronghold = tokio::task::spawn_blocking(move || {
+        let key = KeyDerivation::argon2(&password, &salt);
+        Stronghold::new(&path, key).map_err(|e| format!("unlock failed: {}", e))
+    })
+    .await
+    .map_err(|e| format!("unlock task error: {}", e))??;
+
+    *state
+        .secrets
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))? = Some(stronghold);
+    Ok(())
+}
+
+/// 保存密钥（供命令和其他后端模块使用）；临时模式下只保存在内存中
+pub fn write(state: &AppState, client: &str, key: &str, value: &str) -> Result<(), String> {
+    let guard = state
+        .secrets
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?;
+    let stronghold = guard.as_ref().ok_or(ERR_NOT_INITIALIZED)?;
+    open_client(stronghold, client)?
+        .store()
+        .insert(key.as_bytes().to_vec(), value.as_bytes().to_vec(), None)
+        .map_err(|e| format!("store error: {}", e))?;
+    stronghold
+        .write_client(client)
+        .map_err(|e| format!("stronghold client error: {}", e))?;
+    if ephemeral::is_enabled(state) {
+        return Ok(());
+    }
+    stronghold.save().map_err(|e| format!("save error: {}", e))
+}
+
+/// 读取密钥（供命令和其他后端模块使用），不存在时返回 None
+pub fn read(state: &AppState, client: &str, key: &str) -> Result<Option<String>, String> {
+    let guard = state
+        .secrets
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?;
+    let stronghold = guard.as_ref().ok_or(ERR_NOT_INITIALIZED)?;
+    let value = open_client(stronghold, client)?
+        .store()
+        .get(key.as_bytes())
+        .map_err(|e| format!("store error: {}", e))?;
+    value
+        .map(|v| String::from_utf8(v).map_err(|_| "secret is not valid UTF-8".to_string()))
+        .transpose()
+}
+
+/**
+ * 保存密钥到 client 下的 key（已存在则覆盖），并立即写入快照（临时模式下只保存在内存中）
+ */
+#[tauri::command]
+pub fn store_secret(
+    state: State<'_, AppState>,
+    client: String,
+    key: String,
+    value: String,
+) -> Result<(), String> {
+    write(&state, &client, &key, &value)
+}
+
+/**
+ * 读取密钥，不存在时返回 None
+ */
+#[tauri::command]
+pub fn get_secret(
+    state: State<'_, AppState>,
+    client: String,
+    key: String,
+) -> Result<Option<String>, String> {
+    read(&state, &client, &key)
+}
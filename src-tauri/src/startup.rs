@@ -0,0 +1,165 @@
+/*!
+ * 前端启动快照
+ *
+ * 启动时前端需要的多项数据在一次 IPC 中并发获取，每个分区直接调用对应的命令实现。
+ * 超出时间预算的分区先返回 pending，完成后通过 "startup:section" 事件补发。
+ */
+
+use crate::AppState;
+use crate::capture_backend;
+use crate::commands;
+use crate::dict;
+use crate::disk;
+use crate::events;
+use crate::print;
+use crate::sync;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// 默认时间预算
+const DEFAULT_BUDGET: Duration = Duration::from_millis(300);
+
+/// 可请求的分区及对应命令
+const SECTIONS: &[&str] = &[
+    "display_info",    // get_display_info
+    "screens",         // get_all_screens
+    "system_info",     // get_system_info
+    "mouse_poller",    // mouse_poller_status
+    "capture_backend", // get_capture_backend_info
+    "custom_words",    // list_custom_words
+    "sync_status",     // sync_status
+    "drives",          // list_drives
+    "printers",        // list_printers
+];
+
+/// 单个分区的结果；status 为 "ready" | "failed" | "pending"
+#[derive(Serialize, Clone, JsonSchema)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum SectionResult {
+    Ready {
+        value: serde_json::Value,
+    },
+    Failed {
+        error: String,
+    },
+    /// 超出时间预算，稍后通过 "startup:section" 事件送达
+    Pending,
+}
+
+/// "startup:section" 事件负载：pending 分区的最终结果
+#[derive(Serialize, Clone, JsonSchema)]
+pub struct StartupSection {
+    pub section: String,
+    pub result: SectionResult,
+}
+
+/// 启动快照
+#[derive(Serialize)]
+pub struct StartupSnapshot {
+    pub sections: BTreeMap<String, SectionResult>,
+    /// 无法识别的分区名（不影响其他分区）
+    pub unknown: Vec<String>,
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<serde_json::Value, String> {
+    serde_json::to_value(value).map_err(|e| format!("serialize error: {}", e))
+}
+
+/// 在阻塞线程池中执行同步命令
+async fn blocking<T, F>(f: F) -> Result<serde_json::Value, String>
+where
+    T: Serialize + Send + 'static,
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| format!("section task error: {}", e))?
+        .and_then(to_value)
+}
+
+async fn load(app: AppHandle, section: &str) -> Result<serde_json::Value, String> {
+    match section {
+        "display_info" => blocking(commands::get_display_info).await,
+        "screens" => blocking(commands::get_all_screens).await,
+        "system_info" => to_value(commands::get_system_info(app.state::<AppState>())),
+        "mouse_poller" => to_value(commands::mouse_poller_status(app.state::<AppState>())?),
+        "capture_backend" => to_value(capture_backend::get_capture_backend_info().await),
+        "custom_words" => to_value(dict::list_custom_words(app.state::<AppState>())),
+        "sync_status" => {
+            blocking(move || sync::sync_status(app.clone(), app.state::<AppState>())).await
+        }
+        "drives" => blocking(|| Ok(disk::list_drives())).await,
+        "printers" => to_value(print::list_printers().await.map_err(|e| e.to_string())?),
+        other => Err(format!("unknown section: {}", other)),
+    }
+}
+
+fn into_result(result: Result<serde_json::Value, String>) -> SectionResult {
+    match result {
+        Ok(value) => SectionResult::Ready { value },
+        Err(error) => SectionResult::Failed { error },
+    }
+}
+
+/**
+ * 一次获取启动所需的多个分区（见 SECTIONS），各分区并发执行
+ * budget_ms: 总时间预算，缺省 300ms；超时的分区返回 pending，完成后发送 "startup:section"
+ * 未知分区名记录在 unknown 中
+ */
+#[tauri::command]
+pub async fn get_startup_snapshot(
+    app: AppHandle,
+    sections: Vec<String>,
+    budget_ms: Option<u64>,
+) -> StartupSnapshot {
+    let deadline =
+        tokio::time::Instant::now() + budget_ms.map_or(DEFAULT_BUDGET, Duration::from_millis);
+
+    let mut unknown = Vec::new();
+    let mut tasks = Vec::new();
+    for section in sections {
+        if !SECTIONS.contains(&section.as_str()) {
+            unknown.push(section);
+            continue;
+        }
+        let handle = app.clone();
+        let name = section.clone();
+        let task = tokio::spawn(async move { load(handle, &name).await });
+        tasks.push((section, task));
+    }
+
+    let mut results = BTreeMap::new();
+    for (section, mut task) in tasks {
+        let result = match tokio::time::timeout_at(deadline, &mut task).await {
+            Ok(joined) => into_result(
+                joined
+                    .map_err(|e| format!("section task error: {}", e))
+                    .and_then(|r| r),
+            ),
+            Err(_) => {
+                let handle = app.clone();
+                let name = section.clone();
+                tokio::spawn(async move {
+                    let result = into_result(task.await.unwrap_or_else(|e| Err(e.to_string())));
+                    let _ = events::emit_event(
+                        &handle,
+                        events::Event::StartupSection(StartupSection {
+                            section: name,
+                            result,
+                        }),
+                    );
+                });
+                SectionResult::Pending
+            }
+        };
+        results.insert(section, result);
+    }
+
+    StartupSnapshot {
+        sections: results,
+        unknown,
+    }
+}
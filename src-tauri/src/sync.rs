@@ -0,0 +1,443 @@
+/*!
+ * 多设备增量同步（用户词典、设置、片段、截图配置等）
+ *
+ * 每个条目带 lamport 时间戳和设备 id，冲突时按 (lamport, device) 取较大者（last-writer-wins），
+ * 被覆盖的一方记入冲突日志供 UI 展示。
+ *
+ * 协议：
+ *   POST {endpoint}/push           body: { device, items: [SyncItem] }
+ *   GET  {endpoint}/pull?since=N   resp: { cursor, items: [SyncItem] }
+ *
+ * 凭据（bearer token）保存在 stronghold 密钥存储中（client "sync"，key "token"），
+ * 由 sync_configure 写入，推送 / 拉取时在后端读取，不经过前端；需先调用 unlock_secret_store。
+ */
+
+use crate::AppState;
+use crate::dict;
+use crate::ephemeral;
+use crate::events;
+use crate::secrets;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+
+const SYNC_FILE: &str = "sync.json";
+
+/// 同步凭据在密钥存储中的位置
+const SECRET_CLIENT: &str = "sync";
+const SECRET_KEY: &str = "token";
+
+/// 词典条目类型，value 为 { "freq": Option<usize>, "tag": Option<String> }
+pub const KIND_DICT_WORD: &str = "dict_word";
+
+/// 单个同步条目
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+pub struct SyncItem {
+    pub kind: String,
+    pub key: String,
+    pub value: serde_json::Value,
+    pub lamport: u64,
+    pub device: String,
+    pub deleted: bool,
+}
+
+impl SyncItem {
+    fn id(&self) -> String {
+        item_id(&self.kind, &self.key)
+    }
+
+    /// LWW 排序键
+    fn version(&self) -> (u64, &str) {
+        (self.lamport, self.device.as_str())
+    }
+}
+
+/// 冲突记录
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SyncConflict {
+    pub kind: String,
+    pub key: String,
+    pub local: SyncItem,
+    pub remote: SyncItem,
+    /// "local" 或 "remote"
+    pub winner: String,
+    pub at: u64,
+}
+
+/// 持久化的同步状态
+#[derive(Serialize, Deserialize, Default)]
+pub struct SyncState {
+    device: String,
+    clock: u64,
+    endpoint: Option<String>,
+    items: HashMap<String, SyncItem>,
+    pending: HashSet<String>,
+    remote_cursor: u64,
+    last_sync: Option<u64>,
+    conflicts: Vec<SyncConflict>,
+}
+
+#[derive(Serialize)]
+pub struct SyncStatus {
+    pub endpoint: Option<String>,
+    pub device: String,
+    pub pending: usize,
+    pub last_sync: Option<u64>,
+    pub conflicts: Vec<SyncConflict>,
+}
+
+#[derive(Serialize)]
+pub struct SyncPullResult {
+    pub applied: usize,
+    pub conflicts: usize,
+}
+
+#[derive(Serialize)]
+struct PushBody<'a> {
+    device: &'a str,
+    items: Vec<SyncItem>,
+}
+
+#[derive(Deserialize)]
+struct PullResponse {
+    cursor: u64,
+    items: Vec<SyncItem>,
+}
+
+fn item_id(kind: &str, key: &str) -> String {
+    format!("{}/{}", kind, key)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn sync_path(app: &AppHandle, state: &AppState) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| format!("path error: {}", e))?;
+    let dir = ephemeral::redirect(state, "sync", dir);
+    fs::create_dir_all(&dir).map_err(|e| format!("mkdir error: {}", e))?;
+    Ok(dir.join(SYNC_FILE))
+}
+
+fn load(app: &AppHandle, state: &AppState) -> Result<SyncState, String> {
+    let path = sync_path(app, state)?;
+    let mut sync: SyncState = match fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| format!("parse error: {}", e))?,
+        Err(_) => SyncState::default(),
+    };
+    if sync.device.is_empty() {
+        // 首次使用时生成设备 id，并立即保存，之后的调用都使用同一个 id
+        let mut hasher = Sha256::new();
+        hasher.update(host_name().as_bytes());
+        hasher.update(format!("{:?}", SystemTime::now()).as_bytes());
+        sync.device = format!("{:x}", hasher.finalize())[..16].to_string();
+        save(app, state, &sync)?;
+    }
+    Ok(sync)
+}
+
+fn save(app: &AppHandle, state: &AppState, sync: &SyncState) -> Result<(), String> {
+    let path = sync_path(app, state)?;
+    let bytes = serde_json::to_vec_pretty(sync).map_err(|e| format!("serialize error: {}", e))?;
+    fs::write(&path, bytes).map_err(|e| format!("write error: {}", e))
+}
+
+fn host_name() -> String {
+    use sysinfo::{System, SystemExt};
+    System::new().host_name().unwrap_or_default()
+}
+
+/// 串行化对同步文件的读改写（不能跨 await 持有）
+fn lock(state: &AppState) -> Result<std::sync::MutexGuard<'_, ()>, String> {
+    state
+        .sync_lock
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))
+}
+
+/// 从密钥存储读取同步凭据
+fn token(state: &AppState) -> Result<String, String> {
+    secrets::read(state, SECRET_CLIENT, SECRET_KEY)?
+        .ok_or_else(|| "sync token not configured; call sync_configure with a token".to_string())
+}
+
+fn endpoint(sync: &SyncState) -> Result<String, String> {
+    sync.endpoint
+        .clone()
+        .map(|e| e.trim_end_matches('/').to_string())
+        .ok_or_else(|| "sync endpoint not configured".to_string())
+}
+
+/// 把远端条目应用到本地（词典走用户词典流程，其他类型通过事件交给前端）
+fn apply_remote(app: &AppHandle, state: &AppState, item: &SyncItem) {
+    if item.kind == KIND_DICT_WORD {
+        let res = if item.deleted {
+            state
+                .user_words
+                .lock()
+                .map_err(|e| format!("lock error: {}", e))
+                .map(|mut words| {
+                    words.remove(&item.key);
+                })
+                .and_then(|_| dict::rebuild(state))
+        } else {
+            let freq = item
+                .value
+                .get("freq")
+                .and_then(|v| v.as_u64())
+                .map(|f| f as usize);
+            let tag = item.value.get("tag").and_then(|v| v.as_str());
+            dict::add_word(state, &item.key, freq, tag).map(|_| ())
+        };
+        if let Err(e) = res.and_then(|_| dict::persist(state)) {
+            eprintln!("[sync] apply dict word {} failed: {}", item.key, e);
+        }
+    }
+    if let Err(e) = events::emit_event(app, events::Event::SyncItemUpdated(item.clone())) {
+        eprintln!("[sync] emit error: {:?}", e);
+    }
+}
+
+/**
+ * 设置同步服务地址
+ * token: 同步凭据，传入时保存到密钥存储（需已调用 unlock_secret_store）；不传则保留原凭据
+ */
+#[tauri::command]
+pub fn sync_configure(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    endpoint: String,
+    token: Option<String>,
+) -> Result<(), String> {
+    if let Some(token) = token {
+        secrets::write(&state, SECRET_CLIENT, SECRET_KEY, &token)?;
+    }
+    let _lock = lock(&state)?;
+    let mut sync = load(&app, &state)?;
+    sync.endpoint = Some(endpoint);
+    save(&app, &state, &sync)
+}
+
+/**
+ * 记录一次本地修改（value 为 None 表示删除），等待下次 sync_push
+ */
+#[tauri::command]
+pub fn sync_record_change(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    kind: String,
+    key: String,
+    value: Option<serde_json::Value>,
+) -> Result<(), String> {
+    record_local_changes(&app, &state, [(kind, key, value)])
+}
+
+/// 记录一批本地修改 (kind, key, value) 并保存，等待下次 sync_push（用户词典等后端修改也经此记录）
+pub fn record_local_changes(
+    app: &AppHandle,
+    state: &AppState,
+    changes: impl IntoIterator<Item = (String, String, Option<serde_json::Value>)>,
+) -> Result<(), String> {
+    let _lock = lock(state)?;
+    let mut sync = load(app, state)?;
+    for (kind, key, value) in changes {
+        record_change(&mut sync, kind, key, value);
+    }
+    save(app, state, &sync)
+}
+
+pub fn record_change(
+    sync: &mut SyncState,
+    kind: String,
+    key: String,
+    value: Option<serde_json::Value>,
+) {
+    sync.clock += 1;
+    let item = SyncItem {
+        deleted: value.is_none(),
+        value: value.unwrap_or(serde_json::Value::Null),
+        kind,
+        key,
+        lamport: sync.clock,
+        device: sync.device.clone(),
+    };
+    let id = item.id();
+    sync.items.insert(id.clone(), item);
+    sync.pending.insert(id);
+}
+
+/**
+ * 推送本地待同步的修改（只推送变化的条目）
+ * 返回推送的条目数；凭据从密钥存储读取
+ */
+#[tauri::command]
+pub async fn sync_push(app: AppHandle, state: State<'_, AppState>) -> Result<usize, String> {
+    let token = token(&state)?;
+    let sync = {
+        let _lock = lock(&state)?;
+        load(&app, &state)?
+    };
+    let url = format!("{}/push", endpoint(&sync)?);
+    let items: Vec<SyncItem> = sync
+        .pending
+        .iter()
+        .filter_map(|id| sync.items.get(id).cloned())
+        .collect();
+    if items.is_empty() {
+        return Ok(0);
+    }
+
+    let body = serde_json::to_vec(&PushBody {
+        device: &sync.device,
+        items: items.clone(),
+    })
+    .map_err(|e| format!("serialize error: {}", e))?;
+
+    let resp = state
+        .http
+        .post(&url)
+        .bearer_auth(&token)
+        .header("Content-Type", "application/json")
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| format!("request error: {}", e))?;
+    if !resp.status().is_success() {
+        return Err(format!("push failed: {}", resp.status()));
+    }
+
+    // 请求期间可能有新的本地修改，只清除版本未变的条目
+    let _lock = lock(&state)?;
+    let mut sync = load(&app, &state)?;
+    for pushed in &items {
+        let id = pushed.id();
+        if sync.items.get(&id).map(|i| i.lamport) == Some(pushed.lamport) {
+            sync.pending.remove(&id);
+        }
+    }
+    sync.last_sync = Some(now_secs());
+    save(&app, &state, &sync)?;
+    Ok(items.len())
+}
+
+/**
+ * 拉取远端修改并按 LWW 合并；凭据从密钥存储读取
+ */
+#[tauri::command]
+pub async fn sync_pull(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<SyncPullResult, String> {
+    let token = token(&state)?;
+    let (url, since, device) = {
+        let _lock = lock(&state)?;
+        let sync = load(&app, &state)?;
+        (
+            format!("{}/pull", endpoint(&sync)?),
+            sync.remote_cursor.to_string(),
+            sync.device.clone(),
+        )
+    };
+
+    let resp = state
+        .http
+        .get(&url)
+        .query(&[("since", since.as_str()), ("device", device.as_str())])
+        .bearer_auth(&token)
+        .send()
+        .await
+        .map_err(|e| format!("request error: {}", e))?;
+    if !resp.status().is_success() {
+        return Err(format!("pull failed: {}", resp.status()));
+    }
+    let bytes = resp
+        .bytes()
+        .await
+        .map_err(|e| format!("bytes error: {}", e))?;
+    let remote: PullResponse =
+        serde_json::from_slice(&bytes).map_err(|e| format!("parse error: {}", e))?;
+
+    let _lock = lock(&state)?;
+    let mut sync = load(&app, &state)?;
+    let mut applied = 0;
+    let mut conflicts = 0;
+
+    for item in remote.items {
+        if item.device == sync.device {
+            continue;
+        }
+        // lamport 接收规则
+        sync.clock = sync.clock.max(item.lamport);
+        let id = item.id();
+
+        let remote_wins = match sync.items.get(&id) {
+            Some(local) => {
+                let wins = item.version() > local.version();
+                if sync.pending.contains(&id) {
+                    conflicts += 1;
+                    sync.conflicts.push(SyncConflict {
+                        kind: item.kind.clone(),
+                        key: item.key.clone(),
+                        local: local.clone(),
+                        remote: item.clone(),
+                        winner: if wins { "remote" } else { "local" }.to_string(),
+                        at: now_secs(),
+                    });
+                }
+                wins
+            }
+            None => true,
+        };
+
+        if remote_wins {
+            sync.pending.remove(&id);
+            sync.items.insert(id, item.clone());
+            apply_remote(&app, &state, &item);
+            applied += 1;
+        }
+    }
+
+    sync.remote_cursor = sync.remote_cursor.max(remote.cursor);
+    sync.last_sync = Some(now_secs());
+    save(&app, &state, &sync)?;
+
+    Ok(SyncPullResult { applied, conflicts })
+}
+
+/**
+ * 同步状态：待推送数量、上次成功同步时间、冲突日志
+ */
+#[tauri::command]
+pub fn sync_status(app: AppHandle, state: State<'_, AppState>) -> Result<SyncStatus, String> {
+    let _lock = lock(&state)?;
+    let sync = load(&app, &state)?;
+    Ok(SyncStatus {
+        endpoint: sync.endpoint,
+        device: sync.device,
+        pending: sync.pending.len(),
+        last_sync: sync.last_sync,
+        conflicts: sync.conflicts,
+    })
+}
+
+/**
+ * 清空冲突日志（UI 确认后调用）
+ */
+#[tauri::command]
+pub fn sync_clear_conflicts(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    let _lock = lock(&state)?;
+    let mut sync = load(&app, &state)?;
+    sync.conflicts.clear();
+    save(&app, &state, &sync)
+}
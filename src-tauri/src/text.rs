@@ -0,0 +1,459 @@
+/*!
+ * 基于 jieba 分词的文本分析（相似度、中英混合分词等）
+ */
+
+use crate::AppState;
+use crate::commands::SegmentOutput;
+use crate::text_util;
+use jieba_rs::{Jieba, KeywordExtract, KeywordExtractConfig, TextRank, TfIdf};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+use tauri::State;
+
+/// 文本分析使用的停用词：text_util::DEFAULT_STOP_WORDS 加上 jieba 内置的英文停用词
+fn stop_words() -> &'static HashSet<String> {
+    static STOP_WORDS: OnceLock<HashSet<String>> = OnceLock::new();
+    STOP_WORDS.get_or_init(|| {
+        let mut words: Vec<String> = text_util::DEFAULT_STOP_WORDS
+            .iter()
+            .map(|w| w.to_string())
+            .collect();
+        words.extend(jieba_rs::DEFAULT_STOP_WORDS.iter().cloned());
+        text_util::stop_word_set(&words)
+    })
+}
+
+/// 是否为纯空白或纯标点的词（不参与打分）
+pub fn is_noise(word: &str) -> bool {
+    word.chars()
+        .all(|c| c.is_whitespace() || c.is_ascii_punctuation() || is_cjk_punctuation(c))
+}
+
+fn is_cjk_punctuation(c: char) -> bool {
+    matches!(c, '\u{3000}'..='\u{303F}' | '\u{FF00}'..='\u{FF0F}' | '\u{FF1A}'..='\u{FF20}' | '…' | '—' | '“' | '”' | '‘' | '’')
+}
+
+/// 分词并过滤空白/标点，英文统一小写
+fn tokens(jieba: &Jieba, text: &str, filter_stop_words: bool) -> Vec<String> {
+    jieba
+        .cut(text, true)
+        .into_iter()
+        .filter(|w| !is_noise(w))
+        .filter(|w| !filter_stop_words || !text_util::is_stop_word(w, stop_words()))
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+fn term_freq(tokens: &[String]) -> HashMap<&str, f64> {
+    let mut tf = HashMap::new();
+    for t in tokens {
+        *tf.entry(t.as_str()).or_insert(0.0) += 1.0;
+    }
+    tf
+}
+
+fn cosine(a: &[String], b: &[String]) -> f64 {
+    let (ta, tb) = (term_freq(a), term_freq(b));
+    let dot: f64 = ta
+        .iter()
+        .filter_map(|(k, va)| tb.get(k).map(|vb| va * vb))
+        .sum();
+    let na = ta.values().map(|v| v * v).sum::<f64>().sqrt();
+    let nb = tb.values().map(|v| v * v).sum::<f64>().sqrt();
+    if na == 0.0 || nb == 0.0 {
+        0.0
+    } else {
+        (dot / (na * nb)).clamp(0.0, 1.0)
+    }
+}
+
+fn jaccard(a: &[String], b: &[String]) -> f64 {
+    let sa: HashSet<&str> = a.iter().map(|s| s.as_str()).collect();
+    let sb: HashSet<&str> = b.iter().map(|s| s.as_str()).collect();
+    let union = sa.union(&sb).count();
+    if union == 0 {
+        0.0
+    } else {
+        sa.intersection(&sb).count() as f64 / union as f64
+    }
+}
+
+fn score(method: &str, a: &[String], b: &[String]) -> Result<f64, String> {
+    match method {
+        "cosine" => Ok(cosine(a, b)),
+        "jaccard" => Ok(jaccard(a, b)),
+        other => Err(format!("unknown similarity method: {}", other)),
+    }
+}
+
+/**
+ * 文本相似度
+ * method: "cosine"（词频向量余弦）或 "jaccard"（词集合）
+ * filter_stop_words: 打分前过滤停用词（默认 true）
+ * 返回 [0, 1] 之间的分数
+ */
+#[tauri::command]
+pub fn text_similarity(
+    state: State<'_, AppState>,
+    a: String,
+    b: String,
+    method: String,
+    filter_stop_words: Option<bool>,
+) -> Result<f64, String> {
+    let filter = filter_stop_words.unwrap_or(true);
+    let jieba = state.jieba.read().map_err(|e| format!("lock error: {}", e))?;
+    let ta = tokens(&jieba, &a, filter);
+    let tb = tokens(&jieba, &b, filter);
+    score(&method, &ta, &tb)
+}
+
+/**
+ * 按与 query 的相似度对候选排序
+ * candidates: (id, 文本) 列表
+ * 返回得分最高的 top_k 个 (id, 分数)，按分数降序
+ */
+#[tauri::command]
+pub fn rank_by_similarity(
+    state: State<'_, AppState>,
+    query: String,
+    candidates: Vec<(String, String)>,
+    top_k: usize,
+    method: Option<String>,
+    filter_stop_words: Option<bool>,
+) -> Result<Vec<(String, f64)>, String> {
+    let method = method.unwrap_or_else(|| "cosine".to_string());
+    let filter = filter_stop_words.unwrap_or(true);
+    let jieba = state.jieba.read().map_err(|e| format!("lock error: {}", e))?;
+    let tq = tokens(&jieba, &query, filter);
+
+    let mut scored = candidates
+        .into_iter()
+        .map(|(id, text)| {
+            let tc = tokens(&jieba, &text, filter);
+            score(&method, &tq, &tc).map(|s| (id, s))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.truncate(top_k);
+    Ok(scored)
+}
+
+/// 是否按 CJK 文字处理（汉字、假名、谚文、全角标点）
+pub fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{4E00}'..='\u{9FFF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{2FA1F}'
+        | '\u{3000}'..='\u{30FF}'
+        | '\u{AC00}'..='\u{D7AF}'
+        | '\u{FF00}'..='\u{FFEF}')
+}
+
+/// URL / 邮箱末尾常见的句读符号不算在内
+fn trim_trailing_punct(chunk: &str) -> &str {
+    chunk.trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']', '}', '"', '\''])
+}
+
+fn is_url(chunk: &str) -> bool {
+    chunk.contains("://") || chunk.to_ascii_lowercase().starts_with("www.")
+}
+
+fn is_email(chunk: &str) -> bool {
+    let Some((local, domain)) = chunk.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && local
+            .chars()
+            .all(|c| c.is_alphanumeric() || "._%+-".contains(c))
+        && domain
+            .chars()
+            .all(|c| c.is_alphanumeric() || ".-".contains(c))
+}
+
+/// 非 CJK 片段按空白/标点切分；URL 与邮箱保持为一个词
+fn latin_tokens<'a>(run: &'a str, out: &mut Vec<&'a str>) {
+    for chunk in run.split_whitespace() {
+        let kept = trim_trailing_punct(chunk);
+        if !kept.is_empty() && (is_url(kept) || is_email(kept)) {
+            out.push(kept);
+            split_words(&chunk[kept.len()..], out);
+        } else {
+            split_words(chunk, out);
+        }
+    }
+}
+
+/// 字母数字连成一个词（允许词内的 ' 和数字间的 .），其他符号单独成词
+fn split_words<'a>(chunk: &'a str, out: &mut Vec<&'a str>) {
+    let chars: Vec<(usize, char)> = chunk.char_indices().collect();
+    let byte_at = |i: usize| chars.get(i).map_or(chunk.len(), |&(pos, _)| pos);
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        if chars[i].1.is_alphanumeric() || chars[i].1 == '_' {
+            i += 1;
+            while i < chars.len() {
+                let c = chars[i].1;
+                let joins = |prev: char| {
+                    let Some(&(_, next)) = chars.get(i + 1) else {
+                        return false;
+                    };
+                    (c == '\'' && prev.is_alphabetic() && next.is_alphabetic())
+                        || (c == '.' && prev.is_ascii_digit() && next.is_ascii_digit())
+                };
+                if c.is_alphanumeric() || c == '_' || joins(chars[i - 1].1) {
+                    i += 1;
+                } else {
+                    break;
+                }
+            }
+        } else {
+            i += 1;
+        }
+        out.push(&chunk[byte_at(start)..byte_at(i)]);
+    }
+}
+
+/**
+ * 中英混合分词：CJK 片段交给 jieba，其余按空白/标点规则切分，保持原文顺序
+ * 返回的偏移同 segment_text 的 with_offsets，为原文 UTF-8 字节偏移
+ */
+pub fn mixed_tokens(jieba: &Jieba, text: &str, hmm: bool) -> Vec<text_util::TokenWithOffset> {
+    let mut words: Vec<&str> = Vec::new();
+    let mut rest = text;
+    while let Some(first) = rest.chars().next() {
+        let cjk = is_cjk(first);
+        // 连续的 CJK / 非 CJK 字符为一段
+        let len = rest
+            .char_indices()
+            .find(|&(_, c)| is_cjk(c) != cjk)
+            .map_or(rest.len(), |(i, _)| i);
+        let (run, tail) = rest.split_at(len);
+        if cjk {
+            words.extend(jieba.cut(run, hmm));
+        } else {
+            latin_tokens(run, &mut words);
+        }
+        rest = tail;
+    }
+    // 所有词都是原文的切片，偏移由 token_offsets 按指针计算
+    text_util::token_offsets(text, &words)
+}
+
+/**
+ * 中英混合分词（适合夹杂英文、代码、URL 的文本）
+ * with_offsets: 同 segment_text，为 true 时返回 { word, start, end }（原文 UTF-8 字节偏移）
+ */
+#[tauri::command]
+pub fn segment_mixed(
+    state: State<'_, AppState>,
+    text: String,
+    with_offsets: Option<bool>,
+) -> Result<SegmentOutput, String> {
+    let jieba = state.jieba.read().map_err(|e| format!("lock error: {}", e))?;
+    let tokens = mixed_tokens(&jieba, &text, true);
+    if with_offsets.unwrap_or(false) {
+        return Ok(SegmentOutput::WithOffsets(tokens));
+    }
+    Ok(SegmentOutput::Words(
+        tokens.into_iter().map(|t| t.word).collect(),
+    ))
+}
+
+/// 关键词及其权重
+#[derive(Serialize, Clone)]
+pub struct KeywordResult {
+    pub word: String,
+    pub score: f64,
+}
+
+/// 关键词提取配置：在默认英文停用词基础上加入 text_util::DEFAULT_STOP_WORDS
+fn keyword_config() -> KeywordExtractConfig {
+    let mut builder = KeywordExtractConfig::builder();
+    for w in text_util::DEFAULT_STOP_WORDS {
+        builder.add_stop_word(w.to_string());
+    }
+    builder.build().unwrap_or_default()
+}
+
+/// TF-IDF 提取器（加载内置 IDF 词典开销较大，只初始化一次）
+fn tfidf() -> &'static TfIdf {
+    static TFIDF: OnceLock<TfIdf> = OnceLock::new();
+    TFIDF.get_or_init(|| {
+        let mut tfidf = TfIdf::default();
+        *tfidf.config_mut() = keyword_config();
+        tfidf
+    })
+}
+
+fn textrank() -> &'static TextRank {
+    static TEXTRANK: OnceLock<TextRank> = OnceLock::new();
+    TEXTRANK.get_or_init(|| TextRank::new(5, keyword_config()))
+}
+
+/// 使用指定提取器在共享的 jieba 实例上提取关键词
+fn extract_tags(
+    extractor: &impl KeywordExtract,
+    jieba: &Jieba,
+    text: &str,
+    top_k: usize,
+    allowed_pos: Vec<String>,
+) -> Vec<KeywordResult> {
+    extractor
+        .extract_keywords(jieba, text, top_k, allowed_pos)
+        .into_iter()
+        .map(|k| KeywordResult {
+            word: k.keyword,
+            score: k.weight,
+        })
+        .collect()
+}
+
+/**
+ * TF-IDF 关键词提取
+ * allowed_pos: 只保留指定词性（如 ["n", "v"]），为空不过滤
+ * 返回按权重降序的前 top_k 个关键词
+ */
+#[tauri::command]
+pub fn extract_keywords_tfidf(
+    state: State<'_, AppState>,
+    text: String,
+    top_k: usize,
+    allowed_pos: Option<Vec<String>>,
+) -> Vec<KeywordResult> {
+    let jieba = state.jieba.read().expect("RwLock poisoned");
+    extract_tags(tfidf(), &jieba, &text, top_k, allowed_pos.unwrap_or_default())
+}
+
+/**
+ * TextRank 关键词提取（参数同 extract_keywords_tfidf）
+ */
+#[tauri::command]
+pub fn extract_keywords_textrank(
+    state: State<'_, AppState>,
+    text: String,
+    top_k: usize,
+    allowed_pos: Option<Vec<String>>,
+) -> Vec<KeywordResult> {
+    let jieba = state.jieba.read().expect("RwLock poisoned");
+    extract_tags(textrank(), &jieba, &text, top_k, allowed_pos.unwrap_or_default())
+}
+
+/// 批量 TF-IDF 关键词提取，接受 (id, 文本) 元组，返回 (id, 关键词) 元组
+#[tauri::command]
+pub fn batch_extract_keywords_tfidf(
+    state: State<'_, AppState>,
+    inputs: Vec<(String, String)>,
+    top_k: usize,
+) -> Vec<(String, Vec<KeywordResult>)> {
+    let jieba = state.jieba.read().expect("RwLock poisoned");
+    inputs
+        .into_iter()
+        .map(|(id, text)| {
+            let keywords = extract_tags(tfidf(), &jieba, &text, top_k, Vec::new());
+            (id, keywords)
+        })
+        .collect()
+}
+
+/// 带词性的词（tag 为 jieba 词性标注，如 "n"、"v"、"nr"）
+#[derive(Serialize, Clone)]
+pub struct TaggedWord {
+    pub word: String,
+    pub tag: String,
+}
+
+fn tag_words(jieba: &Jieba, text: &str, hmm: bool) -> Vec<TaggedWord> {
+    if text.is_empty() {
+        return Vec::new();
+    }
+    jieba
+        .tag(text, hmm)
+        .into_iter()
+        .map(|t| TaggedWord {
+            word: t.word.to_string(),
+            tag: t.tag.to_string(),
+        })
+        .collect()
+}
+
+/**
+ * 分词并标注词性
+ * 中英文混排时英文词标为 "eng"，数字为 "m"，标点和空白为 "x"
+ * hmm: 是否识别词典外的新词，缺省为 true（新词没有词性，标为 "x"）
+ */
+#[tauri::command]
+pub fn tag_text(state: State<'_, AppState>, text: String, hmm: Option<bool>) -> Vec<TaggedWord> {
+    let jieba = state.jieba.read().expect("RwLock poisoned");
+    tag_words(&jieba, &text, hmm.unwrap_or(true))
+}
+
+/// 批量词性标注，接受 (id, 文本) 元组，返回 (id, 标注结果) 元组
+#[tauri::command]
+pub fn batch_tag_text(
+    state: State<'_, AppState>,
+    inputs: Vec<(String, String)>,
+    hmm: Option<bool>,
+) -> Vec<(String, Vec<TaggedWord>)> {
+    let hmm = hmm.unwrap_or(true);
+    let jieba = state.jieba.read().expect("RwLock poisoned");
+    inputs
+        .into_iter()
+        .map(|(id, text)| {
+            let tagged = tag_words(&jieba, &text, hmm);
+            (id, tagged)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_english_and_punctuation_in_chinese_text() {
+        let jieba = Jieba::new();
+        let tagged = tag_words(&jieba, "写了3个程序，hello world!", true);
+        let tag_of = |word: &str| {
+            tagged
+                .iter()
+                .find(|t| t.word == word)
+                .map(|t| t.tag.as_str())
+        };
+
+        assert_eq!(tag_of("程序"), Some("n"));
+        assert_eq!(tag_of("3"), Some("m"));
+        assert_eq!(tag_of("hello"), Some("eng"));
+        assert_eq!(tag_of("world"), Some("eng"));
+        assert_eq!(tag_of("，"), Some("x"));
+        assert_eq!(tag_of("!"), Some("x"));
+        assert!(tag_words(&jieba, "", true).is_empty());
+    }
+
+    #[test]
+    fn mixed_tokens_keep_urls_and_emails_with_byte_offsets() {
+        let jieba = Jieba::new();
+        let text = "请访问 https://example.com/a?b=1，或联系 dev@example.com. 版本 v2.5 发布";
+        let tokens = mixed_tokens(&jieba, text, true);
+        for t in &tokens {
+            assert_eq!(&text[t.start..t.end], t.word);
+        }
+
+        let find = |word: &str| tokens.iter().find(|t| t.word == word);
+        let url = find("https://example.com/a?b=1").expect("url token");
+        assert_eq!((url.start, url.end), (10, 35));
+        let email = find("dev@example.com").expect("email token");
+        assert_eq!(&text[email.end..email.end + 1], ".");
+        assert!(find("访问").is_some());
+        // 数字间的 . 不拆开
+        let version = find("v2.5").expect("version token");
+        assert_eq!(&text[version.start..version.end], "v2.5");
+    }
+}
@@ -0,0 +1,336 @@
+/*!
+ * 分词结果的通用处理（停用词过滤等）
+ */
+
+use jieba_rs::Jieba;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+/// 内置中文停用词表（虚词、代词、连词、语气词及常用标点）
+#[rustfmt::skip]
+pub static DEFAULT_STOP_WORDS: &[&str] = &[
+    "的", "了", "和", "是", "在", "就", "都", "而", "及", "与", "着", "或", "一个", "没有", "我们",
+    "你们", "他们", "她们", "它们", "这", "那", "之", "也", "把", "被", "让", "给", "对", "从",
+    "向", "于", "为", "以", "等", "吗", "呢", "吧", "啊", "呀", "哦", "嗯", "个", "很", "还", "又",
+    "但", "所以", "因为", "如果", "然后", "这个", "那个", "什么", "我", "你", "他", "她", "它",
+    "您", "咱", "咱们", "自己", "人家", "别人", "大家", "谁", "哪", "哪个", "哪些", "哪儿", "哪里",
+    "那儿", "那里", "这儿", "这里", "那些", "这些", "那么", "这么", "那样", "这样", "怎么", "怎样",
+    "怎么样", "为什么", "多少", "几", "啥", "某", "某个", "某些", "各", "各个", "各位", "各种",
+    "每", "每个", "每当", "其", "其他", "其它", "其中", "其余", "其实", "另", "另外", "另一方面",
+    "此", "此外", "此时", "此次", "彼", "彼此", "本", "本身", "该", "凡", "凡是", "诸位", "所有",
+    "一切", "一些", "一样", "一般", "一旦", "一直", "一边", "一面", "一起", "一方面", "一则",
+    "一来", "一何", "一再", "一定", "不", "不但", "不仅", "不光", "不过", "不管", "不论", "不如",
+    "不然", "不是", "不要", "不会", "不能", "不可", "不必", "不用", "不得", "不敢", "不怎么",
+    "不只", "不外乎", "不尽", "不单", "不比", "不至于", "不料", "没", "没用", "别", "别的", "别说",
+    "非", "非但", "非常", "非得", "莫", "莫若", "勿", "无", "无论", "无法", "否则", "甚至", "甚而",
+    "甚么", "尤其", "尤其是", "而且", "而是", "而已", "而言", "而后", "而外", "并", "并且", "并不",
+    "并非", "并没有", "况且", "何况", "何以", "何处", "何时", "何必", "加之", "加以", "假如",
+    "假若", "假使", "假设", "倘若", "倘使", "倘或", "要是", "要么", "要不", "要不然", "要不是",
+    "要么是", "只要", "只有", "只是", "只能", "只怕", "只限", "只当", "只消", "除", "除了", "除非",
+    "除此之外", "除外", "即", "即使", "即便", "即若", "即或", "既", "既然", "既是", "虽", "虽然",
+    "虽说", "虽则", "尽管", "固然", "然而", "但是", "可是", "可", "可以", "可能", "可见", "却",
+    "便", "便于", "于是", "于是乎", "因", "因此", "因而", "因之", "由", "由于", "由此", "由此可见",
+    "由是", "从而", "从此", "从来", "以便", "以免", "以及", "以至", "以至于", "以致", "以后",
+    "以前", "以上", "以下", "以外", "以来", "以为", "以故", "为了", "为此", "为何", "为止", "为着",
+    "对于", "对比", "关于", "至于", "至", "至今", "直到", "按", "按照", "依", "依照", "依据",
+    "根据", "据", "据此", "经", "经过", "通过", "随", "随着", "随后", "随时", "趁", "趁着", "当",
+    "当然", "当时", "当着", "朝", "朝着", "沿", "沿着", "鉴于", "基于", "针对", "将", "将要",
+    "将近", "连", "连同", "同", "同时", "同样", "及其", "及至", "与其", "与否", "与此同时", "或者",
+    "或是", "或则", "还是", "还有", "还要", "就是", "就是说", "就要", "就算", "哪怕", "纵然",
+    "纵使", "继而", "接着", "然则", "否", "是否", "是的", "是以", "啦", "嘛", "呗", "哇", "哈",
+    "哎", "唉", "喂", "哟", "嘿", "喔", "咦", "嗬", "嘻", "呵", "呕", "呜", "呸", "啐", "咳", "嗳",
+    "哼", "嗡", "呐", "咧", "罢了", "也罢", "也好", "也是", "的话", "似的", "一般来说", "来说",
+    "来讲", "说来", "看来", "例如", "比如", "比方", "譬如", "诸如", "好比", "如", "如此", "如同",
+    "如何", "如若", "如上", "如下", "如其", "如是", "像", "好像", "似乎", "仿佛", "总之",
+    "总的来说", "总而言之", "总是", "总得", "总算", "换句话说", "换言之", "也就是说", "的确",
+    "确实", "其次", "首先", "最后", "接下来", "再", "再者", "再说", "再则", "又及", "及时", "已",
+    "已经", "曾", "曾经", "刚", "刚才", "刚刚", "才", "正", "正在", "正如", "正是", "将会", "会",
+    "能", "能够", "应", "应该", "应当", "须", "必须", "必将", "必然", "得", "要", "敢", "肯", "愿",
+    "愿意", "上", "下", "中", "里", "内", "外", "前", "后", "左", "右", "间", "旁", "边", "之前",
+    "之后", "之间", "之中", "之内", "之外", "之上", "之下", "之类", "之所以", "之一", "等等",
+    "等到", "等于", "们", "者", "所", "所谓", "所在", "所以说", "所幸", "有", "有的", "有些",
+    "有关", "有时", "有着", "又是", "有点", "有所", "较", "较之", "比", "比较", "更", "更加", "最",
+    "最为", "太", "极", "极了", "极其", "颇", "挺", "十分", "相当", "稍", "稍微", "略", "略微",
+    "几乎", "差不多", "大约", "大概", "约", "左右", "恰恰", "恰好", "正好", "刚好", "仅", "仅仅",
+    "光", "单", "单单", "只", "就此", "向着", "往", "往往", "常", "常常", "经常", "时常", "偶尔",
+    "有时候", "一向", "向来", "历来", "素来", "本来", "原来", "后来", "起来", "下来", "上来",
+    "出来", "进来", "过来", "回来", "过去", "出去", "起", "过", "呃", "哈哈", "些", "一", "二",
+    "三", "四", "五", "六", "七", "八", "九", "十", "零", "百", "千", "万", "亿", "第一", "第二",
+    "个别", "各自", "本人", "本着", "自", "自从", "自各儿", "自个儿", "自身", "自家", "人们",
+    "谁人", "任何", "任凭", "不拘", "无论如何", "具体地说", "具体说来", "反之", "反而", "反过来",
+    "反过来说", "相对而言", "相反", "顺着", "顺", "诚然", "诚如", "何须", "何止", "何苦", "莫不",
+    "莫如", "毋宁", "宁可", "宁愿", "宁肯", "与其说", "不如说", "乃", "乃至", "亦", "亦即", "若",
+    "若是", "若非", "若果", "旦", "此间", "彼时", "此处", "遂", "故", "故此", "故而", "然", "焉",
+    "矣", "兮", "哉", "乎", "耶", "欤", "夫", "盖", "且", "况", "则", "即令", "便是", "罢", "来着",
+    "而况", "此地", "彼处", "那般", "这般", "这么些", "那么些", "这会儿", "那会儿", "这边", "那边",
+    "这时", "那时", "这次", "那次", "这种", "那种", "这么点儿", "那么点儿", "欸", "诶", "，", "。",
+    "、", "；", "：", "？", "！", "“", "”", "‘", "’", "（", "）", "《", "》", "【", "】", "…", "—",
+    "～", "·", "「", "」", "『", "』",
+];
+
+/// 构建停用词集合，统一小写以便不区分大小写匹配
+pub fn stop_word_set(words: &[String]) -> HashSet<String> {
+    words.iter().map(|w| w.to_lowercase()).collect()
+}
+
+/// 是否命中停用词集合（集合为 stop_word_set 的结果）
+pub fn is_stop_word(word: &str, stop_words: &HashSet<String>) -> bool {
+    !stop_words.is_empty() && stop_words.contains(&word.to_lowercase())
+}
+
+/// 从分词结果中去掉停用词（在分词之后过滤，不影响 jieba 的上下文）
+pub fn filter_stop_words(tokens: Vec<String>, stop_words: &HashSet<String>) -> Vec<String> {
+    if stop_words.is_empty() {
+        return tokens;
+    }
+    tokens
+        .into_iter()
+        .filter(|t| !is_stop_word(t, stop_words))
+        .collect()
+}
+
+/**
+ * 分词模式
+ * - Default: 精确模式，只用词典（jieba.cut(text, false)）
+ * - Exact: 精确模式并开启 HMM 新词发现（jieba.cut(text, true)，即旧参数 exact: true）
+ * - Search: 搜索引擎模式，在精确模式基础上再把长词切成子词（cut_for_search），适合建索引
+ * - Full: 全模式，列出所有可能成词的片段（cut_all）
+ *
+ * HMM 用于识别词典中没有的词（人名、新词等）：关闭时未登录词会被拆成单字。
+ * 每种模式有默认的 HMM 开关（见 default_hmm），可用 hmm 参数覆盖；
+ * 因此 Default 与 Exact 只差在 HMM 默认值，全模式不使用 HMM。
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum SegmentMode {
+    #[default]
+    Default,
+    Exact,
+    Search,
+    Full,
+}
+
+impl SegmentMode {
+    /// 解析前端传入的 mode；mode 缺省时兼容旧的 exact 参数，都缺省时为 Default
+    pub fn resolve(mode: Option<&str>, exact: Option<bool>) -> Result<Self, String> {
+        match (mode, exact) {
+            (Some(mode), _) => mode.parse(),
+            #[allow(deprecated)]
+            (None, Some(exact)) => Ok(SegmentMode::from_exact(exact)),
+            (None, None) => Ok(SegmentMode::Default),
+        }
+    }
+
+    /// 旧参数 exact 对应的模式（true 为 Exact，false 为 Default）
+    #[deprecated(note = "pass `mode` (\"exact\" / \"default\") instead of `exact`")]
+    pub fn from_exact(exact: bool) -> Self {
+        if exact {
+            SegmentMode::Exact
+        } else {
+            SegmentMode::Default
+        }
+    }
+
+    /// 未指定 hmm 时该模式是否开启 HMM
+    pub fn default_hmm(self) -> bool {
+        matches!(self, SegmentMode::Exact | SegmentMode::Search)
+    }
+}
+
+impl FromStr for SegmentMode {
+    type Err = String;
+
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "default" => Ok(SegmentMode::Default),
+            "exact" => Ok(SegmentMode::Exact),
+            "search" => Ok(SegmentMode::Search),
+            "full" => Ok(SegmentMode::Full),
+            other => Err(format!(
+                "unknown segment mode: {} (expected default, exact, search or full)",
+                other
+            )),
+        }
+    }
+}
+
+/// 按模式分词，返回原文切片；hmm 为 None 时使用模式的默认值
+pub fn cut<'a>(jieba: &Jieba, text: &'a str, mode: SegmentMode, hmm: Option<bool>) -> Vec<&'a str> {
+    let hmm = hmm.unwrap_or(mode.default_hmm());
+    match mode {
+        SegmentMode::Default | SegmentMode::Exact => jieba.cut(text, hmm),
+        SegmentMode::Search => jieba.cut_for_search(text, hmm),
+        SegmentMode::Full => jieba.cut_all(text),
+    }
+}
+
+/// 分词并过滤停用词
+pub fn segment(
+    jieba: &Jieba,
+    text: &str,
+    mode: SegmentMode,
+    stop_words: &HashSet<String>,
+) -> Vec<String> {
+    let words = cut(jieba, text, mode, None);
+    let words = words.into_iter().map(|s| s.to_string()).collect();
+    filter_stop_words(words, stop_words)
+}
+
+/**
+ * 批量分词；parallel 为 true 时用 rayon 在多个线程上处理
+ * Jieba 只读共享（调用方持有读锁），结果顺序与输入一致
+ */
+pub fn segment_batch(
+    jieba: &Jieba,
+    inputs: Vec<(String, String)>,
+    mode: SegmentMode,
+    stop_words: &HashSet<String>,
+    parallel: bool,
+) -> Vec<(String, Vec<String>)> {
+    let run = |(id, text): (String, String)| {
+        let words = segment(jieba, &text, mode, stop_words);
+        (id, words)
+    };
+    if parallel {
+        inputs.into_par_iter().map(run).collect()
+    } else {
+        inputs.into_iter().map(run).collect()
+    }
+}
+
+/// 带位置的词；start/end 为原字符串中的 UTF-8 字节偏移（左闭右开）
+#[derive(Serialize, Debug, PartialEq)]
+pub struct TokenWithOffset {
+    pub word: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/**
+ * 计算分词结果在原文中的字节偏移
+ * jieba 返回的是原文的切片，直接用指针差得到偏移；
+ * 不是原文切片的词（理论上不会出现）从上一个词之后向前查找
+ */
+pub fn token_offsets(text: &str, tokens: &[&str]) -> Vec<TokenWithOffset> {
+    let base = text.as_ptr() as usize;
+    let mut cursor = 0;
+    tokens
+        .iter()
+        .map(|token| {
+            let addr = token.as_ptr() as usize;
+            let start = if addr >= base && addr + token.len() <= base + text.len() {
+                addr - base
+            } else {
+                text[cursor..]
+                    .find(token)
+                    .map_or(cursor, |pos| cursor + pos)
+            };
+            let end = start + token.len();
+            cursor = end.min(text.len());
+            TokenWithOffset {
+                word: token.to_string(),
+                start,
+                end,
+            }
+        })
+        .collect()
+}
+
+/**
+ * 获取内置停用词表，前端可在此基础上增删后传给 segment_text
+ */
+#[tauri::command]
+pub fn get_default_stop_words() -> Vec<String> {
+    DEFAULT_STOP_WORDS.iter().map(|w| w.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets_are_utf8_byte_offsets() {
+        let jieba = jieba_rs::Jieba::new();
+        let text = "我爱北京天安门, hello 世界";
+        let tokens = jieba.cut(text, false);
+        let offsets = token_offsets(text, &tokens);
+
+        assert_eq!(offsets.len(), tokens.len());
+        for t in &offsets {
+            assert_eq!(&text[t.start..t.end], t.word);
+        }
+        // 字节偏移而不是字符偏移：每个汉字占 3 个字节
+        let tian = offsets.iter().find(|t| t.word == "天安门").unwrap();
+        assert_eq!((tian.start, tian.end), (12, 21));
+        let hello = offsets.iter().find(|t| t.word == "hello").unwrap();
+        assert_eq!((hello.start, hello.end), (23, 28));
+        let world = offsets.iter().find(|t| t.word == "世界").unwrap();
+        assert_eq!((world.start, world.end), (29, 35));
+    }
+
+    #[test]
+    fn parallel_batch_segment_test() {
+        let jieba = jieba_rs::Jieba::new();
+        let inputs: Vec<(String, String)> = (0..500)
+            .map(|i| {
+                let text = format!("第{}条消息：我们明天在北京开会，讨论新产品的发布计划。", i);
+                (i.to_string(), text)
+            })
+            .collect();
+        let none = HashSet::new();
+
+        let serial = segment_batch(&jieba, inputs.clone(), SegmentMode::Exact, &none, false);
+        for _ in 0..5 {
+            let parallel = segment_batch(&jieba, inputs.clone(), SegmentMode::Exact, &none, true);
+            assert_eq!(parallel, serial);
+        }
+        let ids: Vec<&str> = serial.iter().map(|(id, _)| id.as_str()).collect();
+        let expected: Vec<String> = (0..500).map(|i| i.to_string()).collect();
+        assert_eq!(ids, expected);
+    }
+
+    #[test]
+    fn offsets_fall_back_to_search_for_foreign_slices() {
+        let text = "你好世界";
+        let owned = ["你好".to_string(), "世界".to_string()];
+        let tokens: Vec<&str> = owned.iter().map(|s| s.as_str()).collect();
+        let offsets = token_offsets(text, &tokens);
+        assert_eq!((offsets[0].start, offsets[0].end), (0, 6));
+        assert_eq!((offsets[1].start, offsets[1].end), (6, 12));
+    }
+
+    #[test]
+    fn hmm_recognizes_out_of_dictionary_words() {
+        let jieba = jieba_rs::Jieba::new();
+        // "杭研" 不在内置词典中
+        let text = "他来到了网易杭研大厦";
+
+        let with_hmm = cut(&jieba, text, SegmentMode::Default, Some(true));
+        assert_eq!(with_hmm, vec!["他", "来到", "了", "网易", "杭研", "大厦"]);
+
+        let without_hmm = cut(&jieba, text, SegmentMode::Default, Some(false));
+        assert_eq!(
+            without_hmm,
+            vec!["他", "来到", "了", "网易", "杭", "研", "大厦"]
+        );
+
+        // 模式默认值：Exact 开启 HMM，Default 关闭
+        assert_eq!(cut(&jieba, text, SegmentMode::Exact, None), with_hmm);
+        assert_eq!(cut(&jieba, text, SegmentMode::Default, None), without_hmm);
+    }
+
+    #[test]
+    fn search_mode_splits_long_words() {
+        let jieba = jieba_rs::Jieba::new();
+        let text = "小明硕士毕业于中国科学院计算所";
+
+        let exact = cut(&jieba, text, SegmentMode::Exact, None);
+        let search = cut(&jieba, text, SegmentMode::Search, None);
+        assert!(exact.contains(&"中国科学院"));
+        // 搜索模式保留长词，同时补充其中的子词
+        assert!(search.contains(&"中国科学院"));
+        assert!(search.contains(&"科学"));
+        assert!(search.len() > exact.len());
+    }
+}
@@ -0,0 +1,251 @@
+/*!
+ * 会话级撤销
+ *
+ * 破坏性命令（清空缓存、删除历史等）不直接删除文件，而是先移动到
+ * app 数据目录下的暂存区并返回 undo_token；undo_operation 可恢复。
+ * 暂存区在应用退出或超过 UNDO_TTL 后清除。
+ * 超过 MAX_STAGE_BYTES 的操作不暂存，需要调用方传 confirm: true 才会直接删除。
+ */
+
+use crate::AppState;
+use crate::ephemeral;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+
+const UNDO_TTL: Duration = Duration::from_secs(10 * 60);
+pub const MAX_STAGE_BYTES: u64 = 1024 * 1024 * 1024;
+
+static TOKEN_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// 撤销时在文件移回后执行，用于恢复清单、索引等元数据
+pub type RestoreHook = Box<dyn FnOnce(&AppState) -> Result<(), String> + Send>;
+
+/// 一次可撤销的操作：(原路径, 暂存路径)
+pub struct UndoEntry {
+    pub label: String,
+    pub created: Instant,
+    pub staging_dir: PathBuf,
+    pub items: Vec<(PathBuf, PathBuf)>,
+    pub restore: Option<RestoreHook>,
+}
+
+/// 破坏性操作的结果
+#[derive(Serialize, Default)]
+pub struct DestructiveResult {
+    pub removed: usize,
+    pub bytes: u64,
+    /// 可撤销时返回
+    pub undo_token: Option<String>,
+    /// 操作过大无法暂存，需要 confirm: true 才会执行
+    pub requires_confirm: bool,
+    pub message: Option<String>,
+}
+
+fn staging_root(app: &AppHandle, state: &AppState) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| format!("path error: {}", e))?
+        .join("undo-staging");
+    Ok(ephemeral::redirect(state, "undo-staging", dir))
+}
+
+fn new_token() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("{:x}-{}", nanos, TOKEN_SEQ.fetch_add(1, Ordering::Relaxed))
+}
+
+/// 路径占用的字节数（文件或目录）
+pub fn path_size(path: &Path) -> u64 {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|e| path_size(&e.path())).sum())
+        .unwrap_or(0)
+}
+
+/// 移动文件/目录，跨设备时回退为复制 + 删除
+fn move_path(from: &Path, to: &Path) -> std::io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    copy_recursive(from, to)?;
+    remove_path(from)
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> std::io::Result<()> {
+    if fs::metadata(from)?.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        fs::copy(from, to).map(|_| ())
+    }
+}
+
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// 清除过期的暂存
+pub fn purge_expired(state: &AppState) {
+    let expired: Vec<UndoEntry> = match state.undo_journal.lock() {
+        Ok(mut journal) => {
+            let keys: Vec<String> = journal
+                .iter()
+                .filter(|(_, e)| e.created.elapsed() >= UNDO_TTL)
+                .map(|(k, _)| k.clone())
+                .collect();
+            keys.iter().filter_map(|k| journal.remove(k)).collect()
+        }
+        Err(_) => return,
+    };
+    for entry in expired {
+        let _ = fs::remove_dir_all(&entry.staging_dir);
+    }
+}
+
+/// 退出时清除所有暂存
+pub fn purge_all(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    if let Ok(mut journal) = state.undo_journal.lock() {
+        journal.clear();
+    }
+    if let Ok(root) = staging_root(app, &state) {
+        let _ = fs::remove_dir_all(root);
+    }
+}
+
+/**
+ * 删除一组路径（可撤销），撤销时在文件移回后再执行 restore（如把记录加回清单 / 索引）
+ * 总大小超过 MAX_STAGE_BYTES 时：confirm 为 false 只返回 requires_confirm，
+ * confirm 为 true 直接永久删除
+ * 返回 requires_confirm 时文件没有被删除，调用方需放回事先移除的元数据
+ */
+pub fn stage_with_restore(
+    app: &AppHandle,
+    state: &AppState,
+    label: &str,
+    paths: Vec<PathBuf>,
+    confirm: bool,
+    restore: Option<RestoreHook>,
+) -> Result<DestructiveResult, String> {
+    purge_expired(state);
+
+    let paths: Vec<PathBuf> = paths.into_iter().filter(|p| p.exists()).collect();
+    let bytes: u64 = paths.iter().map(|p| path_size(p)).sum();
+
+    if bytes > MAX_STAGE_BYTES {
+        if !confirm {
+            return Ok(DestructiveResult {
+                bytes,
+                requires_confirm: true,
+                message: Some(format!(
+                    "{} bytes is too large to stage for undo; pass confirm: true to delete permanently",
+                    bytes
+                )),
+                ..Default::default()
+            });
+        }
+        let mut removed = 0;
+        for p in &paths {
+            match remove_path(p) {
+                Ok(_) => removed += 1,
+                Err(e) => eprintln!("[undo] remove {} failed: {}", p.display(), e),
+            }
+        }
+        return Ok(DestructiveResult {
+            removed,
+            bytes,
+            message: Some("deleted permanently (not undoable)".into()),
+            ..Default::default()
+        });
+    }
+
+    let token = new_token();
+    let staging_dir = staging_root(app, state)?.join(&token);
+    let mut items = Vec::with_capacity(paths.len());
+    for (i, p) in paths.into_iter().enumerate() {
+        let staged = staging_dir.join(i.to_string());
+        match move_path(&p, &staged) {
+            Ok(_) => items.push((p, staged)),
+            Err(e) => eprintln!("[undo] stage {} failed: {}", p.display(), e),
+        }
+    }
+
+    let removed = items.len();
+    state
+        .undo_journal
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?
+        .insert(
+            token.clone(),
+            UndoEntry {
+                label: label.to_string(),
+                created: Instant::now(),
+                staging_dir,
+                items,
+                restore,
+            },
+        );
+
+    Ok(DestructiveResult {
+        removed,
+        bytes,
+        undo_token: Some(token),
+        ..Default::default()
+    })
+}
+
+/**
+ * 撤销一次破坏性操作，返回恢复的条目数
+ */
+#[tauri::command]
+pub fn undo_operation(state: State<'_, AppState>, token: String) -> Result<usize, String> {
+    purge_expired(&state);
+    let entry = state
+        .undo_journal
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?
+        .remove(&token)
+        .ok_or_else(|| "undo token not found or expired".to_string())?;
+
+    let mut restored = 0;
+    for (original, staged) in &entry.items {
+        if original.exists() {
+            eprintln!("[undo] {} already exists, skipped", original.display());
+            continue;
+        }
+        match move_path(staged, original) {
+            Ok(_) => restored += 1,
+            Err(e) => eprintln!("[undo] restore {} failed: {}", original.display(), e),
+        }
+    }
+    let _ = fs::remove_dir_all(&entry.staging_dir);
+    if let Some(restore) = entry.restore {
+        restore(state.inner())?;
+    }
+    println!("[undo] restored {} item(s) of '{}'", restored, entry.label);
+    Ok(restored)
+}
@@ -1,104 +1,267 @@
-// // 异常类型与序列化
-// use serde::{Serialize, ser::Serializer};
-// use thiserror::Error;
-
-// // Tauri 核心
-// use tauri::{command, Runtime, Window};
-
-// // HTTP 客户端
-// use reqwest::Client;
-
-// // 异步文件与 I/O
-// use tokio::{
-//     fs::{metadata, OpenOptions},
-//     io::{AsyncWriteExt, AsyncSeekExt, BufWriter},
-// };
-// use std::io::SeekFrom;
-
-// // 流式读取响应体
-// use futures_util::TryStreamExt;
-// use tauri::Emitter;
-
-// // 标准集合
-// use std::collections::HashMap;
-
-// type Result<T> = std::result::Result<T, Error>;
-
-// impl Serialize for Error {
-//     fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
-//     where
-//         S: Serializer,
-//     {
-//         serializer.serialize_str(self.to_string().as_ref())
-//     }
-// }
-
-// #[derive(Clone, Serialize)]
-// struct ProgressPayload {
-//     id: u32,
-//     progress: u64,
-//     total: u64,
-// }
-
-// #[tauri::command]
-// pub async fn file_download<R: Runtime>(
-//     window: Window<R>,
-//     id: u32,
-//     url: &str,
-//     file_path: &str,
-//     mut headers: HashMap<String, String>,
-// ) -> Result<u32> {
-//     let client = reqwest::Client::new();
-
-//     // Step 1: 获取本地文件大小（断点续传偏移量）
-//     let offset = match tokio::fs::metadata(file_path).await {
-//         Ok(meta) => meta.len(),
-//         Err(_) => 0,
-//     };
-
-//     if offset > 0 {
-//         headers.insert("Range".to_string(), format!("bytes={}-", offset));
-//     }
-
-//     let mut request = client.get(url);
-//     for (key, value) in headers {
-//         request = request.header(&key, value);
-//     }
-
-//     let response = request.send().await?;
-
-//     let total = match response.content_length() {
-//         Some(len) => offset + len,
-//         None => offset,
-//     };
-
-//     // Step 2: 打开文件为“可写追加”模式 + seek 到 offset
-//     let f = tokio::fs::OpenOptions::new()
-//         .create(true)
-//         .write(true)
-//         .open(file_path)
-//         .await?;
-//     let mut file = BufWriter::new(f);
-//     file.seek(SeekFrom::Start(offset)).await?;
-
-//     // Step 3: 下载并写入剩余部分
-//     let mut stream = response.bytes_stream();
-//     let mut downloaded = offset;
-
-//     while let Some(chunk) = stream.try_next().await? {
-//         file.write_all(&chunk).await?;
-//         downloaded += chunk.len() as u64;
-
-//         let _ = window.emit(
-//             "download://progress",
-//             ProgressPayload {
-//                 id,
-//                 progress: downloaded,
-//                 total,
-//             },
-//         );
-//     }
-
-//     file.flush().await?;
-//     Ok(id)
-// }
+/*!
+ * 文件下载
+ *
+ * 先写入 <dest>.part，完成后重命名为目标文件。
+ * .part 已存在且服务端支持 Range（Accept-Ranges: bytes）时断点续传，否则从头下载。
+ * 重定向由 reqwest 自动跟随（最多 MAX_REDIRECTS 次）。
+ */
+
+use crate::AppState;
+use crate::ephemeral;
+use crate::events;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_http::reqwest;
+use tokio::fs::OpenOptions;
+use tokio::io::{AsyncWriteExt, BufWriter};
+
+/// 共享 HTTP 客户端的连接超时
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// 两次读取之间的最长间隔（不限制大文件下载的总时长）
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+/// 最多跟随的重定向次数
+const MAX_REDIRECTS: usize = 10;
+
+/// 构建共享的 HTTP 客户端（AppState.http），各网络命令复用其连接池
+pub fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .read_timeout(READ_TIMEOUT)
+        .user_agent(concat!(
+            env!("CARGO_PKG_NAME"),
+            "/",
+            env!("CARGO_PKG_VERSION")
+        ))
+        .redirect(reqwest::redirect::Policy::limited(MAX_REDIRECTS))
+        .build()
+        .expect("could not build http client")
+}
+
+/// "download:progress" 事件负载；total 未知时为 None，speed 为字节/秒
+#[derive(Serialize, Clone, JsonSchema)]
+pub struct DownloadProgress {
+    pub download_id: String,
+    pub bytes: u64,
+    pub total: Option<u64>,
+    pub speed: u64,
+}
+
+fn part_path(dest: &str) -> PathBuf {
+    let mut p = PathBuf::from(dest).into_os_string();
+    p.push(".part");
+    PathBuf::from(p)
+}
+
+/// 服务端是否支持按字节范围请求
+async fn accepts_ranges(
+    client: &reqwest::Client,
+    url: &str,
+    headers: &HashMap<String, String>,
+) -> bool {
+    let mut request = client.head(url);
+    for (key, value) in headers {
+        request = request.header(key, value);
+    }
+    match request.send().await {
+        Ok(resp) => resp
+            .headers()
+            .get("accept-ranges")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.eq_ignore_ascii_case("bytes")),
+        Err(_) => false,
+    }
+}
+
+async fn download(
+    app: &AppHandle,
+    cancel: &AtomicBool,
+    url: &str,
+    dest_path: &str,
+    headers: HashMap<String, String>,
+    download_id: &str,
+) -> Result<String, String> {
+    let part = part_path(dest_path);
+    if let Some(parent) = part.parent().filter(|p| !p.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("create directory {} failed: {}", parent.display(), e))?;
+    }
+
+    let client = app.state::<AppState>().http.clone();
+    let existing = tokio::fs::metadata(&part)
+        .await
+        .map(|m| m.len())
+        .unwrap_or(0);
+    let offset = if existing > 0 && accepts_ranges(&client, url, &headers).await {
+        existing
+    } else {
+        0
+    };
+
+    let mut request = client.get(url);
+    for (key, value) in &headers {
+        request = request.header(key, value);
+    }
+    if offset > 0 {
+        request = request.header("Range", format!("bytes={}-", offset));
+    }
+    let mut resp = request
+        .send()
+        .await
+        .map_err(|e| format!("request failed: {}", e))?;
+    if !resp.status().is_success() {
+        return Err(format!("download failed: {}", resp.status()));
+    }
+
+    // 服务端忽略 Range 返回 200 时从头写入
+    let resumed = offset > 0 && resp.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+    let start = if resumed { offset } else { 0 };
+    let total = resp.content_length().map(|len| start + len);
+
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(&part)
+        .await
+        .map_err(|e| format!("open {} failed: {}", part.display(), e))?;
+    let mut writer = BufWriter::new(file);
+
+    let mut downloaded = start;
+    let mut window_bytes = 0u64;
+    let mut window_start = Instant::now();
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            // 保留 .part 以便下次续传
+            let _ = writer.flush().await;
+            return Err("download cancelled".into());
+        }
+        let Some(chunk) = resp
+            .chunk()
+            .await
+            .map_err(|e| format!("read response failed: {}", e))?
+        else {
+            break;
+        };
+        writer
+            .write_all(&chunk)
+            .await
+            .map_err(|e| format!("write {} failed: {}", part.display(), e))?;
+        downloaded += chunk.len() as u64;
+        window_bytes += chunk.len() as u64;
+
+        let elapsed = window_start.elapsed();
+        if elapsed >= Duration::from_millis(200) {
+            let speed = (window_bytes as f64 / elapsed.as_secs_f64()) as u64;
+            let _ = events::emit_event(
+                app,
+                events::Event::DownloadProgress(DownloadProgress {
+                    download_id: download_id.to_string(),
+                    bytes: downloaded,
+                    total,
+                    speed,
+                }),
+            );
+            window_bytes = 0;
+            window_start = Instant::now();
+        }
+    }
+    writer
+        .flush()
+        .await
+        .map_err(|e| format!("write {} failed: {}", part.display(), e))?;
+    drop(writer);
+
+    if let Some(total) = total.filter(|t| downloaded < *t) {
+        return Err(format!(
+            "download incomplete: {} of {} bytes",
+            downloaded, total
+        ));
+    }
+    tokio::fs::rename(&part, dest_path)
+        .await
+        .map_err(|e| format!("rename to {} failed: {}", dest_path, e))?;
+
+    let _ = events::emit_event(
+        app,
+        events::Event::DownloadProgress(DownloadProgress {
+            download_id: download_id.to_string(),
+            bytes: downloaded,
+            total: Some(downloaded),
+            speed: 0,
+        }),
+    );
+    Ok(std::fs::canonicalize(dest_path)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| dest_path.to_string()))
+}
+
+/**
+ * 下载文件到 dest_path，返回最终路径
+ * 下载过程中通过 "download:progress" 事件上报进度；可用 cancel_download(download_id) 取消，
+ * 取消后保留 .part 文件，再次调用相同参数可断点续传
+ */
+#[tauri::command]
+pub async fn file_download(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    url: String,
+    dest_path: String,
+    headers: Option<HashMap<String, String>>,
+    download_id: String,
+    allow_persistent: Option<bool>,
+) -> Result<String, String> {
+    ephemeral::check_persist(&state, allow_persistent.unwrap_or(false))?;
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    {
+        let mut downloads = state
+            .downloads
+            .lock()
+            .map_err(|e| format!("lock error: {}", e))?;
+        if downloads.contains_key(&download_id) {
+            return Err(format!("download {} is already running", download_id));
+        }
+        downloads.insert(download_id.clone(), cancel.clone());
+    }
+
+    let result = download(
+        &app,
+        &cancel,
+        &url,
+        &dest_path,
+        headers.unwrap_or_default(),
+        &download_id,
+    )
+    .await;
+
+    if let Ok(mut downloads) = app.state::<AppState>().downloads.lock() {
+        downloads.remove(&download_id);
+    }
+    result
+}
+
+/**
+ * 取消下载，返回是否找到该下载任务
+ */
+#[tauri::command]
+pub fn cancel_download(state: State<'_, AppState>, download_id: String) -> Result<bool, String> {
+    let downloads = state
+        .downloads
+        .lock()
+        .map_err(|e| format!("lock error: {}", e))?;
+    match downloads.get(&download_id) {
+        Some(flag) => {
+            flag.store(true, Ordering::Relaxed);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
@@ -0,0 +1,709 @@
+/*!
+ * 枚举屏幕上可见的顶层窗口（截图遮罩层 Tab 切换窗口用）
+ *
+ * 坐标约定与截图一致：逻辑坐标即 screenshots 的 DisplayInfo 坐标系，
+ * 物理坐标 = 逻辑坐标 × 窗口中心所在屏幕的 scale_factor（与 capture_area 拼接时的缩放一致）。
+ * Windows 原生返回物理像素，macOS / X11 原生返回逻辑坐标，在这里统一换算。
+ * 本进程的窗口（截图遮罩等）不会出现在结果中。
+ */
+
+use screenshots::{DisplayInfo, Screen};
+use serde::Serialize;
+use sysinfo::{Pid, PidExt, ProcessExt, System, SystemExt};
+
+/// 平台 API 返回的窗口信息（原生坐标）
+pub struct NativeWindow {
+    pub id: u64,
+    pub title: String,
+    pub pid: u32,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// 可见窗口；z_order 为 0 表示最上层
+#[derive(Serialize, Clone, Debug)]
+pub struct WindowInfo {
+    pub id: u64,
+    pub title: String,
+    pub pid: u32,
+    pub process_name: String,
+    pub z_order: usize,
+    /// 窗口中心所在屏幕
+    pub screen_id: u32,
+    pub scale_factor: f32,
+    pub logical: Rect,
+    pub physical: Rect,
+}
+
+fn scale_rect(r: Rect, factor: f32) -> Rect {
+    Rect {
+        x: (r.x as f32 * factor).round() as i32,
+        y: (r.y as f32 * factor).round() as i32,
+        width: (r.width as f32 * factor).round() as u32,
+        height: (r.height as f32 * factor).round() as u32,
+    }
+}
+
+fn contains(d: &DisplayInfo, x: i32, y: i32, factor: f32) -> bool {
+    let r = scale_rect(
+        Rect {
+            x: d.x,
+            y: d.y,
+            width: d.width,
+            height: d.height,
+        },
+        factor,
+    );
+    x >= r.x && y >= r.y && x < r.x + r.width as i32 && y < r.y + r.height as i32
+}
+
+/// 原生坐标换算为 (屏幕, 逻辑坐标, 物理坐标)；窗口中心不在任何屏幕内时返回 None
+fn locate(displays: &[DisplayInfo], w: &NativeWindow) -> Option<(DisplayInfo, Rect, Rect)> {
+    let native = Rect {
+        x: w.x,
+        y: w.y,
+        width: w.width,
+        height: w.height,
+    };
+    let (cx, cy) = (w.x + (w.width / 2) as i32, w.y + (w.height / 2) as i32);
+    let d = *displays.iter().find(|d| {
+        let factor = if platform::NATIVE_IS_PHYSICAL {
+            d.scale_factor
+        } else {
+            1.0
+        };
+        contains(d, cx, cy, factor)
+    })?;
+    if platform::NATIVE_IS_PHYSICAL {
+        Some((d, scale_rect(native, 1.0 / d.scale_factor), native))
+    } else {
+        Some((d, native, scale_rect(native, d.scale_factor)))
+    }
+}
+
+/**
+ * 当前可见的顶层窗口，按 z 序从上到下排列
+ * 排除最小化、隐藏、零尺寸以及本进程的窗口
+ */
+pub fn visible_windows() -> Result<Vec<WindowInfo>, String> {
+    let displays: Vec<DisplayInfo> = Screen::all()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|s| s.display_info)
+        .collect();
+    let own_pid = std::process::id();
+    let mut sys = System::new();
+
+    let mut windows = Vec::new();
+    for w in platform::list()? {
+        if w.pid == own_pid || w.width == 0 || w.height == 0 {
+            continue;
+        }
+        let Some((d, logical, physical)) = locate(&displays, &w) else {
+            continue;
+        };
+        let pid = Pid::from_u32(w.pid);
+        let process_name = if sys.refresh_process(pid) {
+            sys.process(pid)
+                .map(|p| p.name().to_string())
+                .unwrap_or_default()
+        } else {
+            String::new()
+        };
+        windows.push(WindowInfo {
+            id: w.id,
+            title: w.title,
+            pid: w.pid,
+            process_name,
+            z_order: windows.len(),
+            screen_id: d.id,
+            scale_factor: d.scale_factor,
+            logical,
+            physical,
+        });
+    }
+    Ok(windows)
+}
+
+/**
+ * 窗口客户区（不含标题栏和边框）的逻辑坐标，平台无法获取时返回 None
+ * Windows 用 GetClientRect + ClientToScreen；X11 枚举到的就是客户窗口本身；
+ * macOS 无法读取其他应用窗口的内容视图，按标准标题栏高度从窗口顶部扣除（与屏幕等大的全屏窗口不扣除）
+ */
+pub fn client_area(window: &WindowInfo) -> Option<Rect> {
+    if platform::NATIVE_IS_PHYSICAL {
+        let client = platform::client_rect(window.id, window.physical)?;
+        Some(scale_rect(client, 1.0 / window.scale_factor))
+    } else {
+        platform::client_rect(window.id, window.logical)
+    }
+}
+
+/**
+ * 获取屏幕上可见的顶层窗口矩形（含标题、进程、z 序），同时给出逻辑和物理坐标
+ */
+#[tauri::command]
+pub async fn get_visible_window_rects() -> Result<Vec<WindowInfo>, String> {
+    tokio::task::spawn_blocking(visible_windows)
+        .await
+        .map_err(|e| format!("enumerate task error: {}", e))?
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::{NativeWindow, Rect};
+    use std::ffi::c_void;
+
+    pub const NATIVE_IS_PHYSICAL: bool = true;
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct RECT {
+        left: i32,
+        top: i32,
+        right: i32,
+        bottom: i32,
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct POINT {
+        x: i32,
+        y: i32,
+    }
+
+    #[link(name = "user32")]
+    unsafe extern "system" {
+        fn EnumWindows(
+            callback: unsafe extern "system" fn(isize, isize) -> i32,
+            lparam: isize,
+        ) -> i32;
+        fn IsWindowVisible(hwnd: isize) -> i32;
+        fn IsIconic(hwnd: isize) -> i32;
+        fn GetWindowTextW(hwnd: isize, buf: *mut u16, max: i32) -> i32;
+        fn GetWindowThreadProcessId(hwnd: isize, pid: *mut u32) -> u32;
+        fn GetWindowRect(hwnd: isize, rect: *mut RECT) -> i32;
+        fn GetClientRect(hwnd: isize, rect: *mut RECT) -> i32;
+        fn ClientToScreen(hwnd: isize, point: *mut POINT) -> i32;
+    }
+
+    #[link(name = "dwmapi")]
+    unsafe extern "system" {
+        fn DwmGetWindowAttribute(hwnd: isize, attr: u32, value: *mut c_void, size: u32) -> i32;
+    }
+
+    const DWMWA_EXTENDED_FRAME_BOUNDS: u32 = 9;
+    const DWMWA_CLOAKED: u32 = 14;
+
+    unsafe extern "system" fn collect(hwnd: isize, lparam: isize) -> i32 {
+        let handles = unsafe { &mut *(lparam as *mut Vec<isize>) };
+        handles.push(hwnd);
+        1
+    }
+
+    fn is_cloaked(hwnd: isize) -> bool {
+        let mut cloaked: u32 = 0;
+        let hr = unsafe {
+            DwmGetWindowAttribute(
+                hwnd,
+                DWMWA_CLOAKED,
+                &mut cloaked as *mut u32 as *mut c_void,
+                4,
+            )
+        };
+        hr == 0 && cloaked != 0
+    }
+
+    /// 优先取不含阴影的可见边框
+    fn bounds(hwnd: isize) -> RECT {
+        let mut rect = RECT::default();
+        let hr = unsafe {
+            DwmGetWindowAttribute(
+                hwnd,
+                DWMWA_EXTENDED_FRAME_BOUNDS,
+                &mut rect as *mut RECT as *mut c_void,
+                std::mem::size_of::<RECT>() as u32,
+            )
+        };
+        if hr != 0 {
+            unsafe { GetWindowRect(hwnd, &mut rect) };
+        }
+        rect
+    }
+
+    fn title(hwnd: isize) -> String {
+        let mut buf = [0u16; 512];
+        let len = unsafe { GetWindowTextW(hwnd, buf.as_mut_ptr(), buf.len() as i32) };
+        String::from_utf16_lossy(&buf[..len.max(0) as usize])
+    }
+
+    /// EnumWindows 按 z 序从上到下返回
+    pub fn list() -> Result<Vec<NativeWindow>, String> {
+        let mut handles: Vec<isize> = Vec::new();
+        unsafe { EnumWindows(collect, &mut handles as *mut Vec<isize> as isize) };
+
+        let mut windows = Vec::new();
+        for hwnd in handles {
+            if unsafe { IsWindowVisible(hwnd) == 0 || IsIconic(hwnd) != 0 } || is_cloaked(hwnd) {
+                continue;
+            }
+            let title = title(hwnd);
+            if title.is_empty() {
+                continue;
+            }
+            let mut pid = 0u32;
+            unsafe { GetWindowThreadProcessId(hwnd, &mut pid) };
+            let r = bounds(hwnd);
+            windows.push(NativeWindow {
+                id: hwnd as u64,
+                title,
+                pid,
+                x: r.left,
+                y: r.top,
+                width: (r.right - r.left).max(0) as u32,
+                height: (r.bottom - r.top).max(0) as u32,
+            });
+        }
+        Ok(windows)
+    }
+
+    /// 客户区的屏幕坐标（物理像素）
+    pub fn client_rect(id: u64, _frame: Rect) -> Option<Rect> {
+        let hwnd = id as isize;
+        let mut rect = RECT::default();
+        let mut origin = POINT::default();
+        unsafe {
+            if GetClientRect(hwnd, &mut rect) == 0 || ClientToScreen(hwnd, &mut origin) == 0 {
+                return None;
+            }
+        }
+        Some(Rect {
+            x: origin.x,
+            y: origin.y,
+            width: (rect.right - rect.left).max(0) as u32,
+            height: (rect.bottom - rect.top).max(0) as u32,
+        })
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{NativeWindow, Rect};
+    use screenshots::Screen;
+    use std::ffi::{CStr, c_char, c_void};
+    use std::sync::OnceLock;
+
+    pub const NATIVE_IS_PHYSICAL: bool = false;
+
+    type CFTypeRef = *const c_void;
+
+    #[repr(C)]
+    #[derive(Default, Clone, Copy)]
+    struct CGRect {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    }
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    unsafe extern "C" {
+        fn CGWindowListCopyWindowInfo(option: u32, relative_to: u32) -> CFTypeRef;
+        fn CGRectMakeWithDictionaryRepresentation(dict: CFTypeRef, rect: *mut CGRect) -> bool;
+        static kCGWindowNumber: CFTypeRef;
+        static kCGWindowOwnerPID: CFTypeRef;
+        static kCGWindowOwnerName: CFTypeRef;
+        static kCGWindowName: CFTypeRef;
+        static kCGWindowBounds: CFTypeRef;
+        static kCGWindowLayer: CFTypeRef;
+    }
+
+    #[link(name = "objc")]
+    unsafe extern "C" {
+        fn objc_getClass(name: *const c_char) -> *mut c_void;
+        fn sel_registerName(name: *const c_char) -> *const c_void;
+        #[cfg(target_arch = "x86_64")]
+        fn objc_msgSend_stret();
+        #[cfg(not(target_arch = "x86_64"))]
+        fn objc_msgSend();
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    unsafe extern "C" {
+        fn CFArrayGetCount(array: CFTypeRef) -> isize;
+        fn CFArrayGetValueAtIndex(array: CFTypeRef, index: isize) -> CFTypeRef;
+        fn CFDictionaryGetValue(dict: CFTypeRef, key: CFTypeRef) -> CFTypeRef;
+        fn CFNumberGetValue(number: CFTypeRef, number_type: isize, value: *mut c_void) -> bool;
+        fn CFStringGetCString(
+            string: CFTypeRef,
+            buf: *mut c_char,
+            size: isize,
+            encoding: u32,
+        ) -> bool;
+        fn CFRelease(cf: CFTypeRef);
+    }
+
+    const ON_SCREEN_ONLY: u32 = 1;
+    const EXCLUDE_DESKTOP_ELEMENTS: u32 = 16;
+    const CF_NUMBER_SINT64: isize = 4;
+    const CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+    const NS_WINDOW_STYLE_MASK_TITLED: usize = 1;
+
+    unsafe fn number(dict: CFTypeRef, key: CFTypeRef) -> Option<i64> {
+        let value = unsafe { CFDictionaryGetValue(dict, key) };
+        if value.is_null() {
+            return None;
+        }
+        let mut out: i64 = 0;
+        unsafe { CFNumberGetValue(value, CF_NUMBER_SINT64, &mut out as *mut i64 as *mut c_void) }
+            .then_some(out)
+    }
+
+    unsafe fn string(dict: CFTypeRef, key: CFTypeRef) -> Option<String> {
+        let value = unsafe { CFDictionaryGetValue(dict, key) };
+        if value.is_null() {
+            return None;
+        }
+        let mut buf = [0 as c_char; 1024];
+        let ok = unsafe {
+            CFStringGetCString(
+                value,
+                buf.as_mut_ptr(),
+                buf.len() as isize,
+                CF_STRING_ENCODING_UTF8,
+            )
+        };
+        ok.then(|| {
+            unsafe { CStr::from_ptr(buf.as_ptr()) }
+                .to_string_lossy()
+                .into_owned()
+        })
+    }
+
+    /// CGWindowList 按 z 序从前到后返回；坐标为全局点坐标（逻辑坐标）
+    pub fn list() -> Result<Vec<NativeWindow>, String> {
+        let mut windows = Vec::new();
+        unsafe {
+            let array = CGWindowListCopyWindowInfo(ON_SCREEN_ONLY | EXCLUDE_DESKTOP_ELEMENTS, 0);
+            if array.is_null() {
+                return Err("CGWindowListCopyWindowInfo failed".into());
+            }
+            for i in 0..CFArrayGetCount(array) {
+                let dict = CFArrayGetValueAtIndex(array, i);
+                // 只保留普通应用窗口（菜单栏、Dock 等 layer 不为 0）
+                if number(dict, kCGWindowLayer) != Some(0) {
+                    continue;
+                }
+                let bounds = CFDictionaryGetValue(dict, kCGWindowBounds);
+                let mut rect = CGRect::default();
+                if bounds.is_null() || !CGRectMakeWithDictionaryRepresentation(bounds, &mut rect) {
+                    continue;
+                }
+                let owner = string(dict, kCGWindowOwnerName).unwrap_or_default();
+                // 没有屏幕录制权限时窗口标题为空，用应用名代替
+                let title = string(dict, kCGWindowName)
+                    .filter(|t| !t.is_empty())
+                    .unwrap_or(owner);
+                windows.push(NativeWindow {
+                    id: number(dict, kCGWindowNumber).unwrap_or(0) as u64,
+                    title,
+                    pid: number(dict, kCGWindowOwnerPID).unwrap_or(0) as u32,
+                    x: rect.x.round() as i32,
+                    y: rect.y.round() as i32,
+                    width: rect.width.round() as u32,
+                    height: rect.height.round() as u32,
+                });
+            }
+            CFRelease(array);
+        }
+        Ok(windows)
+    }
+
+    /// +[NSWindow contentRectForFrameRect:styleMask:]，只做几何计算，不需要窗口实例
+    fn content_rect_for_frame(frame: CGRect, style_mask: usize) -> CGRect {
+        unsafe {
+            let class = objc_getClass(c"NSWindow".as_ptr());
+            let sel = sel_registerName(c"contentRectForFrameRect:styleMask:".as_ptr());
+            // x86_64 上返回结构体需要用 objc_msgSend_stret
+            #[cfg(target_arch = "x86_64")]
+            {
+                let send: unsafe extern "C" fn(
+                    *mut CGRect,
+                    *mut c_void,
+                    *const c_void,
+                    CGRect,
+                    usize,
+                ) = std::mem::transmute(objc_msgSend_stret as unsafe extern "C" fn());
+                let mut content = CGRect::default();
+                send(&mut content, class, sel, frame, style_mask);
+                content
+            }
+            #[cfg(not(target_arch = "x86_64"))]
+            {
+                let send: unsafe extern "C" fn(
+                    *mut c_void,
+                    *const c_void,
+                    CGRect,
+                    usize,
+                ) -> CGRect = std::mem::transmute(objc_msgSend as unsafe extern "C" fn());
+                send(class, sel, frame, style_mask)
+            }
+        }
+    }
+
+    /// 标准窗口标题栏的高度（点）
+    fn title_bar_height() -> f64 {
+        static HEIGHT: OnceLock<f64> = OnceLock::new();
+        *HEIGHT.get_or_init(|| {
+            let frame = CGRect {
+                x: 0.0,
+                y: 0.0,
+                width: 1000.0,
+                height: 1000.0,
+            };
+            let content = content_rect_for_frame(frame, NS_WINDOW_STYLE_MASK_TITLED);
+            (frame.height - content.height).max(0.0)
+        })
+    }
+
+    /// 内容区域的全局点坐标：窗口矩形去掉顶部标题栏
+    pub fn client_rect(_id: u64, frame: Rect) -> Option<Rect> {
+        let fullscreen = Screen::all().ok()?.iter().any(|s| {
+            let d = s.display_info;
+            d.x == frame.x && d.y == frame.y && d.width == frame.width && d.height == frame.height
+        });
+        let title_bar = if fullscreen {
+            0
+        } else {
+            title_bar_height().round() as u32
+        };
+        if frame.height <= title_bar {
+            return None;
+        }
+        Some(Rect {
+            x: frame.x,
+            y: frame.y + title_bar as i32,
+            width: frame.width,
+            height: frame.height - title_bar,
+        })
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{NativeWindow, Rect};
+    use std::ffi::CString;
+    use std::os::raw::{c_char, c_int, c_long, c_uchar, c_ulong, c_void};
+
+    pub const NATIVE_IS_PHYSICAL: bool = false;
+
+    #[repr(C)]
+    struct XWindowAttributes {
+        x: c_int,
+        y: c_int,
+        width: c_int,
+        height: c_int,
+        border_width: c_int,
+        depth: c_int,
+        visual: *mut c_void,
+        root: c_ulong,
+        class: c_int,
+        bit_gravity: c_int,
+        win_gravity: c_int,
+        backing_store: c_int,
+        backing_planes: c_ulong,
+        backing_pixel: c_ulong,
+        save_under: c_int,
+        colormap: c_ulong,
+        map_installed: c_int,
+        map_state: c_int,
+        all_event_masks: c_long,
+        your_event_mask: c_long,
+        do_not_propagate_mask: c_long,
+        override_redirect: c_int,
+        screen: *mut c_void,
+    }
+
+    #[link(name = "X11")]
+    unsafe extern "C" {
+        fn XOpenDisplay(name: *const c_char) -> *mut c_void;
+        fn XCloseDisplay(display: *mut c_void) -> c_int;
+        fn XDefaultRootWindow(display: *mut c_void) -> c_ulong;
+        fn XInternAtom(display: *mut c_void, name: *const c_char, only_if_exists: c_int)
+        -> c_ulong;
+        fn XGetWindowProperty(
+            display: *mut c_void,
+            window: c_ulong,
+            property: c_ulong,
+            long_offset: c_long,
+            long_length: c_long,
+            delete: c_int,
+            req_type: c_ulong,
+            actual_type: *mut c_ulong,
+            actual_format: *mut c_int,
+            nitems: *mut c_ulong,
+            bytes_after: *mut c_ulong,
+            prop: *mut *mut c_uchar,
+        ) -> c_int;
+        fn XGetWindowAttributes(
+            display: *mut c_void,
+            window: c_ulong,
+            attrs: *mut XWindowAttributes,
+        ) -> c_int;
+        fn XTranslateCoordinates(
+            display: *mut c_void,
+            src: c_ulong,
+            dest: c_ulong,
+            src_x: c_int,
+            src_y: c_int,
+            dest_x: *mut c_int,
+            dest_y: *mut c_int,
+            child: *mut c_ulong,
+        ) -> c_int;
+        fn XFree(data: *mut c_void) -> c_int;
+    }
+
+    const ANY_PROPERTY_TYPE: c_ulong = 0;
+    const IS_VIEWABLE: c_int = 2;
+
+    unsafe fn atom(display: *mut c_void, name: &str) -> c_ulong {
+        let name = CString::new(name).unwrap_or_default();
+        unsafe { XInternAtom(display, name.as_ptr(), 0) }
+    }
+
+    /// 读取窗口属性，返回 (格式, 元素个数, 原始数据)
+    unsafe fn property(
+        display: *mut c_void,
+        window: c_ulong,
+        property: c_ulong,
+    ) -> Option<(c_int, usize, Vec<u8>)> {
+        let (mut actual_type, mut format, mut nitems, mut after) = (0, 0, 0, 0);
+        let mut data: *mut c_uchar = std::ptr::null_mut();
+        let status = unsafe {
+            XGetWindowProperty(
+                display,
+                window,
+                property,
+                0,
+                4096,
+                0,
+                ANY_PROPERTY_TYPE,
+                &mut actual_type,
+                &mut format,
+                &mut nitems,
+                &mut after,
+                &mut data,
+            )
+        };
+        if status != 0 || data.is_null() {
+            return None;
+        }
+        // 格式为 32 时每个元素在内存中占一个 long
+        let unit = match format {
+            8 => 1,
+            16 => 2,
+            32 => std::mem::size_of::<c_long>(),
+            _ => 0,
+        };
+        let bytes = unsafe { std::slice::from_raw_parts(data, nitems as usize * unit) }.to_vec();
+        unsafe { XFree(data as *mut c_void) };
+        Some((format, nitems as usize, bytes))
+    }
+
+    unsafe fn longs(display: *mut c_void, window: c_ulong, prop: c_ulong) -> Vec<c_ulong> {
+        match unsafe { property(display, window, prop) } {
+            Some((32, _, bytes)) => bytes
+                .chunks_exact(std::mem::size_of::<c_ulong>())
+                .map(|c| c_ulong::from_ne_bytes(c.try_into().unwrap_or_default()))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    unsafe fn text(display: *mut c_void, window: c_ulong, prop: c_ulong) -> Option<String> {
+        match unsafe { property(display, window, prop) } {
+            Some((8, _, bytes)) => Some(String::from_utf8_lossy(&bytes).into_owned()),
+            _ => None,
+        }
+    }
+
+    /// _NET_CLIENT_LIST_STACKING 为从下到上的顺序，这里反转为从上到下
+    pub fn list() -> Result<Vec<NativeWindow>, String> {
+        if std::env::var_os("DISPLAY").is_none() {
+            return Err("window enumeration requires X11 (Wayland without XWayland?)".into());
+        }
+        unsafe {
+            let display = XOpenDisplay(std::ptr::null());
+            if display.is_null() {
+                return Err("failed to open X11 display".into());
+            }
+            let root = XDefaultRootWindow(display);
+            let stacking = atom(display, "_NET_CLIENT_LIST_STACKING");
+            let net_wm_name = atom(display, "_NET_WM_NAME");
+            let wm_name = atom(display, "WM_NAME");
+            let net_wm_pid = atom(display, "_NET_WM_PID");
+
+            let mut windows = Vec::new();
+            for window in longs(display, root, stacking).into_iter().rev() {
+                let mut attrs: XWindowAttributes = std::mem::zeroed();
+                if XGetWindowAttributes(display, window, &mut attrs) == 0
+                    || attrs.map_state != IS_VIEWABLE
+                {
+                    continue;
+                }
+                let (mut x, mut y, mut child) = (0, 0, 0);
+                XTranslateCoordinates(display, window, root, 0, 0, &mut x, &mut y, &mut child);
+                let title = text(display, window, net_wm_name)
+                    .or_else(|| text(display, window, wm_name))
+                    .unwrap_or_default();
+                let pid = longs(display, window, net_wm_pid)
+                    .first()
+                    .copied()
+                    .unwrap_or(0);
+                // c_ulong 在 32 位平台上是 u32
+                #[allow(clippy::unnecessary_cast)]
+                windows.push(NativeWindow {
+                    id: window as u64,
+                    title,
+                    pid: pid as u32,
+                    x,
+                    y,
+                    width: attrs.width.max(0) as u32,
+                    height: attrs.height.max(0) as u32,
+                });
+            }
+            XCloseDisplay(display);
+            Ok(windows)
+        }
+    }
+
+    /// _NET_CLIENT_LIST_STACKING 中的是客户窗口，几何信息已不含窗口管理器的装饰
+    pub fn client_rect(_id: u64, frame: Rect) -> Option<Rect> {
+        Some(frame)
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+mod platform {
+    use super::{NativeWindow, Rect};
+
+    pub const NATIVE_IS_PHYSICAL: bool = false;
+
+    pub fn list() -> Result<Vec<NativeWindow>, String> {
+        Err("window enumeration is not supported on this platform".into())
+    }
+
+    pub fn client_rect(_id: u64, _frame: Rect) -> Option<Rect> {
+        None
+    }
+}
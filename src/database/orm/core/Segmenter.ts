@@ -23,7 +23,7 @@ class Segmenter {
       // 将 Record 转换为 Tauri 需要的元组数组
       const inputArray: [string, string][] = Object.entries(inputs).map(([id, text]) => [id, text]);
       // 调用 Tauri 命令进行批量分词
-      const result: [string, string[]][] = await invoke("batch_segment_text", { inputs: inputArray, exact: true });
+      const result: [string, string[]][] = await invoke("batch_segment_text", { inputs: inputArray, mode: "exact" });
       // 将结果转换回 Record 格式
       return Object.fromEntries(result);
     } catch (error) {
@@ -40,7 +40,7 @@ class Segmenter {
   async segment(text: string): Promise<string[]> {
     try {
       // 调用 Tauri 命令进行分词
-      const result: string[] = await invoke("segment_text", { text, exact: true });
+      const result: string[] = await invoke("segment_text", { text, mode: "exact" });
       return result;
     } catch (error) {
       log.prettyError("database", "Error during segmentation:", error);